@@ -13,7 +13,7 @@ use curl::easy::Easy;
 use flate2::bufread::GzDecoder;
 use tar::Archive;
 
-const VERSION: &'static str = "1.7.1";
+const VERSION: &'static str = "1.17.0";
 
 fn main() {
     env_logger::init();
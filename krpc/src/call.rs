@@ -5,6 +5,8 @@ use std::time::Instant;
 
 use prost::Message;
 
+use RequestId;
+
 /// A remote procedure call.
 ///
 /// `Call` describes a remote procedure call: the remote service, the method, the required feature
@@ -19,6 +21,7 @@ where
     pub(crate) method: &'static str,
     pub(crate) required_feature_flags: &'static [u32],
     pub(crate) deadline: Instant,
+    pub(crate) request_id: Option<RequestId>,
     pub request: Arc<Req>,
     _marker: PhantomData<Resp>,
 }
@@ -40,6 +43,7 @@ where
             method,
             required_feature_flags: &[],
             deadline,
+            request_id: None,
             request,
             _marker: PhantomData::default(),
         }
@@ -80,6 +84,20 @@ where
         self.required_feature_flags = required_feature_flags;
         self
     }
+
+    /// Returns the call's request ID, if set.
+    pub fn request_id(&self) -> Option<&RequestId> {
+        self.request_id.as_ref()
+    }
+
+    /// Sets the request ID sent in the header of the call.
+    ///
+    /// The request ID identifies the call to the server across retries, since clones of the call
+    /// share the ID. If not set, no request ID is sent with the call.
+    pub fn set_request_id(&mut self, request_id: RequestId) -> &mut Call<Req, Resp> {
+        self.request_id = Some(request_id);
+        self
+    }
 }
 
 impl<Req, Resp> fmt::Debug for Call<Req, Resp>
@@ -94,6 +112,9 @@ where
         if !self.required_feature_flags.is_empty() {
             dbg.field("required_feature_flags", &self.required_feature_flags);
         }
+        if let Some(ref request_id) = self.request_id {
+            dbg.field("request_id", request_id);
+        }
         dbg.field("deadline", &self.deadline);
         dbg.finish()
     }
@@ -110,6 +131,7 @@ where
             method: self.method,
             required_feature_flags: self.required_feature_flags,
             deadline: self.deadline,
+            request_id: self.request_id.clone(),
             request: self.request.clone(),
             _marker: PhantomData::default(),
         }
@@ -1,6 +1,7 @@
 use std::cmp;
 use std::fmt;
 use std::io;
+use std::net::SocketAddr;
 use std::time::Instant;
 
 use fnv::FnvHashMap;
@@ -47,6 +48,11 @@ impl Connection {
         }
     }
 
+    /// Returns the address of the remote server.
+    pub fn addr(&self) -> &SocketAddr {
+        self.transport.addr()
+    }
+
     /// This is a total hack to allow Proxy to reach in and grab the RPCs.
     /// Would probably be better to return an `impl Iterator<Item=Rpc>`
     pub fn in_flight_rpcs(&mut self) -> &mut FnvHashMap<i32, Rpc> {
@@ -124,6 +130,7 @@ impl Connection {
             rpc.service,
             rpc.method,
             rpc.required_feature_flags,
+            rpc.request_id.as_ref(),
             &*rpc.request,
             Some(rpc.deadline - now),
         );
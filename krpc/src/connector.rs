@@ -16,10 +16,24 @@ use HostPort;
 use Options;
 
 type SocketAddrs = vec::IntoIter<SocketAddr>;
+pub(crate) type Resolve = Box<Future<Item = SocketAddrs, Error = Error> + Send + 'static>;
+type ResolveHost = Box<Future<Item = (String, SocketAddrs), Error = Error> + Send + 'static>;
+
+/// Resolves the hostport to socket addresses on a background thread.
+pub(crate) fn resolve(hostport: HostPort) -> Resolve {
+    // TODO(tokio-rs/tokio#432): use tokio_threadpool::blocking.
+    let (send, recv) = futures::sync::oneshot::channel();
+    ::std::thread::spawn(move || {
+        // TODO: add hostport context to error.
+        // The receiver may be dropped if the resolution is no longer needed.
+        let _ = send.send(hostport.to_socket_addrs().map_err(Error::from));
+    });
+    Box::new(recv.map_err(|_| -> Error { unreachable!() }).flatten())
+}
 
 pub(crate) struct Connector {
     options: Options,
-    resolving: FuturesUnordered<Box<Future<Item = SocketAddrs, Error = Error> + Send + 'static>>,
+    resolving: FuturesUnordered<ResolveHost>,
     connecting: FuturesUnordered<TransportNew>,
     negotiating: FuturesUnordered<Negotiator>,
     errors: Vec<Error>,
@@ -27,8 +41,7 @@ pub(crate) struct Connector {
 
 impl Connector {
     pub fn connect(hostports: &[HostPort], options: Options) -> Connector {
-        let mut resolving =
-            FuturesUnordered::<Box<Future<Item = _, Error = _> + Send + 'static>>::new();
+        let mut resolving = FuturesUnordered::new();
         let mut connecting = FuturesUnordered::new();
         let negotiating = FuturesUnordered::new();
         let errors = Vec::new();
@@ -38,22 +51,17 @@ impl Connector {
             if let Ok(addr) = IpAddr::from_str(&hostport.host()) {
                 connecting.push(Transport::connect(
                     SocketAddr::new(addr, hostport.port()),
+                    hostport.host().to_owned(),
                     options.clone(),
                 ));
 
-            // Otherwise resolve the hostport.
+            // Otherwise resolve the hostport. Hostnames are resolved anew for every connection
+            // attempt, so reconnecting after a failure picks up changed DNS records.
             } else {
-                let hostport: HostPort = hostport.clone();
-                // TODO(tokio-rs/tokio#432): use tokio_threadpool::blocking.
-                let (send, recv) = futures::sync::oneshot::channel();
-                ::std::thread::spawn(move || {
-                    // TODO: add hostport context to error.
-                    send.send(hostport.to_socket_addrs().map_err(Error::from))
-                        .unwrap()
-                });
+                let host = hostport.host().to_owned();
                 resolving.push(Box::new(
-                    recv.map_err(|_| -> Error { unreachable!() }).flatten(),
-                ))
+                    resolve(hostport.clone()).map(move |addrs| (host, addrs)),
+                ));
             }
         }
 
@@ -75,9 +83,10 @@ impl Future for Connector {
         // Poll the resolving futures.
         loop {
             match self.resolving.poll() {
-                Ok(Async::Ready(Some(addrs))) => {
+                Ok(Async::Ready(Some((host, addrs)))) => {
                     for addr in addrs {
-                        let transport = Transport::connect(addr, self.options.clone());
+                        let transport =
+                            Transport::connect(addr, host.clone(), self.options.clone());
                         self.connecting.push(transport);
                     }
                 }
@@ -123,7 +132,7 @@ impl Future for Connector {
         // If all futures resulted in an error, return the error.
         if self.resolving.is_empty() && self.connecting.is_empty() && self.negotiating.is_empty() {
             if self.errors.len() == 1 {
-                return Err(self.errors.pop().unwrap());
+                return Err(self.errors[0].clone());
             } else {
                 return Err(io::Error::new(
                     io::ErrorKind::ConnectionRefused,
@@ -138,3 +147,35 @@ impl Future for Connector {
         Ok(Async::NotReady)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::TcpListener;
+
+    use futures::future;
+    use tokio::runtime::current_thread::Runtime;
+
+    use super::*;
+
+    #[test]
+    fn connect_tries_each_address() {
+        // Find a port which nothing is listening on.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let hostport = HostPort::new("localhost".to_string(), port);
+        let num_addrs = hostport.to_socket_addrs().unwrap().count();
+        assert!(num_addrs > 0);
+
+        let mut runtime = Runtime::new().unwrap();
+        let mut connector = Connector::connect(&[hostport], Options::default());
+        let result = runtime.block_on(future::poll_fn(|| connector.poll()));
+        assert!(result.is_err());
+
+        // Every resolved address was attempted, and failed.
+        assert_eq!(num_addrs, connector.errors.len());
+    }
+}
@@ -26,6 +26,13 @@ pub enum Error {
 
     /// Negotiation with the remote server failed.
     Negotiation(String),
+
+    /// The proxy was shut down before the RPC was sent.
+    Shutdown,
+
+    /// The RPC was dropped by its caller before it completed, for instance because it lost a
+    /// speculative race. Only reported to `Metrics::on_rpc_complete`.
+    Cancelled,
 }
 
 impl Error {
@@ -36,6 +43,8 @@ impl Error {
             Error::Serialization(_) => true,
             Error::TimedOut => false,
             Error::Negotiation(_) => true,
+            Error::Shutdown => true,
+            Error::Cancelled => false,
         }
     }
 }
@@ -54,6 +63,8 @@ impl Clone for Error {
             Error::Serialization(ref error) => Error::Serialization(error.clone()),
             Error::TimedOut => Error::TimedOut,
             Error::Negotiation(ref error) => Error::Negotiation(error.clone()),
+            Error::Shutdown => Error::Shutdown,
+            Error::Cancelled => Error::Cancelled,
         }
     }
 }
@@ -66,6 +77,8 @@ impl error::Error for Error {
             Error::Serialization(ref error) => error,
             Error::TimedOut => "RPC timed out",
             Error::Negotiation(ref error) => error,
+            Error::Shutdown => "proxy shut down",
+            Error::Cancelled => "RPC cancelled",
         }
     }
 
@@ -108,6 +121,8 @@ impl fmt::Display for Error {
             Error::Serialization(ref error) => f.write_str(error),
             Error::TimedOut => f.write_str("timed out"),
             Error::Negotiation(ref error) => f.write_str(error),
+            Error::Shutdown => f.write_str("proxy shut down"),
+            Error::Cancelled => f.write_str("RPC cancelled"),
         }
     }
 }
@@ -0,0 +1,402 @@
+//! A minimal binding to the system GSSAPI library, implementing the client side of the SASL
+//! GSSAPI mechanism (RFC 4752) with Kerberos credentials from the ambient ticket cache.
+
+#![allow(non_camel_case_types)]
+
+use std::fmt;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::slice;
+
+type OM_uint32 = u32;
+type gss_name_t = *mut c_void;
+type gss_ctx_id_t = *mut c_void;
+type gss_cred_id_t = *mut c_void;
+type gss_OID = *mut gss_OID_desc;
+type gss_OID_set = *mut c_void;
+
+#[repr(C)]
+struct gss_OID_desc {
+    length: OM_uint32,
+    elements: *mut c_void,
+}
+
+#[repr(C)]
+struct gss_buffer_desc {
+    length: usize,
+    value: *mut c_void,
+}
+
+impl gss_buffer_desc {
+    fn empty() -> gss_buffer_desc {
+        gss_buffer_desc {
+            length: 0,
+            value: ptr::null_mut(),
+        }
+    }
+
+    fn from_slice(slice: &[u8]) -> gss_buffer_desc {
+        gss_buffer_desc {
+            length: slice.len(),
+            value: slice.as_ptr() as *mut c_void,
+        }
+    }
+}
+
+const GSS_S_COMPLETE: OM_uint32 = 0;
+const GSS_S_CONTINUE_NEEDED: OM_uint32 = 1;
+const GSS_C_GSS_CODE: c_int = 1;
+const GSS_C_MECH_CODE: c_int = 2;
+const GSS_C_INITIATE: c_int = 1;
+const GSS_C_INDEFINITE: OM_uint32 = 0xffff_ffff;
+const GSS_C_MUTUAL_FLAG: OM_uint32 = 2;
+const GSS_C_SEQUENCE_FLAG: OM_uint32 = 8;
+
+/// The DER encoding of `GSS_C_NT_HOSTBASED_SERVICE` (1.2.840.113554.1.2.1.4).
+const NT_HOSTBASED_SERVICE: &[u8] = b"\x2a\x86\x48\x86\xf7\x12\x01\x02\x01\x04";
+
+/// The SASL GSSAPI security layer indicating that no security layer is in use.
+const SECURITY_LAYER_NONE: u8 = 1;
+
+#[link(name = "gssapi_krb5")]
+extern "C" {
+    fn gss_acquire_cred(
+        minor_status: *mut OM_uint32,
+        desired_name: gss_name_t,
+        time_req: OM_uint32,
+        desired_mechs: gss_OID_set,
+        cred_usage: c_int,
+        output_cred_handle: *mut gss_cred_id_t,
+        actual_mechs: *mut gss_OID_set,
+        time_rec: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_release_cred(minor_status: *mut OM_uint32, cred_handle: *mut gss_cred_id_t)
+        -> OM_uint32;
+
+    fn gss_import_name(
+        minor_status: *mut OM_uint32,
+        input_name_buffer: *mut gss_buffer_desc,
+        input_name_type: gss_OID,
+        output_name: *mut gss_name_t,
+    ) -> OM_uint32;
+
+    fn gss_release_name(minor_status: *mut OM_uint32, name: *mut gss_name_t) -> OM_uint32;
+
+    fn gss_init_sec_context(
+        minor_status: *mut OM_uint32,
+        initiator_cred_handle: gss_cred_id_t,
+        context_handle: *mut gss_ctx_id_t,
+        target_name: gss_name_t,
+        mech_type: gss_OID,
+        req_flags: OM_uint32,
+        time_req: OM_uint32,
+        input_chan_bindings: *mut c_void,
+        input_token: *mut gss_buffer_desc,
+        actual_mech_type: *mut gss_OID,
+        output_token: *mut gss_buffer_desc,
+        ret_flags: *mut OM_uint32,
+        time_rec: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_delete_sec_context(
+        minor_status: *mut OM_uint32,
+        context_handle: *mut gss_ctx_id_t,
+        output_token: *mut gss_buffer_desc,
+    ) -> OM_uint32;
+
+    fn gss_wrap(
+        minor_status: *mut OM_uint32,
+        context_handle: gss_ctx_id_t,
+        conf_req_flag: c_int,
+        qop_req: OM_uint32,
+        input_message_buffer: *mut gss_buffer_desc,
+        conf_state: *mut c_int,
+        output_message_buffer: *mut gss_buffer_desc,
+    ) -> OM_uint32;
+
+    fn gss_unwrap(
+        minor_status: *mut OM_uint32,
+        context_handle: gss_ctx_id_t,
+        input_message_buffer: *mut gss_buffer_desc,
+        output_message_buffer: *mut gss_buffer_desc,
+        conf_state: *mut c_int,
+        qop_state: *mut OM_uint32,
+    ) -> OM_uint32;
+
+    fn gss_release_buffer(minor_status: *mut OM_uint32, buffer: *mut gss_buffer_desc)
+        -> OM_uint32;
+
+    fn gss_display_status(
+        minor_status: *mut OM_uint32,
+        status_value: OM_uint32,
+        status_type: c_int,
+        mech_type: gss_OID,
+        message_context: *mut OM_uint32,
+        status_string: *mut gss_buffer_desc,
+    ) -> OM_uint32;
+}
+
+fn is_error(major: OM_uint32) -> bool {
+    major & 0xffff_0000 != 0
+}
+
+/// Copies the contents of a buffer allocated by the GSSAPI library, and releases it.
+unsafe fn take_buffer(buffer: &mut gss_buffer_desc) -> Vec<u8> {
+    let contents = if buffer.value.is_null() {
+        Vec::new()
+    } else {
+        slice::from_raw_parts(buffer.value as *const u8, buffer.length).to_vec()
+    };
+    let mut minor = 0;
+    gss_release_buffer(&mut minor, buffer);
+    contents
+}
+
+/// An error returned by the GSSAPI library.
+#[derive(Debug)]
+pub(crate) struct GssError {
+    /// The GSSAPI function which failed.
+    function: &'static str,
+    /// The major and minor status messages.
+    message: String,
+}
+
+impl GssError {
+    fn new(function: &'static str, major: OM_uint32, minor: OM_uint32) -> GssError {
+        let mut messages = Vec::new();
+        for &(status, status_type) in &[(major, GSS_C_GSS_CODE), (minor, GSS_C_MECH_CODE)] {
+            if status_type == GSS_C_MECH_CODE && status == 0 {
+                continue;
+            }
+            let mut message_context = 0;
+            loop {
+                let mut display_minor = 0;
+                let mut buffer = gss_buffer_desc::empty();
+                let display_major = unsafe {
+                    gss_display_status(
+                        &mut display_minor,
+                        status,
+                        status_type,
+                        ptr::null_mut(),
+                        &mut message_context,
+                        &mut buffer,
+                    )
+                };
+                if is_error(display_major) {
+                    break;
+                }
+                let message = unsafe { take_buffer(&mut buffer) };
+                messages.push(String::from_utf8_lossy(&message).into_owned());
+                if message_context == 0 {
+                    break;
+                }
+            }
+        }
+        GssError {
+            function,
+            message: messages.join(": "),
+        }
+    }
+
+    fn protocol(message: String) -> GssError {
+        GssError {
+            function: "SASL GSSAPI",
+            message,
+        }
+    }
+}
+
+impl fmt::Display for GssError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.function, self.message)
+    }
+}
+
+/// The Kerberos credentials of the client, acquired from the ambient ticket cache.
+pub(crate) struct Credentials {
+    cred: gss_cred_id_t,
+}
+
+impl Credentials {
+    /// Acquires the client's default credentials, failing if there is no valid ticket cache.
+    pub fn acquire() -> Result<Credentials, GssError> {
+        let mut minor = 0;
+        let mut cred = ptr::null_mut();
+        let major = unsafe {
+            gss_acquire_cred(
+                &mut minor,
+                ptr::null_mut(),
+                GSS_C_INDEFINITE,
+                ptr::null_mut(),
+                GSS_C_INITIATE,
+                &mut cred,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if is_error(major) {
+            return Err(GssError::new("gss_acquire_cred", major, minor));
+        }
+        Ok(Credentials { cred })
+    }
+}
+
+impl Drop for Credentials {
+    fn drop(&mut self) {
+        let mut minor = 0;
+        unsafe {
+            gss_release_cred(&mut minor, &mut self.cred);
+        }
+    }
+}
+
+/// A client security context established with a Kerberized server.
+pub(crate) struct Context {
+    credentials: Credentials,
+    target: gss_name_t,
+    context: gss_ctx_id_t,
+    established: bool,
+}
+
+// GSSAPI handles are not tied to the thread which created them, and the context is only ever
+// used by one thread at a time.
+unsafe impl Send for Context {}
+
+impl Context {
+    /// Begins establishing a security context with the `service` on `host`, returning the
+    /// context and the initial token to send to the server.
+    pub fn initiate(
+        credentials: Credentials,
+        service: &str,
+        host: &str,
+    ) -> Result<(Context, Vec<u8>), GssError> {
+        let name = format!("{}@{}", service, host);
+        let mut name_buffer = gss_buffer_desc::from_slice(name.as_bytes());
+        let mut name_type = gss_OID_desc {
+            length: NT_HOSTBASED_SERVICE.len() as OM_uint32,
+            elements: NT_HOSTBASED_SERVICE.as_ptr() as *mut c_void,
+        };
+        let mut minor = 0;
+        let mut target = ptr::null_mut();
+        let major =
+            unsafe { gss_import_name(&mut minor, &mut name_buffer, &mut name_type, &mut target) };
+        if is_error(major) {
+            return Err(GssError::new("gss_import_name", major, minor));
+        }
+
+        let mut context = Context {
+            credentials,
+            target,
+            context: ptr::null_mut(),
+            established: false,
+        };
+        let token = context.step(&[])?;
+        Ok((context, token))
+    }
+
+    /// Returns `true` if the security context is established.
+    pub fn is_established(&self) -> bool {
+        self.established
+    }
+
+    /// Processes a token received from the server, returning the token to send in response.
+    pub fn step(&mut self, token: &[u8]) -> Result<Vec<u8>, GssError> {
+        debug_assert!(!self.established);
+        let mut input = gss_buffer_desc::from_slice(token);
+        let mut output = gss_buffer_desc::empty();
+        let mut minor = 0;
+        let major = unsafe {
+            gss_init_sec_context(
+                &mut minor,
+                self.credentials.cred,
+                &mut self.context,
+                self.target,
+                ptr::null_mut(),
+                GSS_C_MUTUAL_FLAG | GSS_C_SEQUENCE_FLAG,
+                0,
+                ptr::null_mut(),
+                &mut input,
+                ptr::null_mut(),
+                &mut output,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        let output = unsafe { take_buffer(&mut output) };
+        match major {
+            GSS_S_COMPLETE => self.established = true,
+            GSS_S_CONTINUE_NEEDED => (),
+            _ => return Err(GssError::new("gss_init_sec_context", major, minor)),
+        }
+        Ok(output)
+    }
+
+    /// Negotiates the SASL security layer once the security context is established.
+    ///
+    /// Unwraps the security layers offered by the server, and returns the wrapped response
+    /// selecting no security layer and an empty authorization ID. Fails if the server requires
+    /// integrity or confidentiality protection, which is not supported.
+    pub fn negotiate_security_layer(&mut self, challenge: &[u8]) -> Result<Vec<u8>, GssError> {
+        debug_assert!(self.established);
+        let mut input = gss_buffer_desc::from_slice(challenge);
+        let mut output = gss_buffer_desc::empty();
+        let mut minor = 0;
+        let major = unsafe {
+            gss_unwrap(
+                &mut minor,
+                self.context,
+                &mut input,
+                &mut output,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        };
+        if is_error(major) {
+            return Err(GssError::new("gss_unwrap", major, minor));
+        }
+        let offer = unsafe { take_buffer(&mut output) };
+        if offer.len() != 4 {
+            return Err(GssError::protocol(format!(
+                "invalid security layer offer length: {}",
+                offer.len()
+            )));
+        }
+        if offer[0] & SECURITY_LAYER_NONE == 0 {
+            return Err(GssError::protocol(
+                "server requires a SASL security layer, which is not supported".to_string(),
+            ));
+        }
+
+        let response = [SECURITY_LAYER_NONE, 0, 0, 0];
+        let mut input = gss_buffer_desc::from_slice(&response);
+        let mut output = gss_buffer_desc::empty();
+        let major = unsafe {
+            gss_wrap(
+                &mut minor,
+                self.context,
+                0,
+                0,
+                &mut input,
+                ptr::null_mut(),
+                &mut output,
+            )
+        };
+        if is_error(major) {
+            return Err(GssError::new("gss_wrap", major, minor));
+        }
+        Ok(unsafe { take_buffer(&mut output) })
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        let mut minor = 0;
+        unsafe {
+            if !self.context.is_null() {
+                gss_delete_sec_context(&mut minor, &mut self.context, ptr::null_mut());
+            }
+            gss_release_name(&mut minor, &mut self.target);
+        }
+    }
+}
@@ -2,6 +2,7 @@ extern crate byteorder;
 extern crate bytes;
 extern crate fnv;
 extern crate itertools;
+extern crate openssl;
 extern crate prost;
 extern crate prost_types;
 extern crate tokio;
@@ -20,26 +21,37 @@ mod call;
 mod connection;
 mod connector;
 mod error;
+#[cfg(feature = "kerberos")]
+mod gssapi;
 mod hostport;
+mod metrics;
 mod negotiator;
 mod pb;
 mod proxy;
 mod rpc;
+mod tls;
 mod transport;
 
+use std::fmt;
 use std::marker;
+use std::sync::Arc;
+use std::time::Duration;
 
 use bytes::{Bytes, BytesMut};
 use futures::sync::oneshot;
 use futures::{Async, Future, Poll};
 use prost::Message;
 
+use metrics::RpcMetrics;
+
 pub use call::Call;
 pub use error::{Error, RpcError, RpcErrorCode};
 pub use hostport::HostPort;
+pub use metrics::Metrics;
 pub use pb::rpc::RequestIdPb as RequestId;
 pub use proxy::Proxy;
 use rpc::Rpc;
+pub use tls::{Certificate, EncryptionPolicy};
 
 trait RequestBody: Send + Sync {
     fn encoded_len(&self) -> usize;
@@ -66,6 +78,7 @@ where
     Resp: Message + Default,
 {
     receiver: oneshot::Receiver<RpcResult>,
+    metrics: Option<RpcMetrics>,
     _marker: marker::PhantomData<Resp>,
 }
 
@@ -74,9 +87,10 @@ where
     Resp: Message + Default,
 {
     /// Returns a new `RpcFuture` wrapping the provided oneshot receiver.
-    fn new(receiver: oneshot::Receiver<RpcResult>) -> RpcFuture<Resp> {
+    fn new(receiver: oneshot::Receiver<RpcResult>, metrics: Option<RpcMetrics>) -> RpcFuture<Resp> {
         RpcFuture {
             receiver,
+            metrics,
             _marker: marker::PhantomData::default(),
         }
     }
@@ -90,9 +104,13 @@ where
     type Error = Error;
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        let (bytes, sidecars) = try_ready!(self.receiver.poll().map_err(|_| -> Error {
+        let result = try_ready!(self.receiver.poll().map_err(|_| -> Error {
             unreachable!("RPC dropped");
-        }))?;
+        }));
+        if let Some(metrics) = self.metrics.take() {
+            metrics.complete(result.as_ref().map(|_| ()));
+        }
+        let (bytes, sidecars) = result?;
         let body = Resp::decode(&bytes).expect("poll");
 
         trace!("RpcFuture complete: {:?}", body);
@@ -101,7 +119,20 @@ where
     }
 }
 
-#[derive(Clone, Debug)]
+impl<Resp> Drop for RpcFuture<Resp>
+where
+    Resp: Message + Default,
+{
+    fn drop(&mut self) {
+        // Report RPCs which are abandoned before completing, so that every started RPC is
+        // completed.
+        if let Some(metrics) = self.metrics.take() {
+            metrics.cancel();
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Options {
     /// Maximum number of outstandings RPCs to allow per connection.
     ///
@@ -117,6 +148,64 @@ pub struct Options {
     ///
     /// Defaults to true.
     pub nodelay: bool,
+
+    /// The user name to authenticate as when negotiating connections with SASL PLAIN.
+    ///
+    /// Defaults to `kudu-rs-user`.
+    pub user: String,
+
+    /// Callbacks for observing RPC activity.
+    ///
+    /// Defaults to `None`.
+    pub metrics: Option<Arc<Metrics>>,
+
+    /// How often to re-resolve the hostnames of a connected server. If the hostnames no longer
+    /// resolve to the connected address, the connection is closed once its in-flight RPCs
+    /// complete, and subsequent RPCs reconnect to a newly resolved address. Hostnames are always
+    /// resolved anew when connecting, including when reconnecting after a failure.
+    ///
+    /// Defaults to `None`, which disables re-resolution of connected servers.
+    pub dns_refresh_interval: Option<Duration>,
+
+    /// Maximum duration allowed for negotiating a newly established connection. Connections
+    /// which fail to negotiate in time are closed, and the RPCs waiting on them fail with
+    /// `Error::TimedOut`.
+    ///
+    /// Defaults to 3 seconds.
+    pub negotiation_timeout: Duration,
+
+    /// The interval between TCP keepalive probes on idle connections.
+    ///
+    /// Defaults to `None`, which leaves TCP keepalive disabled.
+    pub keep_alive_interval: Option<Duration>,
+
+    /// Whether connections are encrypted with TLS.
+    ///
+    /// Defaults to `EncryptionPolicy::Optional`.
+    pub encryption: EncryptionPolicy,
+
+    /// CA certificates trusted to sign server certificates. If empty, server certificates are
+    /// not verified during the TLS handshake.
+    ///
+    /// Defaults to empty.
+    pub trusted_certificates: Vec<Certificate>,
+}
+
+impl fmt::Debug for Options {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Options")
+            .field("max_rpcs_in_flight", &self.max_rpcs_in_flight)
+            .field("max_message_length", &self.max_message_length)
+            .field("nodelay", &self.nodelay)
+            .field("user", &self.user)
+            .field("metrics", &self.metrics.is_some())
+            .field("dns_refresh_interval", &self.dns_refresh_interval)
+            .field("negotiation_timeout", &self.negotiation_timeout)
+            .field("keep_alive_interval", &self.keep_alive_interval)
+            .field("encryption", &self.encryption)
+            .field("trusted_certificates", &self.trusted_certificates.len())
+            .finish()
+    }
 }
 
 impl Default for Options {
@@ -125,6 +214,85 @@ impl Default for Options {
             max_rpcs_in_flight: 32,
             max_message_length: 5 * 1024 * 1024,
             nodelay: true,
+            user: "kudu-rs-user".to_string(),
+            metrics: None,
+            dns_refresh_interval: None,
+            negotiation_timeout: Duration::from_secs(3),
+            keep_alive_interval: None,
+            encryption: EncryptionPolicy::default(),
+            trusted_certificates: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    use futures::future;
+    use tokio::runtime::current_thread::Runtime;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingMetrics {
+        started: AtomicUsize,
+        cancelled: AtomicUsize,
+        timed_out: AtomicUsize,
+    }
+
+    impl Metrics for CountingMetrics {
+        fn on_rpc_start(&self, _service: &str, _method: &str) {
+            self.started.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_rpc_complete(
+            &self,
+            _service: &str,
+            _method: &str,
+            _duration: Duration,
+            result: Result<(), &Error>,
+        ) {
+            match result {
+                Err(&Error::Cancelled) => self.cancelled.fetch_add(1, Ordering::SeqCst),
+                Err(&Error::TimedOut) => self.timed_out.fetch_add(1, Ordering::SeqCst),
+                other => panic!("unexpected result: {:?}", other),
+            };
         }
     }
+
+    #[test]
+    fn dropped_rpcs_complete() {
+        // A server which accepts connections, but never negotiates them.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let hostport = HostPort::new("127.0.0.1".to_string(), port);
+
+        let metrics = Arc::new(CountingMetrics::default());
+        let mut options = Options::default();
+        options.metrics = Some(metrics.clone());
+
+        let mut runtime = Runtime::new().unwrap();
+        runtime
+            .block_on(future::lazy(|| -> Result<(), Error> {
+                let mut proxy = Proxy::spawn(vec![hostport].into_boxed_slice(), options);
+                let request = Arc::new(RequestId::default());
+
+                let deadline = Instant::now() + Duration::from_secs(60);
+                let call = Call::<RequestId, RequestId>::new("S", "M", request.clone(), deadline);
+                drop(proxy.send(call));
+
+                let deadline = Instant::now();
+                let call = Call::<RequestId, RequestId>::new("S", "M", request, deadline);
+                drop(proxy.send(call));
+                Ok(())
+            })).unwrap();
+
+        assert_eq!(2, metrics.started.load(Ordering::SeqCst));
+        assert_eq!(1, metrics.cancelled.load(Ordering::SeqCst));
+        assert_eq!(1, metrics.timed_out.load(Ordering::SeqCst));
+    }
 }
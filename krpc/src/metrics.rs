@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use Error;
+
+/// Callbacks for observing RPC activity.
+///
+/// A `Metrics` implementation may be installed with `Options::metrics`, after which it is invoked
+/// for every RPC sent through a `Proxy` created with the options. All methods have no-op default
+/// implementations, so implementations only need to override the events they are interested in.
+///
+/// Callbacks are invoked inline on the task sending or completing the RPC, so they should be
+/// cheap and must not block.
+pub trait Metrics: Send + Sync {
+    /// Called when an RPC is sent.
+    fn on_rpc_start(&self, _service: &str, _method: &str) {}
+
+    /// Called when an RPC completes, with the time elapsed since it was sent, and the error if
+    /// the RPC failed.
+    ///
+    /// Every started RPC is completed exactly once. RPCs whose response future is dropped before
+    /// the response arrives complete with `Error::TimedOut` if their deadline has passed, and
+    /// with `Error::Cancelled` otherwise.
+    fn on_rpc_complete(
+        &self,
+        _service: &str,
+        _method: &str,
+        _duration: Duration,
+        _result: Result<(), &Error>,
+    ) {
+    }
+
+    /// Called when an RPC is retried after failing with a retriable error.
+    fn on_retry(&self, _service: &str, _method: &str) {}
+}
+
+/// Tracks a single in-flight RPC for reporting to a `Metrics` implementation.
+pub(crate) struct RpcMetrics {
+    metrics: Arc<Metrics>,
+    service: &'static str,
+    method: &'static str,
+    start: Instant,
+    deadline: Instant,
+}
+
+impl RpcMetrics {
+    pub fn start(
+        metrics: Arc<Metrics>,
+        service: &'static str,
+        method: &'static str,
+        deadline: Instant,
+    ) -> RpcMetrics {
+        metrics.on_rpc_start(service, method);
+        RpcMetrics {
+            metrics,
+            service,
+            method,
+            start: Instant::now(),
+            deadline,
+        }
+    }
+
+    pub fn complete(self, result: Result<(), &Error>) {
+        self.metrics
+            .on_rpc_complete(self.service, self.method, self.start.elapsed(), result);
+    }
+
+    /// Completes an RPC which was dropped before its response arrived.
+    pub fn cancel(self) {
+        let error = if Instant::now() >= self.deadline {
+            Error::TimedOut
+        } else {
+            Error::Cancelled
+        };
+        self.complete(Err(&error));
+    }
+}
@@ -1,20 +1,30 @@
 use std::collections::HashSet;
 use std::fmt;
+use std::time::Instant;
 
 use futures::{Async, Future, Poll};
 use prost::Message;
+use tokio::timer::Delay;
 
 use pb::rpc::negotiate_pb::{NegotiateStep, SaslMechanism as SaslMechanismPb};
 use pb::rpc::{
     authentication_type_pb, AuthenticationTypePb, ConnectionContextPb, NegotiatePb, RpcFeatureFlag,
 };
+#[cfg(feature = "kerberos")]
+use gssapi;
+use tls::{Handshake, Progress, Session};
 use transport::Transport;
+use EncryptionPolicy;
 use Error;
+use RpcErrorCode;
 
 const NEGOTIATION_CALL_ID: i32 = -33;
 const CONNECTION_CONTEXT_CALL_ID: i32 = -3;
 const SASL: authentication_type_pb::Type =
     authentication_type_pb::Type::Sasl(authentication_type_pb::Sasl {});
+/// The service name of Kudu's Kerberos principals, `kudu/<host>@<REALM>`.
+#[cfg(feature = "kerberos")]
+const KERBEROS_SERVICE_NAME: &str = "kudu";
 
 #[derive(Debug)]
 enum AuthenticationType {
@@ -59,6 +69,16 @@ pub struct Inner {
     authentication: Option<AuthenticationType>,
     /// The features supported by the server. Filled in during negotiation.
     supported_features: Vec<RpcFeatureFlag>,
+    /// The SASL mechanisms offered by the server, saved while the TLS handshake is in progress.
+    server_sasl_mechanisms: Vec<SaslMechanismPb>,
+    /// The in-progress TLS handshake, if any.
+    tls_handshake: Option<Handshake>,
+    /// The established TLS session, which encrypts the connection once the final handshake
+    /// message has been flushed to the server.
+    tls_session: Option<Box<Session>>,
+    /// The Kerberos security context, if authenticating with SASL GSSAPI.
+    #[cfg(feature = "kerberos")]
+    gssapi: Option<gssapi::Context>,
 }
 
 impl Inner {
@@ -66,7 +86,8 @@ impl Inner {
         match self.pb.step() {
             NegotiateStep::Unknown => self.do_initial_step(),
             NegotiateStep::Negotiate => self.do_negotiate_step(),
-            NegotiateStep::SaslInitiate => self.do_sasl_plain_step(),
+            NegotiateStep::TlsHandshake => self.do_tls_handshake_step(),
+            NegotiateStep::SaslInitiate | NegotiateStep::SaslResponse => self.do_sasl_step(),
             _ => unreachable!(),
         }
     }
@@ -120,8 +141,14 @@ impl Inner {
         self.pb
             .supported_features
             .push(RpcFeatureFlag::ApplicationFeatureFlags as i32);
+        if self.transport.options().encryption != EncryptionPolicy::Disabled {
+            self.pb.supported_features.push(RpcFeatureFlag::Tls as i32);
+        }
         self.pb.step = NegotiateStep::Negotiate as i32;
         self.pb.sasl_mechanisms.push(SaslMechanism::Plain.to_pb());
+        if cfg!(feature = "kerberos") {
+            self.pb.sasl_mechanisms.push(SaslMechanism::Gssapi.to_pb());
+        }
         self.pb
             .authn_types
             .push(AuthenticationTypePb { type_: Some(SASL) });
@@ -154,22 +181,104 @@ impl Inner {
             .and_then(|authn_type| authn_type.type_.take())
             .unwrap_or(SASL);
         match authn_type {
-            authentication_type_pb::Type::Sasl(..) => self.do_sasl_initiate(),
-            authentication_type_pb::Type::Token(..) => Err(Error::Negotiation(
-                "TOKEN authentication is not supported".to_string(),
-            )),
-            authentication_type_pb::Type::Certificate(..) => Err(Error::Negotiation(
-                "CERTIFICATE authentication is not supported".to_string(),
-            )),
+            authentication_type_pb::Type::Sasl(..) => (),
+            authentication_type_pb::Type::Token(..) => {
+                return Err(Error::Negotiation(
+                    "TOKEN authentication is not supported".to_string(),
+                ))
+            }
+            authentication_type_pb::Type::Certificate(..) => {
+                return Err(Error::Negotiation(
+                    "CERTIFICATE authentication is not supported".to_string(),
+                ))
+            }
+        }
+
+        // The server only advertises TLS if the client did, so encryption isn't disabled.
+        if self.supported_features.contains(&RpcFeatureFlag::Tls) {
+            self.server_sasl_mechanisms = self.pb.sasl_mechanisms.split_off(0);
+            return self.do_tls_initiate();
+        }
+        if self.transport.options().encryption == EncryptionPolicy::Required {
+            return Err(Error::Negotiation(
+                "server does not support TLS, which is required by the encryption policy"
+                    .to_string(),
+            ));
+        }
+        let server_mechs = self.pb.sasl_mechanisms.split_off(0);
+        self.do_sasl_initiate(&server_mechs)
+    }
+
+    fn do_tls_initiate(&mut self) -> Poll<(), Error> {
+        trace!("{:?}: do_tls_initiate", self);
+        let progress = Handshake::initiate(self.transport.options())?;
+        self.do_tls_progress(progress)
+    }
+
+    fn do_tls_handshake_step(&mut self) -> Poll<(), Error> {
+        trace!("{:?}: do_tls_handshake_step", self);
+        // The final handshake message has been sent; start encrypting once it's flushed.
+        if self.tls_session.is_some() {
+            return self.do_tls_complete();
+        }
+
+        try_ready!(self.recv_negotiate_pb());
+        if self.pb.step() != NegotiateStep::TlsHandshake {
+            return Err(Error::Negotiation(format!(
+                "expected TlsHandshake step, received: {:?}",
+                self.pb.step()
+            )));
+        }
+        let message = match self.pb.tls_handshake.take() {
+            Some(message) => message,
+            None => {
+                return Err(Error::Negotiation(
+                    "no TLS handshake message in TlsHandshake response".to_string(),
+                ))
+            }
+        };
+        let handshake = self
+            .tls_handshake
+            .take()
+            .expect("TLS handshake message received without a TLS handshake in progress");
+        let progress = handshake.step(&message)?;
+        self.do_tls_progress(progress)
+    }
+
+    fn do_tls_progress(&mut self, progress: Progress) -> Poll<(), Error> {
+        let message = match progress {
+            Progress::Continue(handshake, message) => {
+                self.tls_handshake = Some(handshake);
+                message
+            }
+            Progress::Complete(session, message) => {
+                self.tls_session = Some(session);
+                message
+            }
+        };
+        self.pb.clear();
+        self.pb.step = NegotiateStep::TlsHandshake as i32;
+        if !message.is_empty() {
+            self.pb.tls_handshake = Some(message);
+            self.send_negotiate_pb()?;
         }
+        self.do_tls_handshake_step()
+    }
+
+    fn do_tls_complete(&mut self) -> Poll<(), Error> {
+        trace!("{:?}: do_tls_complete", self);
+        try_ready!(self.transport.poll_flush());
+        let session = self.tls_session.take().unwrap();
+        self.transport.start_tls(session);
+        debug!("{:?}: TLS negotiated", self);
+        let server_mechs = self.server_sasl_mechanisms.split_off(0);
+        self.do_sasl_initiate(&server_mechs)
     }
 
-    fn do_sasl_initiate(&mut self) -> Poll<(), Error> {
+    fn do_sasl_initiate(&mut self, server_mechs: &[SaslMechanismPb]) -> Poll<(), Error> {
         trace!("{:?}: do_sasl_initiate", self);
         // Determine which mechanism to use.
-        let server_mechs = self
-            .pb
-            .sasl_mechanisms
+        let server_mechs = server_mechs
             .iter()
             .flat_map(SaslMechanism::from_pb)
             .collect::<HashSet<_>>();
@@ -178,10 +287,23 @@ impl Inner {
             return Err(Error::Negotiation(
                 "server does not support any SASL mechanisms".to_string(),
             ));
-        } else if !server_mechs.contains(&SaslMechanism::Plain) {
-            return Err(Error::Negotiation(
-                "SASL GSSAPI mechanism is not supported".to_string(),
-            ));
+        }
+
+        // Prefer Kerberos when the client has credentials in its ticket cache, falling back to
+        // PLAIN if the server accepts it.
+        if server_mechs.contains(&SaslMechanism::Gssapi) {
+            if let Some(result) = self.try_sasl_gssapi_initiate(&server_mechs) {
+                return result;
+            }
+        }
+
+        if !server_mechs.contains(&SaslMechanism::Plain) {
+            // The server only accepts Kerberos, which requires the `kerberos` feature.
+            return Err(Error::Negotiation(format!(
+                "no credentials available for the SASL mechanisms offered by the server: {:?}; \
+                 Kerberos (SASL GSSAPI) authentication requires the `kerberos` feature",
+                server_mechs
+            )));
         }
 
         self.authentication = Some(AuthenticationType::Sasl(SaslMechanism::Plain));
@@ -191,27 +313,171 @@ impl Inner {
     fn do_sasl_plain_initiate(&mut self) -> Poll<(), Error> {
         trace!("{:?}: do_sasl_plain_initiate", self);
         self.pb.step = NegotiateStep::SaslInitiate as i32;
-        self.pb.token = Some(b"\0kudu-rs-user\0".to_vec());
+        // The PLAIN token is the authorization ID, authentication ID, and password, each
+        // separated by a NUL byte. Only the authentication ID is used by Kudu.
+        let user = self.transport.options().user.as_bytes();
+        let mut token = Vec::with_capacity(user.len() + 2);
+        token.push(0);
+        token.extend_from_slice(user);
+        token.push(0);
+        self.pb.token = Some(token);
         self.pb.sasl_mechanisms = vec![SaslMechanism::Plain.to_pb()];
         self.send_negotiate_pb()?;
-        self.do_sasl_plain_step()
+        self.do_sasl_step()
     }
 
-    fn do_sasl_plain_step(&mut self) -> Poll<(), Error> {
-        trace!("{:?}: do_sasl_plain_step", self);
-        try_ready!(self.recv_negotiate_pb());
+    /// Initiates SASL GSSAPI authentication if the client has Kerberos credentials. Returns
+    /// `None` if it does not, and the server also accepts PLAIN authentication.
+    #[cfg(feature = "kerberos")]
+    fn try_sasl_gssapi_initiate(
+        &mut self,
+        server_mechs: &HashSet<SaslMechanism>,
+    ) -> Option<Poll<(), Error>> {
+        match gssapi::Credentials::acquire() {
+            Ok(credentials) => Some(self.do_sasl_gssapi_initiate(credentials)),
+            Err(ref error) if server_mechs.contains(&SaslMechanism::Plain) => {
+                debug!(
+                    "{:?}: no Kerberos credentials available, falling back to SASL PLAIN: {}",
+                    self, error
+                );
+                None
+            }
+            Err(error) => Some(Err(Error::Negotiation(format!(
+                "no Kerberos credentials available for SASL GSSAPI authentication: {}",
+                error
+            )))),
+        }
+    }
 
-        if self.pb.step() != NegotiateStep::SaslSuccess {
-            return Err(Error::Negotiation(format!(
-                "expected SaslSuccess step, received: {:?}",
-                self.pb.step()
-            )));
+    #[cfg(not(feature = "kerberos"))]
+    fn try_sasl_gssapi_initiate(
+        &mut self,
+        _server_mechs: &HashSet<SaslMechanism>,
+    ) -> Option<Poll<(), Error>> {
+        None
+    }
+
+    #[cfg(feature = "kerberos")]
+    fn do_sasl_gssapi_initiate(&mut self, credentials: gssapi::Credentials) -> Poll<(), Error> {
+        trace!("{:?}: do_sasl_gssapi_initiate", self);
+        let (context, token) =
+            gssapi::Context::initiate(credentials, KERBEROS_SERVICE_NAME, self.transport.host())
+                .map_err(|error| {
+                    Error::Negotiation(format!(
+                        "failed to initiate SASL GSSAPI authentication: {}",
+                        error
+                    ))
+                })?;
+        self.authentication = Some(AuthenticationType::Sasl(SaslMechanism::Gssapi));
+        self.gssapi = Some(context);
+        self.pb.step = NegotiateStep::SaslInitiate as i32;
+        self.pb.token = Some(token);
+        self.pb.sasl_mechanisms = vec![SaslMechanism::Gssapi.to_pb()];
+        self.send_negotiate_pb()?;
+        self.do_sasl_step()
+    }
+
+    fn do_sasl_step(&mut self) -> Poll<(), Error> {
+        trace!("{:?}: do_sasl_step", self);
+        match self.recv_negotiate_pb() {
+            Ok(Async::Ready(())) => (),
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            // The server rejected the client's credentials. Other errors, such as I/O errors,
+            // are returned unchanged.
+            Err(Error::Rpc(ref error)) if error.code == RpcErrorCode::FatalUnauthorized => {
+                let authentication = match self.authentication {
+                    Some(AuthenticationType::Sasl(SaslMechanism::Gssapi)) => {
+                        "SASL GSSAPI authentication".to_string()
+                    }
+                    _ => format!(
+                        "SASL PLAIN authentication as user {:?}",
+                        self.transport.options().user
+                    ),
+                };
+                return Err(Error::Negotiation(format!(
+                    "server rejected {}: {}",
+                    authentication, error.message
+                )));
+            }
+            Err(error) => return Err(error),
+        }
+
+        match self.pb.step() {
+            NegotiateStep::SaslSuccess => self.do_sasl_success(),
+            NegotiateStep::SaslChallenge => self.do_sasl_challenge(),
+            step => Err(Error::Negotiation(format!(
+                "expected SaslSuccess or SaslChallenge step, received: {:?}",
+                step
+            ))),
         }
+    }
+
+    #[cfg(feature = "kerberos")]
+    fn do_sasl_challenge(&mut self) -> Poll<(), Error> {
+        trace!("{:?}: do_sasl_challenge", self);
+        let challenge = self.pb.token.take().unwrap_or_default();
+        let response = {
+            let context = match self.gssapi {
+                Some(ref mut context) => context,
+                None => {
+                    return Err(Error::Negotiation(
+                        "received unexpected SaslChallenge step during SASL PLAIN \
+                         authentication"
+                            .to_string(),
+                    ))
+                }
+            };
+            // Once the security context is established, the final challenge offers the
+            // security layers supported by the server.
+            if context.is_established() {
+                context.negotiate_security_layer(&challenge)
+            } else {
+                context.step(&challenge)
+            }
+        }.map_err(|error| {
+            Error::Negotiation(format!("SASL GSSAPI authentication failed: {}", error))
+        })?;
+
+        self.pb.clear();
+        self.pb.step = NegotiateStep::SaslResponse as i32;
+        self.pb.token = Some(response);
+        self.send_negotiate_pb()?;
+        self.do_sasl_step()
+    }
+
+    #[cfg(not(feature = "kerberos"))]
+    fn do_sasl_challenge(&mut self) -> Poll<(), Error> {
+        Err(Error::Negotiation(
+            "received unexpected SaslChallenge step during SASL PLAIN authentication".to_string(),
+        ))
+    }
 
+    fn do_sasl_success(&mut self) -> Poll<(), Error> {
+        trace!("{:?}: do_sasl_success", self);
+        self.check_gssapi_established()?;
         self.send_connection_context()?;
         Ok(Async::Ready(()))
     }
 
+    /// Checks that the Kerberos security context, if any, was established before the server
+    /// completed authentication.
+    #[cfg(feature = "kerberos")]
+    fn check_gssapi_established(&self) -> Result<(), Error> {
+        match self.gssapi {
+            Some(ref context) if !context.is_established() => Err(Error::Negotiation(
+                "server completed SASL GSSAPI authentication before the security context was \
+                 established"
+                    .to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "kerberos"))]
+    fn check_gssapi_established(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn send_connection_context(&mut self) -> Result<(), Error> {
         trace!("{:?}: send_connection_context", self);
         let context = ConnectionContextPb::default();
@@ -235,19 +501,31 @@ impl fmt::Debug for Inner {
     }
 }
 
+/// Negotiates a newly connected transport, failing with `Error::TimedOut` if negotiation does not
+/// complete within the negotiation timeout.
 pub(crate) struct Negotiator {
     inner: Option<Inner>,
+    deadline: Delay,
 }
 
 impl Negotiator {
     pub fn negotiate(transport: Transport) -> Negotiator {
+        let deadline = Delay::new(Instant::now() + transport.options().negotiation_timeout);
         let inner = Inner {
             pb: NegotiatePb::default(),
             transport,
             authentication: None,
             supported_features: Vec::new(),
+            server_sasl_mechanisms: Vec::new(),
+            tls_handshake: None,
+            tls_session: None,
+            #[cfg(feature = "kerberos")]
+            gssapi: None,
         };
-        Negotiator { inner: Some(inner) }
+        Negotiator {
+            inner: Some(inner),
+            deadline,
+        }
     }
 }
 
@@ -258,7 +536,18 @@ impl Future for Negotiator {
     fn poll(&mut self) -> Poll<Transport, Error> {
         debug!("{:?}: poll", self);
         match self.inner {
-            Some(ref mut inner) => try_ready!(inner.poll()),
+            Some(ref mut inner) => {
+                if let Async::NotReady = inner.poll()? {
+                    return match self.deadline.poll() {
+                        Ok(Async::NotReady) => Ok(Async::NotReady),
+                        Ok(Async::Ready(())) => {
+                            warn!("{:?}: negotiation timed out", inner);
+                            Err(Error::TimedOut)
+                        }
+                        Err(error) => panic!("timer failed: {}", error),
+                    };
+                }
+            }
             None => return Ok(Async::NotReady),
         }
 
@@ -1,19 +1,26 @@
 use std::collections::VecDeque;
 use std::fmt;
-use std::time::Instant;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use futures::future;
+use futures::future::{self, Shared};
+use futures::stream::FuturesUnordered;
 use futures::sync::{mpsc, oneshot};
 use futures::{Async, AsyncSink, Future, Poll, Sink, Stream};
 use itertools::Itertools;
 use prost::Message;
 use tokio;
+use tokio::timer::Delay;
 
 use connection::Connection;
-use connector::Connector;
+use connector::{self, Connector, Resolve};
+use metrics::RpcMetrics;
 use Call;
 use Error;
 use HostPort;
+use Metrics;
 use Options;
 use Rpc;
 use RpcFuture;
@@ -22,6 +29,30 @@ use RpcFuture;
 #[derive(Clone)]
 pub struct Proxy {
     sender: mpsc::Sender<Rpc>,
+    metrics: Option<Arc<Metrics>>,
+    shutdown: Arc<Shutdown>,
+}
+
+/// The shutdown state shared by the clones of a proxy.
+struct Shutdown {
+    /// Signals the proxy task to shut down. Taken by the first call to `Proxy::shutdown`.
+    signal: Mutex<Option<oneshot::Sender<()>>>,
+    /// Completes when the proxy task finishes.
+    closed: Shared<oneshot::Receiver<()>>,
+    /// Set when the proxy task finishes, including when it is dropped along with its runtime.
+    is_closed: Arc<AtomicBool>,
+}
+
+/// Marks the proxy as closed and completes `Proxy::closed` when dropped.
+struct Finished {
+    is_closed: Arc<AtomicBool>,
+    _sender: oneshot::Sender<()>,
+}
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        self.is_closed.store(true, Ordering::SeqCst);
+    }
 }
 
 impl Proxy {
@@ -34,14 +65,73 @@ impl Proxy {
 
     pub fn spawn(hostports: Box<[HostPort]>, options: Options) -> Proxy {
         let (sender, receiver) = mpsc::channel(options.max_rpcs_in_flight as usize);
+        let (signal, shutdown) = oneshot::channel();
+        let (finished, closed) = oneshot::channel();
+        let is_closed = Arc::new(AtomicBool::new(false));
+        let metrics = options.metrics.clone();
         tokio::spawn(ProxyTask {
             hostports,
             options,
             receiver,
             connection_state: ConnectionState::Quiesced,
             buffer: VecDeque::new(),
+            refresh: None,
+            shutdown,
+            is_shut_down: false,
+            _finished: Finished {
+                is_closed: is_closed.clone(),
+                _sender: finished,
+            },
         });
-        Proxy { sender }
+        Proxy {
+            sender,
+            metrics,
+            shutdown: Arc::new(Shutdown {
+                signal: Mutex::new(Some(signal)),
+                closed: closed.shared(),
+                is_closed,
+            }),
+        }
+    }
+
+    /// Shuts down the proxy and its clones. RPCs sent before the shutdown complete normally,
+    /// while RPCs sent afterwards fail with `Error::Shutdown`.
+    ///
+    /// Returns a future which completes once the in-flight RPCs complete and the connection to
+    /// the remote server is closed.
+    pub fn shutdown(&self) -> impl Future<Item = (), Error = ()> {
+        if let Some(signal) = self.shutdown.signal.lock().unwrap().take() {
+            let _ = signal.send(());
+        }
+        self.closed()
+    }
+
+    /// Returns a future which completes once the proxy is shut down and its connection to the
+    /// remote server is closed.
+    pub fn closed(&self) -> impl Future<Item = (), Error = ()> {
+        self.shutdown.closed.clone().then(|_| Ok(()))
+    }
+
+    /// Returns `true` if the proxy's task has finished, either because the proxy was shut down,
+    /// or because the runtime which the task was spawned on was dropped. RPCs sent through a
+    /// closed proxy fail with `Error::Shutdown`.
+    pub fn is_closed(&self) -> bool {
+        self.shutdown.is_closed.load(Ordering::SeqCst)
+    }
+
+    /// Returns a handle to the same remote server connection which reports RPCs to `metrics`
+    /// instead of the callbacks installed on this proxy.
+    pub fn with_metrics(&self, metrics: Option<Arc<Metrics>>) -> Proxy {
+        Proxy {
+            sender: self.sender.clone(),
+            metrics,
+            shutdown: self.shutdown.clone(),
+        }
+    }
+
+    /// Returns the metrics callbacks installed on the proxy, if any.
+    pub fn metrics(&self) -> Option<&Arc<Metrics>> {
+        self.metrics.as_ref()
     }
 
     /// Polls the proxy to determine if there is guaranteed to be capacity to send at least one
@@ -54,7 +144,8 @@ impl Proxy {
     pub fn poll_ready(&mut self) -> Async<()> {
         match self.sender.poll_ready() {
             Ok(async) => async,
-            Err(_) => unreachable!(),
+            // The proxy is shut down, so RPCs fail immediately.
+            Err(_) => Async::Ready(()),
         }
     }
 
@@ -68,12 +159,17 @@ impl Proxy {
         Resp: Message + Default,
     {
         let (completer, receiver) = oneshot::channel();
+        let metrics = self
+            .metrics
+            .clone()
+            .map(|metrics| RpcMetrics::start(metrics, call.service, call.method, call.deadline));
         let rpc = Rpc {
             service: call.service,
             method: call.method,
             required_feature_flags: call.required_feature_flags,
             timestamp: Instant::now(),
             deadline: call.deadline,
+            request_id: call.request_id,
             request: call.request,
             completer,
         };
@@ -81,10 +177,10 @@ impl Proxy {
         match self.sender.start_send(rpc) {
             Ok(AsyncSink::Ready) => (),
             Ok(AsyncSink::NotReady(_)) => panic!("Proxy not ready"),
-            Err(..) => unreachable!(),
+            Err(error) => error.into_inner().fail(Error::Shutdown),
         }
 
-        RpcFuture::new(receiver)
+        RpcFuture::new(receiver, metrics)
     }
 }
 
@@ -140,8 +236,17 @@ impl Proxy {
 /// | b | `Connecting`  | connect succeeds | `Connected`  |                                  |
 /// | c | `Connecting`  | connect fails    | `Failed`     | fail buffered RPCs               |
 /// | d | `Connected`   | fatal RPC error  | `Quiesced`   | buffer non-failed in-flight RPCs |
+/// | d | `Connected`   | address retired  | `Quiesced`   |                                  |
 /// | e | `Failed`      | backoff elapsed  | `Quiesced`   |                                  |
 ///
+/// When `Options::dns_refresh_interval` is set, the server's hostnames are periodically
+/// re-resolved while connected. If they no longer resolve to the connected address, the
+/// connection is retired: no more RPCs are sent on it, and once its in-flight RPCs complete the
+/// task transitions to `Quiesced`, reconnecting to a newly resolved address.
+///
+/// When the proxy is shut down, the RPC channel is closed. The RPCs already queued in the channel
+/// are sent, and the task finishes once they complete.
+///
 /// TODO: add the Failed state.
 enum ConnectionState {
     Quiesced,
@@ -170,6 +275,11 @@ struct ProxyTask {
     receiver: mpsc::Receiver<Rpc>,
     connection_state: ConnectionState,
     buffer: VecDeque<Rpc>,
+    refresh: Option<Refresh>,
+    shutdown: oneshot::Receiver<()>,
+    is_shut_down: bool,
+    /// Dropped when the task finishes, completing `Proxy::closed`.
+    _finished: Finished,
 }
 
 impl Future for ProxyTask {
@@ -188,8 +298,24 @@ impl Future for ProxyTask {
             ref mut receiver,
             ref mut connection_state,
             ref mut buffer,
+            ref mut refresh,
+            ref mut shutdown,
+            ref mut is_shut_down,
+            ..
         } = *self;
 
+        // The signal is canceled if every handle to the proxy is dropped, which also shuts the
+        // task down.
+        if !*is_shut_down {
+            match shutdown.poll() {
+                Ok(Async::NotReady) => (),
+                _ => {
+                    *is_shut_down = true;
+                    receiver.close();
+                }
+            }
+        }
+
         use self::ConnectionState::*;
         loop {
             let state = match *connection_state {
@@ -207,7 +333,10 @@ impl Future for ProxyTask {
                 }
                 Connecting(ref mut connector) => {
                     match connector.poll() {
-                        Ok(Async::Ready(connection)) => Connected(connection),
+                        Ok(Async::Ready(connection)) => {
+                            *refresh = options.dns_refresh_interval.map(Refresh::new);
+                            Connected(connection)
+                        }
                         Ok(Async::NotReady) => return Ok(Async::NotReady),
                         Err(error) => {
                             // Connecting to the server failed. Fail all buffered and queued
@@ -223,48 +352,62 @@ impl Future for ProxyTask {
                     }
                 }
                 Connected(ref mut conn) => {
+                    let is_retired = match *refresh {
+                        Some(ref mut refresh) => refresh.poll_retired(hostports, conn.addr()),
+                        None => false,
+                    };
+
                     // Send all buffered and queued RPCs. The result of the loop is ok if either
                     // the connection has no more send capacity, or there are no more messages to
                     // send.  If any message fails to send, the result of the loop is the error.
-                    let send_result: Result<(), ()> = loop {
-                        match conn.poll_ready() {
-                            // The connection has capacity to send an RPC.
-                            Ok(Async::Ready(_)) => {
-                                // Take an RPC from the buffer or queue.
-                                let rpc = buffer
-                                    .pop_front()
-                                    .map(|rpc| Ok(Async::Ready(Some(rpc))))
-                                    .unwrap_or_else(|| receiver.poll())?;
-
-                                match rpc {
-                                    // Attempt to send the RPC.
-                                    Async::Ready(Some(rpc)) => match conn.send(rpc, now) {
-                                        Ok(()) => continue,
-                                        error => break error,
-                                    },
-
-                                    // No more senders; shutdown if there are no in-flight RPCs.
-                                    Async::Ready(None) => if conn.in_flight_rpcs().is_empty() {
-                                        return Ok(Async::Ready(()));
-                                    } else {
-                                        break Ok(());
-                                    },
-
-                                    // No messages to send.
-                                    Async::NotReady => break Ok(()),
+                    // Nothing is sent on a retired connection.
+                    let send_result: Result<(), ()> = if is_retired {
+                        Ok(())
+                    } else {
+                        loop {
+                            match conn.poll_ready() {
+                                // The connection has capacity to send an RPC.
+                                Ok(Async::Ready(_)) => {
+                                    // Take an RPC from the buffer or queue.
+                                    let rpc = buffer
+                                        .pop_front()
+                                        .map(|rpc| Ok(Async::Ready(Some(rpc))))
+                                        .unwrap_or_else(|| receiver.poll())?;
+
+                                    match rpc {
+                                        // Attempt to send the RPC.
+                                        Async::Ready(Some(rpc)) => match conn.send(rpc, now) {
+                                            Ok(()) => continue,
+                                            error => break error,
+                                        },
+
+                                        // No more senders; shutdown if there are no in-flight RPCs.
+                                        Async::Ready(None) => if conn.in_flight_rpcs().is_empty() {
+                                            return Ok(Async::Ready(()));
+                                        } else {
+                                            break Ok(());
+                                        },
+
+                                        // No messages to send.
+                                        Async::NotReady => break Ok(()),
+                                    }
                                 }
-                            }
 
-                            // The connection has no remaining capacity.
-                            Ok(Async::NotReady) => break Ok(()),
+                                // The connection has no remaining capacity.
+                                Ok(Async::NotReady) => break Ok(()),
 
-                            // The connection is shutdown.
-                            Err(()) => break Err(()),
+                                // The connection is shutdown.
+                                Err(()) => break Err(()),
+                            }
                         }
                     };
 
                     // Poll the connection in order to complete in-flight RPCs.
                     match send_result.and_then(|_| conn.poll()) {
+                        // Once its in-flight RPCs complete, a retired connection is dropped.
+                        Ok(_) if is_retired && conn.in_flight_rpcs().is_empty() => {
+                            ConnectionState::Quiesced
+                        }
                         Ok(_) => return Ok(Async::NotReady),
                         Err(_) => {
                             buffer.extend(
@@ -307,3 +450,63 @@ impl fmt::Debug for ProxyTask {
         debug.finish()
     }
 }
+
+/// Periodically re-resolves the hostnames of a connected server, in order to detect when the
+/// connection should be retired.
+struct Refresh {
+    interval: Duration,
+    delay: Delay,
+    resolving: FuturesUnordered<Resolve>,
+    addrs: Vec<SocketAddr>,
+    failed: bool,
+    is_retired: bool,
+}
+
+impl Refresh {
+    fn new(interval: Duration) -> Refresh {
+        Refresh {
+            interval,
+            delay: Delay::new(Instant::now() + interval),
+            resolving: FuturesUnordered::new(),
+            addrs: Vec::new(),
+            failed: false,
+            is_retired: false,
+        }
+    }
+
+    /// Returns `true` if a completed re-resolution of the hostnames did not include `addr`. A
+    /// re-resolution in which any hostname fails to resolve never retires the connection.
+    fn poll_retired(&mut self, hostports: &[HostPort], addr: &SocketAddr) -> bool {
+        while !self.is_retired {
+            if self.resolving.is_empty() {
+                match self.delay.poll() {
+                    Ok(Async::Ready(())) => {
+                        self.addrs.clear();
+                        self.failed = false;
+                        for hostport in hostports {
+                            self.resolving.push(connector::resolve(hostport.clone()));
+                        }
+                    }
+                    Ok(Async::NotReady) => return false,
+                    Err(error) => panic!("timer failed: {}", error),
+                }
+            }
+
+            loop {
+                match self.resolving.poll() {
+                    Ok(Async::Ready(Some(addrs))) => self.addrs.extend(addrs),
+                    Ok(Async::Ready(None)) => break,
+                    Ok(Async::NotReady) => return false,
+                    Err(error) => {
+                        warn!("failed to re-resolve server address: {}", error);
+                        self.failed = true;
+                    }
+                }
+            }
+
+            self.is_retired = !self.failed && !self.addrs.contains(addr);
+            self.delay.reset(Instant::now() + self.interval);
+        }
+        true
+    }
+}
@@ -7,6 +7,7 @@ use futures::sync::oneshot;
 
 use Error;
 use RequestBody;
+use RequestId;
 use RpcResult;
 
 /// An in-flight remote procedure call.
@@ -19,6 +20,7 @@ pub(crate) struct Rpc {
     pub required_feature_flags: &'static [u32],
     pub timestamp: Instant,
     pub deadline: Instant,
+    pub request_id: Option<RequestId>,
     pub request: Arc<RequestBody>,
     pub completer: oneshot::Sender<RpcResult>,
 }
@@ -53,6 +55,9 @@ impl fmt::Debug for Rpc {
         if !self.required_feature_flags.is_empty() {
             dbg.field("required_feature_flags", &self.required_feature_flags);
         }
+        if let Some(ref request_id) = self.request_id {
+            dbg.field("request_id", request_id);
+        }
         dbg.field("timestamp", &self.timestamp);
         dbg.field("deadline", &self.deadline);
         dbg.finish()
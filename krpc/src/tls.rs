@@ -0,0 +1,211 @@
+//! TLS encryption of KRPC connections.
+//!
+//! The TLS handshake is tunneled through `TLS_HANDSHAKE` negotiation messages, rather than being
+//! written directly to the socket, so the handshake runs over an in-memory `Channel`. Once the
+//! handshake completes, the channel is attached to the connection's TCP stream, and all
+//! subsequent traffic, including SASL authentication, is encrypted.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use openssl::ssl::{
+    HandshakeError, MidHandshakeSslStream, Ssl, SslContextBuilder, SslMethod, SslMode, SslOptions,
+    SslStream, SslVerifyMode,
+};
+use openssl::x509::{X509VerifyResult, X509};
+use tokio::net::TcpStream;
+
+use Error;
+use Options;
+
+/// Whether connections are encrypted with TLS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EncryptionPolicy {
+    /// Connections are encrypted if the server supports TLS.
+    Optional,
+    /// Connections are always encrypted. Negotiation with servers which do not support TLS
+    /// fails with `Error::Negotiation`.
+    Required,
+    /// Connections are never encrypted. Negotiation with servers which require encryption
+    /// fails.
+    Disabled,
+}
+
+impl Default for EncryptionPolicy {
+    fn default() -> EncryptionPolicy {
+        EncryptionPolicy::Optional
+    }
+}
+
+/// A trusted CA certificate, used to verify the certificates presented by servers during the
+/// TLS handshake.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Certificate {
+    der: Vec<u8>,
+}
+
+impl Certificate {
+    /// Parses a PEM or DER encoded X.509 certificate.
+    pub fn parse(certificate: &[u8]) -> Result<Certificate, Error> {
+        let x509 = if certificate.starts_with(b"-----BEGIN") {
+            X509::from_pem(certificate)
+        } else {
+            X509::from_der(certificate)
+        };
+        let der = x509.and_then(|x509| x509.to_der()).map_err(|error| {
+            Error::Serialization(format!("failed to parse certificate: {}", error))
+        })?;
+        Ok(Certificate { der })
+    }
+
+    /// Returns the DER encoding of the certificate.
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+}
+
+impl fmt::Debug for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Certificate({} bytes)", self.der.len())
+    }
+}
+
+/// The stream underlying a TLS session.
+///
+/// During the handshake, the channel has no TCP stream: handshake messages received from the
+/// server are read from `received`, and messages to the server are buffered in `sent`. After the
+/// handshake the channel reads from and writes to the connection's TCP stream.
+pub(crate) struct Channel {
+    received: Vec<u8>,
+    sent: Vec<u8>,
+    pub(crate) stream: Option<TcpStream>,
+}
+
+impl Read for Channel {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.received.is_empty() {
+            let n = (&self.received[..]).read(buf)?;
+            self.received.drain(..n);
+            return Ok(n);
+        }
+        match self.stream {
+            Some(ref mut stream) => stream.read(buf),
+            None => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+        }
+    }
+}
+
+impl Write for Channel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.stream {
+            Some(ref mut stream) => stream.write(buf),
+            None => {
+                self.sent.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.stream {
+            Some(ref mut stream) => stream.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An established TLS session.
+pub(crate) type Session = SslStream<Channel>;
+
+/// An in-progress client-side TLS handshake.
+pub(crate) struct Handshake(MidHandshakeSslStream<Channel>);
+
+/// The result of a handshake step, along with the handshake message to send to the server, which
+/// may be empty once the handshake is complete.
+pub(crate) enum Progress {
+    Continue(Handshake, Vec<u8>),
+    Complete(Box<Session>, Vec<u8>),
+}
+
+impl Handshake {
+    /// Begins a TLS handshake, returning the initial handshake message.
+    ///
+    /// If the options contain trusted certificates, the server's certificate must be signed by
+    /// one of them. Otherwise the server's certificate is not verified, as with the C++ client
+    /// when it has no trusted certificates, and the server is authenticated by SASL instead.
+    pub fn initiate(options: &Options) -> Result<Progress, Error> {
+        let ssl = || -> Result<Ssl, ::openssl::error::ErrorStack> {
+            let mut context = SslContextBuilder::new(SslMethod::tls())?;
+            context.set_options(
+                SslOptions::ALL
+                    | SslOptions::NO_COMPRESSION
+                    | SslOptions::NO_SSLV2
+                    | SslOptions::NO_SSLV3
+                    | SslOptions::NO_TLSV1
+                    | SslOptions::NO_TLSV1_1,
+            );
+            context.set_mode(
+                SslMode::AUTO_RETRY
+                    | SslMode::ACCEPT_MOVING_WRITE_BUFFER
+                    | SslMode::ENABLE_PARTIAL_WRITE,
+            );
+            if options.trusted_certificates.is_empty() {
+                context.set_verify(SslVerifyMode::NONE);
+            } else {
+                for certificate in &options.trusted_certificates {
+                    context
+                        .cert_store_mut()
+                        .add_cert(X509::from_der(certificate.der())?)?;
+                }
+                // Kudu server certificates are issued for the server's UUID rather than its
+                // hostname, so only the chain is verified.
+                context.set_verify(SslVerifyMode::PEER);
+            }
+            Ssl::new(&context.build())
+        }().map_err(|error| Error::Negotiation(format!("failed to initialize TLS: {}", error)))?;
+
+        let channel = Channel {
+            received: Vec::new(),
+            sent: Vec::new(),
+            stream: None,
+        };
+        progress(ssl.connect(channel))
+    }
+
+    /// Continues the handshake with a handshake message received from the server.
+    pub fn step(mut self, message: &[u8]) -> Result<Progress, Error> {
+        self.0.get_mut().received.extend_from_slice(message);
+        progress(self.0.handshake())
+    }
+}
+
+fn progress(
+    result: Result<SslStream<Channel>, HandshakeError<Channel>>,
+) -> Result<Progress, Error> {
+    match result {
+        Ok(mut session) => {
+            let message = session.get_mut().sent.split_off(0);
+            Ok(Progress::Complete(Box::new(session), message))
+        }
+        Err(HandshakeError::WouldBlock(mut mid)) => {
+            let message = mid.get_mut().sent.split_off(0);
+            Ok(Progress::Continue(Handshake(mid), message))
+        }
+        Err(HandshakeError::Failure(mid)) => {
+            let verify_result = mid.ssl().verify_result();
+            Err(Error::Negotiation(if verify_result == X509VerifyResult::OK {
+                format!("TLS handshake failed: {}", mid.error())
+            } else {
+                format!(
+                    "TLS handshake failed: {}: certificate verification failed: {}",
+                    mid.error(),
+                    verify_result
+                )
+            }))
+        }
+        Err(HandshakeError::SetupFailure(error)) => Err(Error::Negotiation(format!(
+            "TLS handshake failed: {}",
+            error
+        ))),
+    }
+}
@@ -1,4 +1,5 @@
 use std::io::{self, Read, Write};
+use std::mem;
 use std::net::{Shutdown, SocketAddr};
 use std::time::Duration;
 use std::u32;
@@ -10,9 +11,11 @@ use prost::{decode_length_delimiter, length_delimiter_len, Message};
 use tokio::net::{ConnectFuture, TcpStream};
 
 use pb::rpc::{ErrorStatusPb, RemoteMethodPb, RequestHeader, ResponseHeader};
+use tls::Session;
 use Error;
 use Options;
 use RequestBody;
+use RequestId;
 use RpcError;
 use RpcErrorCode;
 
@@ -21,18 +24,69 @@ const BACKPRESSURE_BOUNDARY: usize = INITIAL_CAPACITY;
 
 pub type TransportResponse = (Bytes, Vec<BytesMut>);
 
+/// The stream underlying a transport, which is encrypted once TLS is negotiated.
+enum Stream {
+    Tcp(TcpStream),
+    Tls(Box<Session>),
+    /// Placeholder while switching from a TCP to a TLS stream.
+    Switching,
+}
+
+impl Stream {
+    fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match *self {
+            Stream::Tcp(ref stream) => stream.shutdown(how),
+            Stream::Tls(ref session) => match session.get_ref().stream {
+                Some(ref stream) => stream.shutdown(how),
+                None => Ok(()),
+            },
+            Stream::Switching => Ok(()),
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Tcp(ref mut stream) => stream.read(buf),
+            Stream::Tls(ref mut session) => session.read(buf),
+            Stream::Switching => unreachable!(),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Tcp(ref mut stream) => stream.write(buf),
+            Stream::Tls(ref mut session) => session.write(buf),
+            Stream::Switching => unreachable!(),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Stream::Tcp(ref mut stream) => stream.flush(),
+            Stream::Tls(ref mut session) => session.flush(),
+            Stream::Switching => unreachable!(),
+        }
+    }
+}
+
 /// `Transport` handles sending and receiving raw KRPC messages to a TCP stream.
 ///
 /// The transport manages send and receive buffers, encoding and decoding of messages, message
 /// framing, headers, and RPC errors.
 ///
-/// The transport wraps a single TCP connection. When the TCP connection is shutdown or fails, the
-/// transport should no longer be used. TCP connection shutdown is indicated by a fatal error being
-/// returned from `poll_ready()`, `send()`, or `poll()`.
+/// The transport wraps a single TCP connection, which is encrypted if TLS is negotiated. When the
+/// TCP connection is shutdown or fails, the transport should no longer be used. TCP connection
+/// shutdown is indicated by a fatal error being returned from `poll_ready()`, `send()`, or
+/// `poll()`.
 pub(crate) struct Transport {
     addr: SocketAddr,
+    host: String,
     options: Options,
-    stream: TcpStream,
+    stream: Stream,
     send_buf: BytesMut,
     recv_buf: BytesMut,
     request_header: RequestHeader,
@@ -41,10 +95,13 @@ pub(crate) struct Transport {
 
 impl Transport {
     /// Returns a future which will yield a new transport.
-    pub fn connect(addr: SocketAddr, options: Options) -> TransportNew {
+    ///
+    /// `host` is the hostname or IP address which `addr` was resolved from.
+    pub fn connect(addr: SocketAddr, host: String, options: Options) -> TransportNew {
         let connect = TcpStream::connect(&addr);
         TransportNew {
             addr,
+            host,
             options,
             connect,
         }
@@ -81,12 +138,14 @@ impl Transport {
     ///
     /// If a fatal error is returned the transport is shut down. If a non-fatal error is returned,
     /// the RPC should be failed.
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn send(
         &mut self,
         call_id: i32,
         service: &str,
         method: &str,
         required_feature_flags: &[u32],
+        request_id: Option<&RequestId>,
         body: &RequestBody,
         timeout: Option<Duration>,
     ) -> Result<(), Error> {
@@ -109,6 +168,7 @@ impl Transport {
             self.request_header
                 .required_feature_flags
                 .extend_from_slice(required_feature_flags);
+            self.request_header.request_id = request_id.cloned();
 
             let header_len = Message::encoded_len(&self.request_header);
             let body_len = body.encoded_len();
@@ -262,7 +322,7 @@ impl Transport {
     /// [1]: https://github.com/tokio-rs/tokio-io/blob/0.1.3/src/framed_write.rs#L202-L225
     ///
     /// An error return indicates a fatal error.
-    fn poll_flush(&mut self) -> Result<Async<()>, io::Error> {
+    pub fn poll_flush(&mut self) -> Result<Async<()>, io::Error> {
         while !self.send_buf.is_empty() {
             let n = try_nb!(self.stream.write(&self.send_buf));
 
@@ -276,10 +336,28 @@ impl Transport {
         Ok(Async::Ready(()))
     }
 
+    /// Encrypts all subsequent traffic with an established TLS session.
+    ///
+    /// Must only be called while no bytes are buffered, since buffered bytes were (or will be)
+    /// exchanged in the clear.
+    pub fn start_tls(&mut self, mut session: Box<Session>) {
+        debug_assert!(self.send_buf.is_empty());
+        debug_assert!(self.recv_buf.is_empty());
+        match mem::replace(&mut self.stream, Stream::Switching) {
+            Stream::Tcp(stream) => session.get_mut().stream = Some(stream),
+            _ => unreachable!("TLS is already started"),
+        }
+        self.stream = Stream::Tls(session);
+    }
+
     pub fn addr(&self) -> &SocketAddr {
         &self.addr
     }
 
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
     pub fn options(&self) -> &Options {
         &self.options
     }
@@ -297,6 +375,7 @@ impl Transport {
 /// is connected.
 pub(crate) struct TransportNew {
     addr: SocketAddr,
+    host: String,
     options: Options,
     connect: ConnectFuture,
 }
@@ -308,6 +387,7 @@ impl Future for TransportNew {
     fn poll(&mut self) -> Result<Async<Transport>, io::Error> {
         let stream = try_ready!(self.connect.poll());
         stream.set_nodelay(self.options.nodelay)?;
+        stream.set_keepalive(self.options.keep_alive_interval)?;
 
         // Write the connection header to the send buffer.
         let mut send_buf = BytesMut::with_capacity(INITIAL_CAPACITY);
@@ -315,8 +395,9 @@ impl Future for TransportNew {
 
         Ok(Async::Ready(Transport {
             addr: self.addr,
+            host: self.host.clone(),
             options: self.options.clone(),
-            stream,
+            stream: Stream::Tcp(stream),
             send_buf,
             recv_buf: BytesMut::with_capacity(INITIAL_CAPACITY),
             request_header: RequestHeader::default(),
@@ -0,0 +1,214 @@
+use std::cmp::Ordering;
+
+use DataType;
+use Datum;
+use Error;
+use Result;
+use RowBatch;
+use Schema;
+
+/// Describes the aggregates computed over a column by `Scan::aggregate`.
+///
+/// The number of rows is always computed. The sum, minimum, and maximum are only computed if
+/// requested, and skip null values.
+#[derive(Clone, Debug)]
+pub struct AggSpec {
+    column: String,
+    sum: bool,
+    min: bool,
+    max: bool,
+}
+
+impl AggSpec {
+    /// Creates a spec aggregating the projected column with the name.
+    pub fn new<S>(column: S) -> AggSpec
+    where
+        S: Into<String>,
+    {
+        AggSpec {
+            column: column.into(),
+            sum: false,
+            min: false,
+            max: false,
+        }
+    }
+
+    /// Computes the sum of the column, which must be an integer or floating point column.
+    /// Integer columns are summed as an `Int64`, and floating point columns as a `Double`.
+    pub fn sum(mut self) -> AggSpec {
+        self.sum = true;
+        self
+    }
+
+    /// Computes the minimum value of the column.
+    pub fn min(mut self) -> AggSpec {
+        self.min = true;
+        self
+    }
+
+    /// Computes the maximum value of the column.
+    pub fn max(mut self) -> AggSpec {
+        self.max = true;
+        self
+    }
+}
+
+/// The result of aggregating a scan with `Scan::aggregate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggResult {
+    count: usize,
+    sum: Option<Datum>,
+    min: Option<Datum>,
+    max: Option<Datum>,
+}
+
+impl AggResult {
+    /// Returns the number of rows scanned, including rows in which the column is null.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the sum of the non-null values, or `None` if the sum was not requested or every
+    /// value is null.
+    pub fn sum(&self) -> Option<&Datum> {
+        self.sum.as_ref()
+    }
+
+    /// Returns the minimum non-null value, or `None` if the minimum was not requested or every
+    /// value is null.
+    pub fn min(&self) -> Option<&Datum> {
+        self.min.as_ref()
+    }
+
+    /// Returns the maximum non-null value, or `None` if the maximum was not requested or every
+    /// value is null.
+    pub fn max(&self) -> Option<&Datum> {
+        self.max.as_ref()
+    }
+}
+
+/// Accumulates the aggregates of an `AggSpec` over the batches of a scan.
+pub(crate) struct Aggregator {
+    spec: AggSpec,
+    idx: usize,
+    result: AggResult,
+}
+
+impl Aggregator {
+    /// Creates an aggregator for scans with the projected schema.
+    ///
+    /// Returns an error if the column is not projected, or a sum is requested over a column
+    /// which is not an integer or floating point column.
+    pub fn new(spec: AggSpec, projected_schema: &Schema) -> Result<Aggregator> {
+        let idx = projected_schema.column_index(&spec.column).ok_or_else(|| {
+            Error::InvalidArgument(format!("column {} is not projected", spec.column))
+        })?;
+        let data_type = projected_schema.columns()[idx].data_type();
+        let is_numeric = match data_type {
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float
+            | DataType::Double => true,
+            _ => false,
+        };
+        if spec.sum && !is_numeric {
+            return Err(Error::InvalidArgument(format!(
+                "column {} of type {} can not be summed",
+                spec.column, data_type
+            )));
+        }
+        Ok(Aggregator {
+            spec,
+            idx,
+            result: AggResult {
+                count: 0,
+                sum: None,
+                min: None,
+                max: None,
+            },
+        })
+    }
+
+    pub fn add_batch(&mut self, batch: &RowBatch) -> Result<()> {
+        self.result.count += batch.num_rows();
+        if !self.spec.sum && !self.spec.min && !self.spec.max {
+            return Ok(());
+        }
+        for row in batch {
+            let value = row.get_datum(self.idx)?;
+            if value == Datum::Null {
+                continue;
+            }
+            if self.spec.sum {
+                self.result.sum = Some(add(self.result.sum.take(), &value)?);
+            }
+            if self.spec.min && is_better(&self.result.min, &value, Ordering::Less) {
+                self.result.min = Some(value.clone());
+            }
+            if self.spec.max && is_better(&self.result.max, &value, Ordering::Greater) {
+                self.result.max = Some(value);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> AggResult {
+        self.result
+    }
+}
+
+/// Adds a non-null numeric value to the running sum.
+fn add(sum: Option<Datum>, value: &Datum) -> Result<Datum> {
+    let overflow = || Error::InvalidArgument("sum overflows an Int64".to_owned());
+    let int = match *value {
+        Datum::Int8(value) => i64::from(value),
+        Datum::Int16(value) => i64::from(value),
+        Datum::Int32(value) => i64::from(value),
+        Datum::Int64(value) => value,
+        Datum::Float(value) => return Ok(add_double(sum, f64::from(value))),
+        Datum::Double(value) => return Ok(add_double(sum, value)),
+        _ => unreachable!("sum of non-numeric value: {:?}", value),
+    };
+    match sum {
+        Some(Datum::Int64(sum)) => sum.checked_add(int).map(Datum::Int64).ok_or_else(overflow),
+        _ => Ok(Datum::Int64(int)),
+    }
+}
+
+fn add_double(sum: Option<Datum>, value: f64) -> Datum {
+    match sum {
+        Some(Datum::Double(sum)) => Datum::Double(sum + value),
+        _ => Datum::Double(value),
+    }
+}
+
+/// Returns `true` if the value should replace the current extreme, which is the case if there
+/// is no current extreme, or if the value compares to it with the ordering. Values which can not
+/// be compared, such as `NaN`, never replace the current extreme.
+fn is_better(current: &Option<Datum>, value: &Datum, ordering: Ordering) -> bool {
+    let current = match *current {
+        Some(ref current) => current,
+        None => return compare(value, value).is_some(),
+    };
+    compare(value, current) == Some(ordering)
+}
+
+/// Compares two non-null values of the same type.
+fn compare(a: &Datum, b: &Datum) -> Option<Ordering> {
+    match (a, b) {
+        (&Datum::Bool(ref a), &Datum::Bool(ref b)) => a.partial_cmp(b),
+        (&Datum::Int8(ref a), &Datum::Int8(ref b)) => a.partial_cmp(b),
+        (&Datum::Int16(ref a), &Datum::Int16(ref b)) => a.partial_cmp(b),
+        (&Datum::Int32(ref a), &Datum::Int32(ref b)) => a.partial_cmp(b),
+        (&Datum::Int64(ref a), &Datum::Int64(ref b)) => a.partial_cmp(b),
+        (&Datum::Timestamp(ref a), &Datum::Timestamp(ref b)) => a.partial_cmp(b),
+        (&Datum::Float(ref a), &Datum::Float(ref b)) => a.partial_cmp(b),
+        (&Datum::Double(ref a), &Datum::Double(ref b)) => a.partial_cmp(b),
+        (&Datum::Binary(ref a), &Datum::Binary(ref b)) => a.partial_cmp(b),
+        (&Datum::String(ref a), &Datum::String(ref b)) => a.partial_cmp(b),
+        (&Datum::Date(ref a), &Datum::Date(ref b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
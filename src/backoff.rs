@@ -1,6 +1,8 @@
 use std::cmp;
 use std::time::Duration;
 
+use rand::distributions::uniform::SampleUniform;
+use rand::rngs::StdRng;
 use rand::{self, Rng};
 
 /// A randomized exponential backoff policy for retrying operations.
@@ -18,6 +20,17 @@ pub struct Backoff {
 
     /// Number of retries since last reset.
     retries: u32,
+
+    /// Maximum number of attempts before giving up, or `None` if attempts are only limited by
+    /// the operation's deadline.
+    max_attempts: Option<usize>,
+
+    /// Whether backoffs are drawn from zero up to the exponential backoff duration, instead of
+    /// from one to two times the duration.
+    full_jitter: bool,
+
+    /// The random number generator, if seeded. Otherwise the thread-local generator is used.
+    rng: Option<StdRng>,
 }
 
 impl Backoff {
@@ -33,20 +46,64 @@ impl Backoff {
             initial,
             max,
             retries: 0,
+            max_attempts: None,
+            full_jitter: false,
+            rng: None,
         }
     }
 
+    /// Enables full jitter, which draws each backoff uniformly from zero up to the exponential
+    /// backoff duration. Many clients retrying at the same time are spread out more evenly than
+    /// with the default jitter, which only ranges from one to two times the duration.
+    pub fn with_full_jitter(mut self, full_jitter: bool) -> Backoff {
+        self.full_jitter = full_jitter;
+        self
+    }
+
+    /// Seeds the random number generator, so that the backoff durations are reproducible.
+    #[cfg(test)]
+    pub fn with_seed(mut self, seed: u64) -> Backoff {
+        use byteorder::{ByteOrder, LittleEndian};
+        use rand::SeedableRng;
+
+        let mut bytes = [0u8; 32];
+        LittleEndian::write_u64(&mut bytes, seed);
+        self.rng = Some(StdRng::from_seed(bytes));
+        self
+    }
+
+    /// Sets the maximum number of attempts, including the initial attempt, which may be made
+    /// before the operation gives up.
+    pub fn with_max_attempts(mut self, max_attempts: Option<usize>) -> Backoff {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Returns the maximum number of attempts, if limited.
+    pub fn max_attempts(&self) -> Option<usize> {
+        self.max_attempts
+    }
+
     /// Retrieves the next backoff duration in milliseconds.
     pub fn next_backoff_ms(&mut self) -> u64 {
         // Prevent overflow by testing if the backoff will be greater than the
-        // max in an arithmeticaly stable manner, and if so return the max.
+        // max in an arithmeticaly stable manner, and if so use the max.
         if (f64::from(self.max) / f64::from(self.initial)).log2() < f64::from(self.retries) {
-            return u64::from(self.max);
+            let max = u64::from(self.max);
+            return if self.full_jitter {
+                self.gen_range(0, max + 1)
+            } else {
+                max
+            };
         }
 
-        let rand = rand::thread_rng().gen_range::<f64>(1.0, 2.0);
-        let duration = ((u64::from(self.initial) * 2u64.pow(self.retries)) as f64 * rand) as u64;
-        let ms = cmp::min(u64::from(self.max), duration);
+        let exponential = u64::from(self.initial) * 2u64.pow(self.retries);
+        let ms = if self.full_jitter {
+            self.gen_range(0, exponential + 1)
+        } else {
+            let rand = self.gen_range::<f64>(1.0, 2.0);
+            cmp::min(u64::from(self.max), (exponential as f64 * rand) as u64)
+        };
         self.retries += 1;
         ms
     }
@@ -54,6 +111,16 @@ impl Backoff {
     pub fn next_backoff(&mut self) -> Duration {
         Duration::from_millis(self.next_backoff_ms())
     }
+
+    fn gen_range<T>(&mut self, low: T, high: T) -> T
+    where
+        T: PartialOrd + SampleUniform,
+    {
+        match self.rng {
+            Some(ref mut rng) => rng.gen_range(low, high),
+            None => rand::thread_rng().gen_range(low, high),
+        }
+    }
 }
 
 impl Default for Backoff {
@@ -89,4 +156,29 @@ mod tests {
         let f = backoff.next_backoff_ms();
         assert!(f >= 18 && e <= 18);
     }
+
+    #[test]
+    fn test_full_jitter() {
+        let mut backoff = Backoff::with_duration_range(10, 1000)
+            .with_full_jitter(true)
+            .with_seed(42);
+
+        let mut delays = Vec::new();
+        for retry in 0..10 {
+            let delay = backoff.next_backoff_ms();
+            assert!(delay <= cmp::min(1000, 10 << retry), "retry {}: {}", retry, delay);
+            delays.push(delay);
+        }
+        assert!(delays.windows(2).any(|pair| pair[0] != pair[1]));
+
+        // The same seed reproduces the delays, and another seed does not.
+        let mut same = Backoff::with_duration_range(10, 1000)
+            .with_full_jitter(true)
+            .with_seed(42);
+        assert_eq!(delays, (0..10).map(|_| same.next_backoff_ms()).collect::<Vec<_>>());
+        let mut other = Backoff::with_duration_range(10, 1000)
+            .with_full_jitter(true)
+            .with_seed(7);
+        assert_ne!(delays, (0..10).map(|_| other.next_backoff_ms()).collect::<Vec<_>>());
+    }
 }
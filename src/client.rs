@@ -1,28 +1,39 @@
+use std::cmp;
 use std::fmt;
 use std::str;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use futures::future::{self, Loop};
+use futures::future::{self, Either, Loop};
 use futures::Future;
 use parking_lot::Mutex;
 use tokio_timer::Delay;
 
 use pb::master::{
     DeleteTableRequestPb, IsAlterTableDoneRequestPb, IsCreateTableDoneRequestPb,
-    ListTablesRequestPb, MasterService, TableIdentifierPb,
+    ListTablesRequestPb, MasterFeatures, MasterService, RecallDeletedTableRequestPb,
+    TableIdentifierPb,
 };
 use pb::ExpectField;
 
 use backoff::Backoff;
 use meta_cache::MetaCache;
-use table::AlterTableBuilder;
+use metrics::MetricsCounters;
+use scanner::ContinuationToken;
+use table::{self, AlterTableBuilder};
 use table::Table;
 use table::TableBuilder;
+use ClientMetrics;
 use Error;
+use HmsConfig;
+use HostPort;
+use HybridTime;
 use IntoMasterAddrs;
+use MasterErrorCode;
 use MasterInfo;
 use Options;
+use Scan;
+use ScanToken;
 use TableId;
 use TabletServerInfo;
 
@@ -30,27 +41,44 @@ use TabletServerInfo;
 ///
 /// Encapsulates the connection to a Kudu cluster. Only a single instance should be used per
 /// application per cluster.
+///
+/// `Client` is cheap to clone. Cloned handles share the same meta cache, connections, and latest
+/// observed timestamp, so a clone may be handed to another task in lieu of wrapping the client in
+/// an `Arc`.
 #[derive(Clone)]
 pub struct Client {
     meta_cache: MetaCache,
-    latest_observed_timestamp: Arc<Mutex<u64>>, // Replace with AtomicU64 when stable.
+    latest_observed_timestamp: Arc<Mutex<HybridTime>>, // Replace with AtomicU64 when stable.
 }
 
 impl Client {
     /// Creates a new client with the provided configuration.
+    ///
+    /// Returns `Error::InvalidArgument` if `master_addresses` is empty.
     pub fn new<Addrs>(
         master_addresses: Addrs,
-        options: Options,
+        mut options: Options,
     ) -> impl Future<Item = Client, Error = Error>
     where
         Addrs: IntoMasterAddrs,
     {
-        future::result(master_addresses.into_master_addrs())
-            .and_then(|master_addresses| MetaCache::new(master_addresses, options))
-            .map(move |meta_cache| Client {
-                meta_cache,
-                latest_observed_timestamp: Arc::new(Mutex::new(0)),
-            })
+        // Each client counts from zero, even if it shares options with another client. The
+        // counters wrap any application-provided RPC callbacks.
+        let counters = Arc::new(MetricsCounters::new(options.rpc.metrics.take()));
+        options.rpc.metrics = Some(counters.clone());
+        options.counters = counters;
+
+        future::result(master_addresses.into_master_addrs().and_then(|master_addresses| {
+            if master_addresses.is_empty() {
+                Err(Error::InvalidArgument("no master addresses".to_string()))
+            } else {
+                Ok(master_addresses)
+            }
+        })).and_then(|master_addresses| MetaCache::new(master_addresses, options))
+        .map(move |meta_cache| Client {
+            meta_cache,
+            latest_observed_timestamp: Arc::new(Mutex::new(HybridTime::MIN)),
+        })
     }
 
     /// Creates a new Kudu table with the schema and options specified by `builder`. Returns the
@@ -128,15 +156,50 @@ impl Client {
 
     /// Deletes the table.
     pub fn delete_table_by_id(&mut self, id: TableId) -> impl Future<Item = (), Error = Error> {
-        self.do_delete_table(id.into())
+        self.do_delete_table(id.into(), None)
+    }
+
+    /// Soft-deletes the table, reserving it for the provided period before it is permanently
+    /// deleted.
+    ///
+    /// A soft-deleted table is hidden from `Client::tables` and can not be opened, but may be
+    /// restored with `Client::recall_deleted_table` until the reservation period elapses. The
+    /// period is rounded down to whole seconds. Requires Kudu 1.17 or later.
+    pub fn delete_table_with_reservation<S>(
+        &mut self,
+        table: S,
+        reserve: Duration,
+    ) -> impl Future<Item = (), Error = Error>
+    where
+        S: Into<String>,
+    {
+        let reserve_seconds = cmp::min(reserve.as_secs(), u64::from(u32::max_value())) as u32;
+        self.do_delete_table(table.into().into(), Some(reserve_seconds))
+    }
+
+    /// Restores a soft-deleted table whose reservation period has not yet elapsed.
+    ///
+    /// The table keeps its ID and name. See `Client::delete_table_with_reservation`.
+    pub fn recall_deleted_table(&mut self, id: TableId) -> impl Future<Item = (), Error = Error> {
+        let request = RecallDeletedTableRequestPb {
+            table: id.into(),
+            ..Default::default()
+        };
+        let call = MasterService::recall_deleted_table(Arc::new(request), self.deadline());
+        self.meta_cache.master_rpc(call).map(|_| ())
     }
 
     fn do_delete_table(
         &mut self,
         table: TableIdentifierPb,
+        reserve_seconds: Option<u32>,
     ) -> impl Future<Item = (), Error = Error> {
-        let call =
-            MasterService::delete_table(Arc::new(DeleteTableRequestPb { table }), self.deadline());
+        let request = DeleteTableRequestPb {
+            table,
+            reserve_seconds,
+            ..Default::default()
+        };
+        let call = MasterService::delete_table(Arc::new(request), self.deadline());
         self.meta_cache.master_rpc(call).map(|_| ())
     }
 
@@ -168,31 +231,49 @@ impl Client {
             result,
             mut pb,
             schema,
+            alter_columns,
         } = alter;
         let client = self.clone();
-        future::result(result.map(move |_| {
-            pb.table = identifier;
-            let call = MasterService::alter_table(Arc::new(pb), self.deadline());
-            self.meta_cache.master_rpc(call)
-        })).flatten()
-        .and_then(move |resp| {
-            let table_id = str::from_utf8(resp.table_id())
-                .map_err(|error| Error::Serialization(format!("{}", error)))
-                .and_then(TableId::parse)?;
-
-            // If the table partitioning was altered and there is an existing meta cache for the
-            // table, clear it.
-            if schema.is_some() {
-                // TODO
-                // client.meta_cache.clear_table_locations(table_id);
-            }
+        let meta_cache = self.meta_cache.clone();
+        let deadline = self.deadline();
 
-            Ok((table_id, client))
-        }).and_then(|(table_id, mut client): (TableId, Client)| {
-            client
-                .wait_for_table_alteration(table_id)
-                .map(move |_| table_id)
-        })
+        // Column alterations are validated against the table's current schema.
+        let pb = if alter_columns.is_empty() {
+            Either::A(future::ok(pb))
+        } else {
+            Either::B(
+                self.meta_cache
+                    .open_table(identifier.clone(), deadline)
+                    .and_then(move |current| {
+                        table::fill_alter_column_steps(&mut pb, alter_columns, current.schema())?;
+                        Ok(pb)
+                    }),
+            )
+        };
+
+        future::result(result)
+            .and_then(move |_| pb)
+            .and_then(move |mut pb| {
+                pb.table = identifier;
+                meta_cache.master_rpc(MasterService::alter_table(Arc::new(pb), deadline))
+            }).and_then(move |resp| {
+                let table_id = str::from_utf8(resp.table_id())
+                    .map_err(|error| Error::Serialization(format!("{}", error)))
+                    .and_then(TableId::parse)?;
+
+                // If the table partitioning was altered and there is an existing meta cache for
+                // the table, clear it.
+                if schema.is_some() {
+                    // TODO
+                    // client.meta_cache.clear_table_locations(table_id);
+                }
+
+                Ok((table_id, client))
+            }).and_then(|(table_id, mut client): (TableId, Client)| {
+                client
+                    .wait_for_table_alteration(table_id)
+                    .map(move |_| table_id)
+            })
     }
 
     /// Returns a future which completes when the table is altered.
@@ -242,7 +323,12 @@ impl Client {
         self.do_list_tables(Default::default())
     }
 
-    /// Lists all tables with the a name matching the provided prefix, and their associated table ID.
+    /// Lists all tables with a name starting with the provided prefix, and their associated table
+    /// ID.
+    ///
+    /// The master filters the tables by name, so that only matching tables are transferred. The
+    /// master's filter matches the prefix anywhere in the name, so the results are additionally
+    /// restricted to names which start with the prefix.
     pub fn tables_with_prefix<S>(
         &mut self,
         name_prefix: S,
@@ -250,9 +336,14 @@ impl Client {
     where
         S: Into<String>,
     {
+        let name_prefix = name_prefix.into();
         self.do_list_tables(Arc::new(ListTablesRequestPb {
-            name_filter: Some(name_prefix.into()),
-        }))
+            name_filter: Some(name_prefix.clone()),
+            ..Default::default()
+        })).map(move |mut tables| {
+            tables.retain(|&(ref name, _)| name.starts_with(&name_prefix));
+            tables
+        })
     }
 
     fn do_list_tables(
@@ -282,6 +373,14 @@ impl Client {
         })
     }
 
+    /// Returns the address of the leader master, as provided when the client was created.
+    ///
+    /// Every master is asked for its role, so the result reflects the current leader even after
+    /// a failover. Returns `Error::NotFound` if no master is currently the leader.
+    pub fn leader_master_address(&self) -> impl Future<Item = HostPort, Error = Error> {
+        self.meta_cache.leader_master(self.deadline())
+    }
+
     pub fn tablet_servers(&mut self) -> impl Future<Item = Vec<TabletServerInfo>, Error = Error> {
         let call = MasterService::list_tablet_servers(Default::default(), self.deadline());
 
@@ -294,6 +393,53 @@ impl Client {
         })
     }
 
+    /// Returns the cluster's Hive Metastore integration configuration, or `None` if the cluster
+    /// is not integrated with the Hive Metastore.
+    pub fn hive_metastore_config(
+        &mut self,
+    ) -> impl Future<Item = Option<HmsConfig>, Error = Error> {
+        let mut call = MasterService::connect_to_master(Default::default(), self.deadline());
+        call.set_required_feature_flags(&[MasterFeatures::ConnectToMaster as u32]);
+        self.meta_cache
+            .master_rpc(call)
+            .map(|resp| resp.hms_config.and_then(HmsConfig::from_pb))
+    }
+
+    /// Checks that the leader master is reachable, for example as a readiness probe.
+    ///
+    /// Sends a lightweight `ConnectToMaster` RPC to the leader master, retrying while the masters
+    /// are unreachable or no leader is elected. Fails with `Error::TimedOut` if the leader master
+    /// does not respond within the admin operation timeout.
+    pub fn ping(&self) -> impl Future<Item = (), Error = Error> {
+        let deadline = self.deadline();
+        let state = (self.clone(), Backoff::with_duration_range(32, 2048));
+        future::loop_fn(state, move |(client, mut backoff)| {
+            let mut call = MasterService::connect_to_master(Default::default(), deadline);
+            call.set_required_feature_flags(&[MasterFeatures::ConnectToMaster as u32]);
+            client.meta_cache.master_rpc(call).then(move |result| {
+                let error = match result {
+                    Ok(_) => return Either::A(future::ok(Loop::Break(()))),
+                    Err(error) => error,
+                };
+                if !is_unreachable(&error) {
+                    return Either::A(future::err(error));
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    return Either::A(future::err(Error::TimedOut));
+                }
+                debug!("ping failed, retrying: {}", error);
+                let retry = cmp::min(now + backoff.next_backoff(), deadline);
+                Either::B(
+                    Delay::new(retry)
+                        .map_err(|error| -> Error {
+                            panic!("timer failed: {}", error);
+                        }).map(move |_| Loop::Continue((client, backoff))),
+                )
+            })
+        })
+    }
+
     /// Returns an open table.
     pub fn open_table<S>(&mut self, table: S) -> impl Future<Item = Table, Error = Error>
     where
@@ -308,22 +454,101 @@ impl Client {
         self.meta_cache.open_table(id.into(), self.deadline())
     }
 
-    pub fn latest_observed_timestamp(&self) -> u64 {
+    /// Creates a scan from a serialized scan token.
+    ///
+    /// The token's table is opened by ID, so the scan fails with `Error::NotFound` if the table
+    /// has since been dropped, even if another table with the same name has been created. Renamed
+    /// tables are still scanned.
+    ///
+    /// See `ScanBuilder::build_scan_tokens`.
+    pub fn scan_from_token(&mut self, token: &[u8]) -> impl Future<Item = Scan, Error = Error> {
+        let mut client = self.clone();
+        future::result(ScanToken::deserialize(token).and_then(|token| {
+            let table_id = token.table_id()?;
+            Ok((token, table_id))
+        })).and_then(move |(token, table_id)| {
+            client
+                .open_table_by_id(table_id)
+                .map_err(move |error| token_table_error(error, table_id))
+                .and_then(move |table| token.into_scan(&table))
+        })
+    }
+
+    /// Resumes a scan from a continuation token.
+    ///
+    /// Like scan tokens, the token's table is opened by ID, and the scan fails with
+    /// `Error::NotFound` if the table has since been dropped.
+    ///
+    /// See `Scan::continuation_token`.
+    pub fn resume_scan(&mut self, token: &[u8]) -> impl Future<Item = Scan, Error = Error> {
+        let mut client = self.clone();
+        future::result(ContinuationToken::deserialize(token).and_then(|token| {
+            let table_id = token.table_id()?;
+            Ok((token, table_id))
+        })).and_then(move |(token, table_id)| {
+            client
+                .open_table_by_id(table_id)
+                .map_err(move |error| token_table_error(error, table_id))
+                .and_then(move |table| token.into_scan(&table))
+        })
+    }
+
+    /// Returns the latest hybrid time observed by the client.
+    pub fn latest_observed_timestamp(&self) -> HybridTime {
         *self.latest_observed_timestamp.lock()
     }
 
-    pub fn observe_timestamp(&self, timestamp: u64) {
+    /// Advances the latest observed hybrid time, if `timestamp` is newer.
+    pub fn observe_timestamp(&self, timestamp: HybridTime) {
         let mut latest = self.latest_observed_timestamp.lock();
         if timestamp > *latest {
             *latest = timestamp;
         }
     }
 
+    /// Returns a snapshot of the client's cumulative counters: RPCs sent, RPC failures, bytes
+    /// scanned, rows written, and meta cache hits and misses.
+    pub fn metrics_snapshot(&self) -> ClientMetrics {
+        self.meta_cache.options().counters.snapshot()
+    }
+
+    /// Shuts down the client once the returned future is polled.
+    ///
+    /// Cached table locations are dropped, and the connections to the masters and tablet servers
+    /// are shut down. RPCs already sent on a connection complete normally, and the future
+    /// completes once every connection has finished its in-flight RPCs and closed. Subsequent
+    /// operations on the client, its clones, and the tables, scans, and writers opened from it
+    /// fail with `Error::ClientClosed`, and scanner keep-alive requests stop. Connections in a
+    /// pool shared with other clients stay open, so tablet server RPCs on them still succeed.
+    pub fn shutdown(self) -> impl Future<Item = (), Error = Error> {
+        future::lazy(move || self.meta_cache.shutdown())
+    }
+
     fn deadline(&self) -> Instant {
         Instant::now() + self.meta_cache.options().admin_timeout
     }
 }
 
+/// Returns `true` if the error indicates that the masters are unreachable or have no leader, in
+/// which case a later attempt may succeed.
+fn is_unreachable(error: &Error) -> bool {
+    match *error {
+        Error::Io(_) | Error::TimedOut | Error::Compound(..) => true,
+        ref error => error.is_retriable(),
+    }
+}
+
+/// Converts the error of opening the table of a scan or continuation token. The master fails with
+/// `TableNotFound` if the table was dropped after the token was built.
+fn token_table_error(error: Error, table_id: TableId) -> Error {
+    match error {
+        Error::Master(ref error) if error.code == MasterErrorCode::TableNotFound => {
+            Error::NotFound(format!("scan token table {} does not exist", table_id))
+        }
+        error => error,
+    }
+}
+
 impl fmt::Debug for Client {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Client")
@@ -339,11 +564,15 @@ mod tests {
     use AlterTableBuilder;
     use Column;
     use DataType;
+    use EncryptionPolicy;
     use RangePartitionBound;
+    use Result;
     use SchemaBuilder;
     use TableBuilder;
+    use WriterConfig;
 
     use env_logger;
+    use futures::Stream;
     use tokio::runtime::current_thread::Runtime;
 
     #[test]
@@ -398,6 +627,321 @@ mod tests {
         );
     }
 
+    #[test]
+    fn delete_table_with_reservation() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("soft_delete", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        runtime
+            .block_on(client.delete_table_with_reservation("soft_delete", Duration::from_secs(600)))
+            .expect("delete_table_with_reservation");
+        assert!(runtime.block_on(client.tables()).expect("tables").is_empty());
+        assert!(runtime.block_on(client.open_table("soft_delete")).is_err());
+
+        runtime
+            .block_on(client.recall_deleted_table(table_id))
+            .expect("recall_deleted_table");
+        assert_eq!(
+            vec![("soft_delete".to_string(), table_id)],
+            runtime.block_on(client.tables()).expect("tables")
+        );
+        let table = runtime
+            .block_on(client.open_table("soft_delete"))
+            .expect("open_table");
+        assert_eq!(table_id, table.id());
+    }
+
+    #[test]
+    fn tables_with_prefix() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        for name in &["metrics_cpu", "metrics_disk", "host_metrics_cpu", "events"] {
+            let mut table_builder = TableBuilder::new(*name, simple_schema());
+            table_builder.set_num_replicas(1);
+            runtime
+                .block_on(client.create_table(table_builder))
+                .expect("create_table");
+        }
+
+        let mut names = runtime
+            .block_on(client.tables_with_prefix("metrics_"))
+            .expect("tables_with_prefix")
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(vec!["metrics_cpu", "metrics_disk"], names);
+
+        assert!(runtime
+            .block_on(client.tables_with_prefix("logs_"))
+            .expect("tables_with_prefix")
+            .is_empty());
+        assert_eq!(4, runtime.block_on(client.tables_with_prefix("")).unwrap().len());
+    }
+
+    #[test]
+    fn table_names_outlive_client() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        for name in &["names_a", "names_b"] {
+            let mut table_builder = TableBuilder::new(*name, simple_schema());
+            table_builder.set_num_replicas(1);
+            runtime
+                .block_on(client.create_table(table_builder))
+                .expect("create_table");
+        }
+
+        // The listed names are owned, so they remain valid once the client and the response they
+        // were decoded from are gone.
+        let tables = runtime.block_on(client.tables()).expect("tables");
+        runtime.block_on(client.shutdown()).expect("shutdown");
+        let mut names = tables.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(vec!["names_a".to_string(), "names_b".to_string()], names);
+    }
+
+    #[test]
+    fn clone_client() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let mut clone = client.clone();
+
+        let mut table_builder = TableBuilder::new("clone_client", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let expected = vec![("clone_client".to_string(), table_id)];
+        assert_eq!(expected, runtime.block_on(client.tables()).expect("tables"));
+        assert_eq!(expected, runtime.block_on(clone.tables()).expect("tables"));
+
+        let timestamp = HybridTime::new(42, 1).unwrap();
+        clone.observe_timestamp(timestamp);
+        assert_eq!(timestamp, client.latest_observed_timestamp());
+    }
+
+    #[test]
+    fn custom_user() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut options = Options::default();
+        options.set_user("custom-user");
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+
+        // The cluster does not expose the user which operations are attributed to, so check that
+        // connections negotiated as the custom user are usable for DDL, writes, and scans.
+        let mut table_builder = TableBuilder::new("custom_user", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                let mut insert = table.schema().new_row();
+                insert.set(0, "key").unwrap();
+                insert.set(1, "val").unwrap();
+                writer.insert(insert);
+                Ok(())
+            })).unwrap();
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .expect("flush");
+        assert_eq!(1, stats.successful_operations());
+
+        let batches = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .expect("scan");
+        assert_eq!(1, batches.iter().map(|batch| batch.num_rows()).sum::<usize>());
+    }
+
+    #[test]
+    fn custom_timeouts() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut options = Options::default();
+        options
+            .set_negotiation_timeout(Duration::from_secs(10))
+            .set_keep_alive_interval(Duration::from_secs(30))
+            .set_default_operation_timeout(Duration::from_secs(5));
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options.clone()))
+            .expect("client");
+        runtime.block_on(client.tables()).expect("tables");
+
+        // No connection can be negotiated within a nanosecond.
+        options.set_negotiation_timeout(Duration::new(0, 1));
+        match runtime.block_on(Client::new(cluster.master_addrs(), options)) {
+            Err(Error::TimedOut) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ping() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut options = Options::default();
+        options.set_default_admin_operation_timeout(Duration::from_secs(3));
+        let client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+        runtime.block_on(client.ping()).expect("ping");
+
+        // Once the master is unreachable, pings time out after the admin operation timeout.
+        cluster.stop_master(0);
+        let start = Instant::now();
+        match runtime.block_on(client.ping()) {
+            Err(Error::TimedOut) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(3), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(10), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn import_authentication_credentials() {
+        use pb::client::AuthenticationCredentialsPb;
+        use prost::Message;
+
+        let mut credentials = Vec::new();
+        AuthenticationCredentialsPb {
+            real_user: Some("imported-user".to_string()),
+            ..Default::default()
+        }.encode(&mut credentials)
+        .unwrap();
+
+        let mut options = Options::default();
+        options
+            .import_authentication_credentials(&credentials)
+            .unwrap();
+        assert_eq!("imported-user", options.rpc.user);
+
+        // Credentials without a real user leave the user unchanged.
+        options.import_authentication_credentials(&[]).unwrap();
+        assert_eq!("imported-user", options.rpc.user);
+
+        assert!(options
+            .import_authentication_credentials(b"\xff\xff\xff")
+            .is_err());
+    }
+
+    #[test]
+    fn encryption_required() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(MiniClusterConfig::default().require_encryption());
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut options = Options::default();
+        options.set_encryption(EncryptionPolicy::Required);
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+        let mut table_builder = TableBuilder::new("encryption_required", simple_schema());
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        assert_eq!(1, runtime.block_on(client.tables()).expect("tables").len());
+
+        // Clients which trust the cluster's CA verify the servers' certificates.
+        let mut call = MasterService::connect_to_master(Default::default(), client.deadline());
+        call.set_required_feature_flags(&[MasterFeatures::ConnectToMaster as u32]);
+        let ca_certs = runtime
+            .block_on(client.meta_cache.master_rpc(call))
+            .expect("connect_to_master")
+            .ca_cert_der;
+        assert!(!ca_certs.is_empty());
+        let mut options = Options::default();
+        options.set_encryption(EncryptionPolicy::Required);
+        for ca_cert in &ca_certs {
+            options.add_trusted_certificate(ca_cert).expect("add_trusted_certificate");
+        }
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+        assert_eq!(1, runtime.block_on(client.tables()).expect("tables").len());
+
+        // Clients which disable encryption can't connect.
+        let mut options = Options::default();
+        options
+            .set_encryption(EncryptionPolicy::Disabled)
+            .set_default_admin_operation_timeout(Duration::from_secs(3));
+        assert!(
+            runtime
+                .block_on(Client::new(cluster.master_addrs(), options))
+                .is_err()
+        );
+
+        // Certificates which can't be parsed are rejected.
+        assert!(Options::default().add_trusted_certificate(b"garbage").is_err());
+    }
+
+    #[cfg(feature = "kerberos")]
+    #[test]
+    fn kerberos_authentication() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(MiniClusterConfig::default().enable_kerberos());
+        let mut runtime = Runtime::new().unwrap();
+
+        // Point the GSSAPI library at the cluster's KDC and ticket cache.
+        for (key, value) in cluster.kdc_env_vars() {
+            ::std::env::set_var(key, value);
+        }
+        cluster.kinit("test-admin");
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let mut table_builder = TableBuilder::new("kerberos_authentication", simple_schema());
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        assert_eq!(1, runtime.block_on(client.tables()).expect("tables").len());
+    }
+
     #[test]
     fn list_servers() {
         let _ = env_logger::try_init();
@@ -418,6 +962,75 @@ mod tests {
         assert_eq!(3, masters.len());
     }
 
+    #[test]
+    fn metrics() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        use krpc;
+        use Metrics;
+        use RaftRole;
+
+        #[derive(Default)]
+        struct CountingMetrics {
+            started: AtomicUsize,
+            completed: AtomicUsize,
+            retried: AtomicUsize,
+        }
+
+        impl Metrics for CountingMetrics {
+            fn on_rpc_start(&self, _service: &str, _method: &str) {
+                self.started.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn on_rpc_complete(
+                &self,
+                _service: &str,
+                _method: &str,
+                _duration: Duration,
+                _result: ::std::result::Result<(), &krpc::Error>,
+            ) {
+                self.completed.fetch_add(1, Ordering::SeqCst);
+            }
+
+            fn on_retry(&self, _service: &str, _method: &str) {
+                self.retried.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(3).num_tservers(0));
+        let mut runtime = Runtime::new().unwrap();
+
+        let metrics = Arc::new(CountingMetrics::default());
+        let mut options = Options::default();
+        options.set_metrics(metrics.clone());
+        let master_addrs = cluster.master_addrs();
+        let mut client = runtime
+            .block_on(Client::new(master_addrs.clone(), options))
+            .expect("client");
+
+        let masters = runtime.block_on(client.masters()).expect("masters");
+        assert!(metrics.started.load(Ordering::SeqCst) > 0);
+        assert!(metrics.completed.load(Ordering::SeqCst) > 0);
+
+        // Stop the leader master, so that the next master RPC fails over to a new leader.
+        let leader = masters
+            .iter()
+            .find(|master| master.role() == RaftRole::Leader)
+            .expect("leader master");
+        let leader_index = master_addrs
+            .iter()
+            .position(|addr| leader.rpc_addrs().contains(addr))
+            .expect("leader master index");
+        cluster.stop_master(leader_index as u32);
+
+        let retried = metrics.retried.load(Ordering::SeqCst);
+        runtime.block_on(client.tables()).expect("tables");
+        assert!(metrics.retried.load(Ordering::SeqCst) > retried);
+    }
+
     #[test]
     fn alter_table() {
         let _ = env_logger::try_init();
@@ -484,4 +1097,371 @@ mod tests {
             .clone();
         assert_eq!(2, schema.columns().len());
     }
+
+    #[test]
+    fn alter_column() {
+        use AlterColumn;
+        use CompressionType;
+        use EncodingType;
+
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(1).num_tservers(1));
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("alter_column", simple_schema());
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let mut alter_builder = AlterTableBuilder::new();
+        alter_builder.alter_column(
+            AlterColumn::new("val")
+                .set_compression(CompressionType::Lz4)
+                .set_encoding(EncodingType::Dictionary),
+        );
+        runtime
+            .block_on(client.alter_table("alter_column", alter_builder))
+            .expect("alter_table");
+
+        let table = runtime
+            .block_on(client.open_table("alter_column"))
+            .expect("open_table");
+        let column = table.schema().column_by_name("val").unwrap();
+        assert_eq!(CompressionType::Lz4, column.compression());
+        assert_eq!(EncodingType::Dictionary, column.encoding());
+
+        // Alterations which are invalid for the column are rejected before reaching the master.
+        let mut alter_builder = AlterTableBuilder::new();
+        alter_builder.alter_column(AlterColumn::new("val").set_encoding(EncodingType::BitShuffle));
+        match runtime.block_on(client.alter_table("alter_column", alter_builder)) {
+            Err(Error::InvalidArgument(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn alter_extra_config() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(1).num_tservers(1));
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("alter_extra_config", simple_schema());
+        table_builder.set_num_replicas(1);
+        table_builder.set_extra_config("kudu.table.history_max_age_sec", "3600");
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let mut alter_builder = AlterTableBuilder::new();
+        alter_builder.set_extra_config("kudu.table.history_max_age_sec", "7200");
+        runtime
+            .block_on(client.alter_table("alter_extra_config", alter_builder))
+            .expect("alter_table");
+
+        let table = runtime
+            .block_on(client.open_table("alter_extra_config"))
+            .expect("open_table");
+        assert_eq!(
+            Some("7200"),
+            table.extra_config("kudu.table.history_max_age_sec")
+        );
+    }
+
+    #[test]
+    fn column_comment() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(1).num_tservers(1));
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(
+                Column::new("key", DataType::Int32)
+                    .set_not_null()
+                    .set_comment("the row key"),
+            ).add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("column_comment", schema);
+        table_builder.set_range_partition_columns(vec!["key"]);
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let table = runtime
+            .block_on(client.open_table("column_comment"))
+            .expect("open_table");
+        let schema = table.schema();
+        assert_eq!("the row key", schema.column_by_name("key").unwrap().comment());
+        assert_eq!("", schema.column_by_name("val").unwrap().comment());
+    }
+
+    #[test]
+    fn connection_pool() {
+        use ConnectionPool;
+
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(1).num_tservers(1));
+        let mut runtime = Runtime::new().unwrap();
+
+        let pool = Arc::new(ConnectionPool::new());
+        let mut options = Options::default();
+        options.set_connection_pool(pool.clone());
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options.clone()))
+            .expect("client");
+        let mut other = runtime
+            .block_on(Client::new(cluster.master_addrs(), options.clone()))
+            .expect("client");
+        options.set_user("other-user");
+        let mut other_user = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("connection_pool", simple_schema());
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        assert!(pool.is_empty());
+
+        let table = runtime
+            .block_on(client.open_table("connection_pool"))
+            .expect("open_table");
+        runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .expect("scan");
+        assert_eq!(1, pool.len());
+
+        // The second client scans through the connection opened by the first.
+        let table = runtime
+            .block_on(other.open_table("connection_pool"))
+            .expect("open_table");
+        runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .expect("scan");
+        assert_eq!(1, pool.len());
+
+        // A client authenticating as a different user negotiates its own connection.
+        let table = runtime
+            .block_on(other_user.open_table("connection_pool"))
+            .expect("open_table");
+        runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .expect("scan");
+        assert_eq!(2, pool.len());
+    }
+
+    #[test]
+    fn connection_pool_outlives_runtime() {
+        use ConnectionPool;
+
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(1).num_tservers(1));
+
+        let pool = Arc::new(ConnectionPool::new());
+        let mut options = Options::default();
+        options.set_connection_pool(pool.clone());
+
+        // The first client, and the connection it opens, run on a runtime which is then dropped.
+        {
+            let mut runtime = Runtime::new().unwrap();
+            let mut client = runtime
+                .block_on(Client::new(cluster.master_addrs(), options.clone()))
+                .expect("client");
+            let mut table_builder =
+                TableBuilder::new("connection_pool_outlives_runtime", simple_schema());
+            table_builder.set_num_replicas(1);
+            runtime
+                .block_on(client.create_table(table_builder))
+                .expect("create_table");
+            let table = runtime
+                .block_on(client.open_table("connection_pool_outlives_runtime"))
+                .expect("open_table");
+            runtime
+                .block_on(future::lazy(|| table.scan_builder().build().collect()))
+                .expect("scan");
+            assert_eq!(1, pool.len());
+        }
+
+        // A client on a new runtime replaces the closed connection.
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+        let table = runtime
+            .block_on(client.open_table("connection_pool_outlives_runtime"))
+            .expect("open_table");
+        runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .expect("scan");
+        assert_eq!(1, pool.len());
+    }
+
+    #[test]
+    fn socket_addr_master_addrs() {
+        use std::net::{SocketAddr, ToSocketAddrs};
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let master_addrs = cluster
+            .master_addrs()
+            .iter()
+            .flat_map(|addr| addr.to_socket_addrs().unwrap())
+            .collect::<Vec<SocketAddr>>();
+
+        let mut client = runtime
+            .block_on(Client::new(master_addrs[0], Options::default()))
+            .expect("client");
+        runtime.block_on(client.tables()).expect("tables");
+
+        match runtime.block_on(Client::new(Vec::<SocketAddr>::new(), Options::default())) {
+            Err(Error::InvalidArgument(_)) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn leader_master_address() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(3).num_tservers(0));
+        let mut runtime = Runtime::new().unwrap();
+
+        let master_addrs = cluster.master_addrs();
+        let client = runtime
+            .block_on(Client::new(master_addrs.clone(), Options::default()))
+            .expect("client");
+
+        let leader = runtime
+            .block_on(client.leader_master_address())
+            .expect("leader_master_address");
+        assert!(master_addrs.contains(&leader), "{:?}", leader);
+    }
+
+    #[test]
+    fn master_failover() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(3).num_tservers(1));
+        let mut runtime = Runtime::new().unwrap();
+
+        let master_addrs = cluster.master_addrs();
+        let mut client = runtime
+            .block_on(Client::new(master_addrs.clone(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("master_failover", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        // Kill the leader master; the remaining masters elect a new leader, which the client
+        // must rediscover.
+        let leader = runtime
+            .block_on(client.leader_master_address())
+            .expect("leader_master_address");
+        let index = master_addrs
+            .iter()
+            .position(|addr| *addr == leader)
+            .unwrap();
+        cluster.stop_master(index as u32);
+
+        let tables = runtime.block_on(client.tables()).expect("tables");
+        assert_eq!(vec![("master_failover".to_string(), table_id)], tables);
+
+        let new_leader = runtime
+            .block_on(client.leader_master_address())
+            .expect("leader_master_address");
+        assert_ne!(leader, new_leader);
+    }
+
+    #[test]
+    fn hive_metastore_config() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        assert_eq!(
+            None,
+            runtime
+                .block_on(client.hive_metastore_config())
+                .expect("hive_metastore_config")
+        );
+    }
+
+    #[test]
+    fn shutdown() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let mut clone = client.clone();
+
+        let mut table_builder = TableBuilder::new("shutdown", simple_schema());
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        let table = runtime
+            .block_on(client.open_table("shutdown"))
+            .expect("open_table");
+        let batches = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .expect("scan");
+        assert!(batches.iter().all(|batch| batch.num_rows() == 0));
+
+        // The shutdown waits for the RPC sent before it.
+        let (tables, ()) = runtime
+            .block_on(clone.tables().join(client.shutdown()))
+            .expect("shutdown");
+        assert_eq!(1, tables.len());
+
+        // The table opened before the shutdown can no longer reach the tablet server.
+        assert!(
+            runtime
+                .block_on(future::lazy(|| table.scan_builder().build().collect()))
+                .is_err()
+        );
+
+        match runtime.block_on(clone.tables()) {
+            Err(Error::ClientClosed) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match runtime.block_on(clone.open_table("shutdown")) {
+            Err(Error::ClientClosed) => (),
+            other => panic!("unexpected result: {:?}", other.map(|table| table.name().to_owned())),
+        }
+    }
 }
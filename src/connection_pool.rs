@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use krpc;
+use parking_lot::Mutex;
+
+use HostPort;
+use TabletServerId;
+
+/// A pool of tablet server connections which may be shared by multiple clients.
+///
+/// By default every client opens and negotiates its own connection to each tablet server. Clients
+/// created with the same pool via `Options::set_connection_pool` instead share a single
+/// connection per tablet server, which avoids reconnecting in processes that create many
+/// short-lived clients.
+///
+/// `ConnectionPool` is `Send` and `Sync`, and is typically shared in an `Arc`. Connections are
+/// only shared by clients which authenticate as the same user with the same encryption policy and
+/// trusted certificates; other clients each negotiate their own connection through the pool. The
+/// remaining RPC options of a connection, such as its negotiation timeout, are those of the
+/// client which first connects to the tablet server. Per-client metrics are unaffected by sharing
+/// connections.
+///
+/// Each connection is driven by a task spawned on the runtime of the client which opened it, and
+/// only makes progress while that runtime is running. Connections whose runtime has been
+/// dropped, and connections to tablet servers which have since moved to different addresses,
+/// are replaced when they are next requested, so a pool may outlive the runtimes of its clients,
+/// as in processes which run each request on a short-lived runtime.
+#[derive(Default)]
+pub struct ConnectionPool {
+    proxies: Mutex<HashMap<ConnectionKey, PooledProxy>>,
+}
+
+/// A pooled connection, and the tablet server addresses it connects to.
+struct PooledProxy {
+    rpc_addrs: Vec<HostPort>,
+    proxy: krpc::Proxy,
+}
+
+impl PooledProxy {
+    fn spawn(rpc_addrs: &[HostPort], options: &krpc::Options) -> PooledProxy {
+        PooledProxy {
+            rpc_addrs: rpc_addrs.to_vec(),
+            proxy: krpc::Proxy::spawn(rpc_addrs.to_vec().into_boxed_slice(), options.clone()),
+        }
+    }
+}
+
+/// Identifies a pooled connection by the tablet server it connects to, and the options with
+/// which it was negotiated.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ConnectionKey {
+    tserver: TabletServerId,
+    user: String,
+    encryption: krpc::EncryptionPolicy,
+    trusted_certificates: Vec<krpc::Certificate>,
+}
+
+impl ConnectionPool {
+    /// Creates a new, empty connection pool.
+    pub fn new() -> ConnectionPool {
+        ConnectionPool::default()
+    }
+
+    /// Returns the number of pooled connections.
+    pub fn len(&self) -> usize {
+        self.proxies.lock().len()
+    }
+
+    /// Returns `true` if the pool holds no connections.
+    pub fn is_empty(&self) -> bool {
+        self.proxies.lock().is_empty()
+    }
+
+    /// Removes and returns the pool's connections.
+    pub(crate) fn drain(&self) -> Vec<krpc::Proxy> {
+        self.proxies
+            .lock()
+            .drain()
+            .map(|(_, pooled)| pooled.proxy)
+            .collect()
+    }
+
+    /// Returns a proxy to the tablet server, connecting with `options` if the pool does not yet
+    /// hold a connection to it which was negotiated with the same options. Pooled connections
+    /// which are closed, or which connect to addresses other than `rpc_addrs`, are replaced. The
+    /// returned proxy reports RPCs to the metrics in `options`.
+    pub(crate) fn proxy(
+        &self,
+        id: TabletServerId,
+        rpc_addrs: &[HostPort],
+        options: &krpc::Options,
+    ) -> krpc::Proxy {
+        let mut proxies = self.proxies.lock();
+        let pooled = proxies
+            .entry(ConnectionKey {
+                tserver: id,
+                user: options.user.clone(),
+                encryption: options.encryption,
+                trusted_certificates: options.trusted_certificates.clone(),
+            }).or_insert_with(|| PooledProxy::spawn(rpc_addrs, options));
+        // Clients which still hold a replaced connection keep using it until they refresh their
+        // tablet locations, so it is dropped from the pool rather than shut down.
+        if pooled.proxy.is_closed() || pooled.rpc_addrs[..] != *rpc_addrs {
+            *pooled = PooledProxy::spawn(rpc_addrs, options);
+        }
+        pooled.proxy.with_metrics(options.metrics.clone())
+    }
+}
+
+impl fmt::Debug for ConnectionPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("connections", &self.len())
+            .finish()
+    }
+}
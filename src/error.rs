@@ -5,7 +5,7 @@ use std::result;
 use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 
-use krpc;
+use krpc::{self, RpcErrorCode};
 use url;
 
 use pb::master::MasterErrorPb;
@@ -52,14 +52,47 @@ pub enum Error {
     /// TODO: change TimedOut to include this info.
     Compound(String, Vec<Error>),
 
+    /// An operation failed with retriable errors more times than its retry budget allows.
+    RetriesExhausted {
+        /// The number of attempts made.
+        attempts: usize,
+        /// The error returned by the final attempt.
+        last_error: Box<Error>,
+    },
+
+    /// An update operation set a value for an immutable column.
+    Immutable {
+        /// The name of the immutable column.
+        column: String,
+    },
+
+    /// The operation failed because the client has been shut down.
+    ClientClosed,
+
+    /// A manually flushed writer has buffered its maximum amount of data, and must be flushed
+    /// before it accepts more operations.
+    BufferFull,
+
+    /// A requested entity, such as a tablet replica, does not exist.
+    NotFound(String),
+
     RowError(Status),
 }
 
 impl Error {
-    pub(crate) fn is_retriable(&self) -> bool {
+    /// Returns `true` if the error is transient, so that the failed operation may succeed if it
+    /// is retried after a backoff period.
+    ///
+    /// Transient errors are returned by servers which are overloaded or unavailable, and by
+    /// replicas which are not the leader or whose tablet is not running. All other errors are
+    /// permanent.
+    pub fn is_retriable(&self) -> bool {
         match *self {
-            Error::Rpc(ref error) => error.is_retriable(),
+            Error::Rpc(ref error) => {
+                error.is_retriable() || error.code == RpcErrorCode::ErrorUnavailable
+            }
             Error::Master(ref error) => error.is_retriable(),
+            Error::TabletServer(ref error) => error.is_retriable(),
             _ => false,
         }
     }
@@ -81,6 +114,19 @@ impl Clone for Error {
                 Error::Compound(description.clone(), errors.clone())
             }
             Error::RowError(ref status) => Error::RowError(status.clone()),
+            Error::RetriesExhausted {
+                attempts,
+                ref last_error,
+            } => Error::RetriesExhausted {
+                attempts,
+                last_error: last_error.clone(),
+            },
+            Error::Immutable { ref column } => Error::Immutable {
+                column: column.clone(),
+            },
+            Error::ClientClosed => Error::ClientClosed,
+            Error::BufferFull => Error::BufferFull,
+            Error::NotFound(ref error) => Error::NotFound(error.clone()),
         }
     }
 }
@@ -97,13 +143,17 @@ impl error::Error for Error {
             Error::TabletServer(ref error) => error.description(),
             Error::Io(ref error) => error.description(),
 
-            Error::Negotiation(ref error) => error,
+            Error::Negotiation(ref error) | Error::NotFound(ref error) => error,
 
             Error::Serialization(ref description) | Error::Compound(ref description, _) => {
                 description
             }
 
             Error::RowError(_) => "row error",
+            Error::RetriesExhausted { .. } => "retries exhausted",
+            Error::Immutable { .. } => "immutable column",
+            Error::ClientClosed => "client closed",
+            Error::BufferFull => "writer buffer full",
         }
     }
 
@@ -114,12 +164,17 @@ impl error::Error for Error {
             | Error::TimedOut
             | Error::Negotiation(_)
             | Error::NoRangePartition
-            | Error::RowError(_) => None,
+            | Error::RowError(_)
+            | Error::Immutable { .. }
+            | Error::ClientClosed
+            | Error::BufferFull
+            | Error::NotFound(_) => None,
             Error::Rpc(ref error) => error.cause(),
             Error::Master(ref error) => error.cause(),
             Error::TabletServer(ref error) => error.cause(),
             Error::Io(ref error) => error.cause(),
             Error::Compound(_, ref errors) => errors.iter().next().map(|error| error as _),
+            Error::RetriesExhausted { ref last_error, .. } => Some(&**last_error),
         }
     }
 }
@@ -132,6 +187,9 @@ impl From<krpc::Error> for Error {
             krpc::Error::Serialization(msg) => Error::Serialization(msg),
             krpc::Error::TimedOut => Error::TimedOut,
             krpc::Error::Negotiation(msg) => Error::Negotiation(msg),
+            krpc::Error::Shutdown => Error::ClientClosed,
+            // Only reported to metrics, never returned from an RPC.
+            krpc::Error::Cancelled => unreachable!("cancelled RPC returned a result"),
         }
     }
 }
@@ -234,6 +292,17 @@ pub struct TabletServerError {
     pub status: Status,
 }
 
+impl TabletServerError {
+    pub fn is_retriable(&self) -> bool {
+        match self.code {
+            TabletServerErrorCode::NotTheLeader
+            | TabletServerErrorCode::TabletNotRunning
+            | TabletServerErrorCode::Throttled => true,
+            _ => false,
+        }
+    }
+}
+
 impl error::Error for TabletServerError {
     fn description(&self) -> &str {
         match self.code {
@@ -257,6 +326,11 @@ impl error::Error for TabletServerError {
             TabletServerErrorCode::AlreadyInprogress => "already in progress",
             TabletServerErrorCode::Throttled => "throttled",
             TabletServerErrorCode::TabletFailed => "tablet failed",
+            TabletServerErrorCode::NotAuthorized => "not authorized",
+            TabletServerErrorCode::TxnIllegalState => "transaction in illegal state",
+            TabletServerErrorCode::TxnOpAlreadyApplied => "transaction operation already applied",
+            TabletServerErrorCode::TxnLockedAbort => "transaction aborted by lock conflict",
+            TabletServerErrorCode::TxnLockedRetryOp => "transaction operation locked",
         }
     }
 
@@ -321,6 +395,7 @@ impl error::Error for MasterError {
             MasterErrorCode::EvenReplicationFactor => "even replication factor",
             MasterErrorCode::IllegalReplicationFactor => "illegal replication factor",
             MasterErrorCode::IncompatibleReplicaManagement => "incompatible replica management",
+            MasterErrorCode::HiveMetastoreError => "Hive Metastore error",
         }
     }
 
@@ -344,3 +419,91 @@ impl From<MasterErrorPb> for MasterError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn error_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Error>();
+        assert_send_sync::<Status>();
+        assert_send_sync::<MasterError>();
+        assert_send_sync::<TabletServerError>();
+
+        let error = Error::Compound(
+            "compound".to_string(),
+            vec![
+                Error::InvalidArgument("invalid".to_string()),
+                Error::Io(io::Error::new(io::ErrorKind::Other, "io")),
+                Error::TimedOut,
+            ],
+        );
+
+        let description = thread::spawn(move || error.to_string())
+            .join()
+            .unwrap();
+        assert!(description.contains("invalid"));
+    }
+
+    #[test]
+    fn is_retriable() {
+        fn rpc_error(code: RpcErrorCode) -> Error {
+            Error::Rpc(krpc::RpcError {
+                code,
+                message: String::new(),
+                unsupported_feature_flags: Vec::new(),
+            })
+        }
+        fn status() -> Status {
+            Status {
+                code: StatusCode::IllegalState,
+                message: None,
+                posix_code: None,
+            }
+        }
+
+        assert!(rpc_error(RpcErrorCode::ErrorServerTooBusy).is_retriable());
+        assert!(rpc_error(RpcErrorCode::ErrorUnavailable).is_retriable());
+        assert!(!rpc_error(RpcErrorCode::ErrorNoSuchMethod).is_retriable());
+        assert!(!rpc_error(RpcErrorCode::ErrorInvalidRequest).is_retriable());
+        assert!(!rpc_error(RpcErrorCode::FatalUnauthorized).is_retriable());
+
+        for &code in &[
+            TabletServerErrorCode::NotTheLeader,
+            TabletServerErrorCode::TabletNotRunning,
+        ] {
+            let status = status();
+            assert!(Error::TabletServer(TabletServerError { code, status }).is_retriable());
+        }
+        let status = status();
+        assert!(
+            !Error::TabletServer(TabletServerError {
+                code: TabletServerErrorCode::TabletNotFound,
+                status,
+            }).is_retriable()
+        );
+
+        let status = status();
+        assert!(
+            Error::Master(MasterError {
+                code: MasterErrorCode::NotTheLeader,
+                status,
+            }).is_retriable()
+        );
+        let status = status();
+        assert!(
+            !Error::Master(MasterError {
+                code: MasterErrorCode::TableNotFound,
+                status,
+            }).is_retriable()
+        );
+
+        assert!(!Error::TimedOut.is_retriable());
+        assert!(!Error::InvalidArgument("invalid".to_string()).is_retriable());
+    }
+}
@@ -7,6 +7,8 @@ use ieee754::Ieee754;
 use bounds::IntoBounds;
 use pb::ColumnPredicatePb;
 use Column;
+use DataType;
+use Datum;
 use Error;
 use PhysicalType;
 use Result;
@@ -44,10 +46,13 @@ impl Filter {
         Filter::None
     }
 
+    /// Creates a filter which matches only null values. The filter may only be applied to
+    /// nullable columns.
     pub fn is_null() -> Filter {
         Filter::IsNull
     }
 
+    /// Creates a filter which matches only non-null values.
     pub fn is_not_null() -> Filter {
         Filter::IsNotNull
     }
@@ -92,12 +97,38 @@ impl Filter {
         V: Value<'data>,
         I: IntoIterator<Item = V>,
     {
-        let physical_type = V::PHYSICAL_TYPE;
-        let mut values = values
+        let values = values
             .into_iter()
             .filter(|value| value.is_comparable())
             .map(Value::encode)
             .collect::<Vec<_>>();
+        Filter::in_list_encoded(V::PHYSICAL_TYPE, values)
+    }
+
+    /// Creates an IN list filter from dynamically typed values. Every value must either be null,
+    /// or be of type `data_type`; null values never match.
+    ///
+    /// Returns an error if a value is of a different type.
+    pub fn in_list_datums<I>(data_type: DataType, values: I) -> Result<Filter>
+    where
+        I: IntoIterator<Item = Datum>,
+    {
+        let mut encoded = Vec::new();
+        for value in values {
+            match value.data_type() {
+                Some(value_type) if value_type != data_type => {
+                    return Err(Error::InvalidArgument(format!(
+                        "IN list value {:?} does not match type {:?}",
+                        value, data_type
+                    )))
+                }
+                _ => encoded.extend(value.encode()),
+            }
+        }
+        Ok(Filter::in_list_encoded(data_type.physical_type(), encoded))
+    }
+
+    fn in_list_encoded(physical_type: PhysicalType, mut values: Vec<Vec<u8>>) -> Filter {
         values.sort_unstable_by(TypeInfo::new(physical_type).cmp);
         values.dedup();
         Filter::In {
@@ -181,6 +212,7 @@ impl Filter {
 
             (Filter::All, other) | (other, Filter::All) => other,
             (Filter::None, _) | (_, Filter::None) => Filter::None,
+            (Filter::IsNull, Filter::IsNull) => Filter::IsNull,
             (Filter::IsNull, _) | (_, Filter::IsNull) => Filter::None,
             (Filter::IsNotNull, other) | (other, Filter::IsNotNull) => other,
             (Filter::Equals { physical_type, value: a, .. }, Filter::Equals { value: b, .. }) => {
@@ -235,13 +267,13 @@ impl Filter {
             | (Filter::In { mut values, .. }, Filter::Range { physical_type, lower_bound, upper_bound, .. }) => {
                 let cmp = TypeInfo::new(physical_type).cmp;
                 if let Some(upper) = upper_bound {
-                    match values.binary_search_by(|probe| cmp(&upper, probe)) {
+                    match values.binary_search_by(|probe| cmp(probe, &upper)) {
                         Ok(idx) => values.truncate(idx),
                         Err(idx) => values.truncate(idx),
                     };
                 }
                 if let Some(lower) = lower_bound {
-                    match values.binary_search_by(|probe| cmp(&lower, probe)) {
+                    match values.binary_search_by(|probe| cmp(probe, &lower)) {
                         Ok(idx) => values.drain(..idx),
                         Err(idx) => values.drain(..idx),
                     };
@@ -253,24 +285,8 @@ impl Filter {
             (Filter::In { physical_type, values: a, .. }, Filter::In { values: b, .. }) => {
                 let cmp = TypeInfo::new(physical_type).cmp;
                 let (mut smaller, larger) = if a.len() < b.len() { (a, b) } else { (b, a) };
-
-                // TODO: this should be using retain
-                let mut i = 0;
-                let mut j = 0;
-                while i != smaller.len() {
-                    match larger[j..].binary_search_by(|probe| cmp(&smaller[i], probe)) {
-                        Ok(idx) => {
-                            i += 1;
-                            j = idx;
-                        }
-                        Err(idx) => {
-                            smaller.remove(i);
-                            j = idx;
-                        }
-                    }
-                }
-
-                Filter::In { physical_type, values: larger }.simplify()
+                smaller.retain(|value| larger.binary_search_by(|probe| cmp(probe, value)).is_ok());
+                Filter::In { physical_type, values: smaller }.simplify()
             }
         }
     }
@@ -320,11 +336,46 @@ impl Filter {
                     physical_type, column
                 )))
             }
+            Filter::IsNull if !column.is_nullable() => Err(Error::InvalidArgument(format!(
+                "IS NULL filter is invalid for non-nullable column {:?}",
+                column.name()
+            ))),
             _ => Ok(()),
         }
     }
 }
 
+/// A filter on a named column.
+///
+/// Predicates are created with the comparison methods on `Column`, for example
+/// `column.greater_than(18i32)`, which check the literal against the column's type. They are
+/// applied to a scan with `ScanBuilder::predicate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Predicate {
+    column: String,
+    filter: Filter,
+}
+
+impl Predicate {
+    pub(crate) fn new(column: String, filter: Filter) -> Predicate {
+        Predicate { column, filter }
+    }
+
+    /// Returns the name of the filtered column.
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// Returns the filter applied to the column.
+    pub fn filter(&self) -> &Filter {
+        &self.filter
+    }
+
+    pub(crate) fn into_parts(self) -> (String, Filter) {
+        (self.column, self.filter)
+    }
+}
+
 struct TypeInfo {
     cmp: fn(&Vec<u8>, &Vec<u8>) -> Ordering,
     are_consecutive: fn(&Vec<u8>, &Vec<u8>) -> bool,
@@ -518,3 +569,51 @@ impl TypeInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_and() {
+        // Ranges merge into the tightest range.
+        assert_eq!(
+            Filter::range(18i32..65),
+            Filter::range(18i32..).and(Filter::range(..65i32))
+        );
+        assert_eq!(
+            Filter::range(20i32..30),
+            Filter::range(10i32..30).and(Filter::range(20i32..40))
+        );
+
+        // Contradictory filters match nothing.
+        assert_eq!(Filter::None, Filter::range(11i32..).and(Filter::range(..5i32)));
+        assert_eq!(Filter::None, Filter::equals(5i32).and(Filter::range(..5i32)));
+        assert_eq!(Filter::None, Filter::is_null().and(Filter::equals(5i32)));
+
+        // IN lists are trimmed to the range.
+        assert_eq!(
+            Filter::in_list(vec![5i32, 10]),
+            Filter::in_list(vec![1i32, 5, 10, 20]).and(Filter::range(5i32..20))
+        );
+        assert_eq!(
+            Filter::in_list(vec![5i32, 10]),
+            Filter::range(5i32..20).and(Filter::in_list(vec![1i32, 5, 10, 20]))
+        );
+
+        // IN lists are intersected.
+        assert_eq!(
+            Filter::in_list(vec![3i32, 4]),
+            Filter::in_list(vec![1i32, 2, 3, 4]).and(Filter::in_list(vec![3i32, 4, 5]))
+        );
+        assert_eq!(
+            Filter::equals(4i32),
+            Filter::in_list(vec![4i32, 5]).and(Filter::in_list(vec![1i32, 2, 3, 4]))
+        );
+        assert_eq!(
+            Filter::None,
+            Filter::in_list(vec![1i32, 2]).and(Filter::in_list(vec![3i32, 4, 5]))
+        );
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::time::SystemTime;
+
+use timestamp::DateTime;
+use util;
+use Error;
+use Result;
+
+/// The number of low-order bits reserved for the logical component of a hybrid time.
+const LOGICAL_BITS: u32 = 12;
+const LOGICAL_MASK: u64 = (1 << LOGICAL_BITS) - 1;
+
+/// A Kudu hybrid time timestamp.
+///
+/// Hybrid times combine a physical clock reading (microseconds since the Unix epoch) with a
+/// logical counter which orders events occurring within the same microsecond. The encoded form
+/// matches the server's: the physical component occupies the upper 52 bits, and the logical
+/// component occupies the lower 12 bits.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HybridTime(u64);
+
+impl HybridTime {
+    /// The minimum hybrid time.
+    pub const MIN: HybridTime = HybridTime(0);
+
+    /// Creates a hybrid time from physical and logical components.
+    ///
+    /// Returns an error if either component is out of range.
+    pub fn new(physical_micros: u64, logical: u64) -> Result<HybridTime> {
+        if physical_micros > (u64::max_value() >> LOGICAL_BITS) {
+            return Err(Error::InvalidArgument(format!(
+                "hybrid time physical component {} is out of range",
+                physical_micros
+            )));
+        }
+        if logical > LOGICAL_MASK {
+            return Err(Error::InvalidArgument(format!(
+                "hybrid time logical component {} is out of range",
+                logical
+            )));
+        }
+        Ok(HybridTime((physical_micros << LOGICAL_BITS) | logical))
+    }
+
+    /// Creates a hybrid time with a zero logical component from the number of microseconds
+    /// since the Unix epoch.
+    ///
+    /// Returns an error if the physical time is out of range.
+    pub fn from_micros(physical_micros: u64) -> Result<HybridTime> {
+        HybridTime::new(physical_micros, 0)
+    }
+
+    /// Creates a hybrid time with a zero logical component from a `SystemTime`.
+    ///
+    /// Returns an error if the time is before the Unix epoch, or is out of range.
+    pub fn from_system_time(time: SystemTime) -> Result<HybridTime> {
+        let micros = util::time_to_us(time);
+        if micros < 0 {
+            return Err(Error::InvalidArgument(format!(
+                "hybrid time may not precede the Unix epoch: {}",
+                DateTime::from(time)
+            )));
+        }
+        HybridTime::from_micros(micros as u64)
+    }
+
+    /// Creates a hybrid time from its encoded representation, as returned by the server.
+    pub fn from_encoded(encoded: u64) -> HybridTime {
+        HybridTime(encoded)
+    }
+
+    /// Returns the encoded representation of the hybrid time, as expected by the server.
+    pub fn encoded(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the physical component of the hybrid time, in microseconds since the Unix epoch.
+    pub fn physical_micros(self) -> u64 {
+        self.0 >> LOGICAL_BITS
+    }
+
+    /// Returns the logical component of the hybrid time.
+    pub fn logical(self) -> u64 {
+        self.0 & LOGICAL_MASK
+    }
+
+    /// Returns the physical component of the hybrid time as a `SystemTime`.
+    pub fn to_system_time(self) -> SystemTime {
+        util::us_to_time(self.physical_micros() as i64)
+    }
+}
+
+impl fmt::Debug for HybridTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "HybridTime({}, {})",
+            DateTime::from(self.to_system_time()),
+            self.logical()
+        )
+    }
+}
+
+impl fmt::Display for HybridTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let ht = HybridTime::from_encoded(0);
+        assert_eq!(HybridTime::MIN, ht);
+        assert_eq!(0, ht.physical_micros());
+        assert_eq!(0, ht.logical());
+        assert_eq!(UNIX_EPOCH, ht.to_system_time());
+
+        let ht = HybridTime::from_encoded(6_204_491_872_079_007_751);
+        assert_eq!(1_514_768_523_456_789, ht.physical_micros());
+        assert_eq!(7, ht.logical());
+        assert_eq!(
+            UNIX_EPOCH + Duration::new(1_514_768_523, 456_789_000),
+            ht.to_system_time()
+        );
+        assert_eq!(
+            "HybridTime(2018-01-01T01:02:03.456789Z, 7)",
+            format!("{:?}", ht)
+        );
+
+        let ht = HybridTime::from_encoded(4095);
+        assert_eq!(0, ht.physical_micros());
+        assert_eq!(4095, ht.logical());
+    }
+
+    #[test]
+    fn test_encode() {
+        let ht = HybridTime::new(1_514_768_523_456_789, 7).unwrap();
+        assert_eq!(6_204_491_872_079_007_751, ht.encoded());
+
+        let ht = HybridTime::from_micros(1_514_768_523_456_789).unwrap();
+        assert_eq!(6_204_491_872_079_007_744, ht.encoded());
+        assert_eq!(
+            ht,
+            HybridTime::from_system_time(ht.to_system_time()).unwrap()
+        );
+
+        assert!(HybridTime::new(0, 4096).is_err());
+        assert!(HybridTime::from_micros(1 << 52).is_err());
+        assert!(HybridTime::from_system_time(UNIX_EPOCH - Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_ord() {
+        let a = HybridTime::new(100, 5).unwrap();
+        let b = HybridTime::new(101, 0).unwrap();
+        let c = HybridTime::new(101, 1).unwrap();
+        assert!(a < b);
+        assert!(b < c);
+    }
+}
@@ -4,7 +4,7 @@
 
 use std::{f32, f64, i16, i32, i64, i8};
 
-use byteorder::{BigEndian, ByteOrder, NativeEndian, WriteBytesExt};
+use byteorder::{BigEndian, ByteOrder, LittleEndian, WriteBytesExt};
 use ieee754::Ieee754;
 
 use partition::PartitionKey;
@@ -19,6 +19,10 @@ use Schema;
 use Value;
 
 /// Murmur2 hash implementation returning 64-bit hashes.
+///
+/// Kudu defines the hash over little-endian 8-byte blocks, so the blocks are read as little-endian
+/// regardless of the host's byte order. Reading them in native order would cause big-endian hosts
+/// to compute different hash buckets than the server.
 pub(crate) fn murmur2_64(mut data: &[u8], seed: u64) -> u64 {
     const M: u64 = 0xc6a4_a793_5bd1_e995;
     const R: u8 = 47;
@@ -26,7 +30,7 @@ pub(crate) fn murmur2_64(mut data: &[u8], seed: u64) -> u64 {
     let mut h: u64 = seed ^ ((data.len() as u64).wrapping_mul(M));
 
     while data.len() >= 8 {
-        let mut k = NativeEndian::read_u64(data);
+        let mut k = LittleEndian::read_u64(data);
 
         k = k.wrapping_mul(M);
         k ^= k >> R;
@@ -73,6 +77,34 @@ pub(crate) fn encode_primary_key(row: &Row) -> Result<Vec<u8>> {
     Ok(buf)
 }
 
+/// Encodes the primary key of a row from a projection which includes the primary key columns.
+/// `key_columns` holds the indexes of the primary key columns in the projection, in primary key
+/// order. The key is written to `buf`, replacing its contents.
+pub(crate) fn encode_projected_primary_key(
+    row: &Row,
+    key_columns: &[usize],
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    buf.clear();
+    encode_columns(row, key_columns.iter().cloned(), buf)
+}
+
+/// Increments an encoded key to the smallest key which is greater than every key it prefixes, by
+/// incrementing the last byte which is not `0xFF` and truncating the trailing `0xFF` bytes.
+///
+/// Returns `false`, leaving the key unchanged, if there is no such key because the key consists
+/// only of `0xFF` bytes.
+pub(crate) fn increment_key(encoded: &mut Vec<u8>) -> bool {
+    match encoded.iter().rposition(|&b| b != 0xFF) {
+        Some(idx) => {
+            encoded.truncate(idx + 1);
+            encoded[idx] += 1;
+            true
+        }
+        None => false,
+    }
+}
+
 pub(crate) fn encode_partition_key(
     partition_schema: &PartitionSchema,
     row: &Row,
@@ -126,7 +158,7 @@ fn encode_column(row: &Row, idx: usize, is_last: bool, buf: &mut Vec<u8>) -> Res
         DataType::Int16 => buf
             .write_i16::<BigEndian>(row.get::<_, i16>(idx)? ^ i16::MIN)
             .unwrap(),
-        DataType::Int32 => buf
+        DataType::Int32 | DataType::Date => buf
             .write_i32::<BigEndian>(row.get::<_, i32>(idx)? ^ i32::MIN)
             .unwrap(),
         DataType::Int64 | DataType::Timestamp => buf
@@ -156,11 +188,19 @@ fn encode_binary(value: &[u8], is_last: bool, buf: &mut Vec<u8>) {
     }
 }
 
+/// Decodes an encoded primary key into a row.
+///
+/// The key may be a prefix of a full primary key, as is the case for many tablet partition
+/// bounds. Decoding stops once the key is exhausted, and only the decoded prefix columns are set
+/// in the returned row.
 pub(crate) fn decode_primary_key(schema: &Schema, mut key: &[u8]) -> Result<Row<'static>> {
     let mut row = schema.new_row();
 
     let num_primary_key_columns = row.schema().num_primary_key_columns();
     for idx in 0..num_primary_key_columns {
+        if key.is_empty() {
+            break;
+        }
         key = decode_column(&mut row, idx, idx + 1 == num_primary_key_columns, key)?;
     }
 
@@ -199,9 +239,18 @@ pub(crate) fn decode_range_partition_key(
 }
 
 fn decode_column<'a>(row: &mut Row, idx: usize, is_last: bool, key: &'a [u8]) -> Result<&'a [u8]> {
+    let data_type = row.schema().columns()[idx].data_type();
+    if !data_type.is_var_len() && key.len() < data_type.size() {
+        return Err(Error::Serialization(format!(
+            "truncated {:?} column {:?} in key",
+            data_type,
+            row.schema().columns()[idx].name()
+        )));
+    }
+
     unsafe {
         // Use set_unchecked since the column type is already checked.
-        match row.schema().columns()[idx].data_type() {
+        match data_type {
             DataType::Int8 => {
                 row.set_unchecked(idx, (key[0] as i8) ^ i8::MIN);
                 Ok(&key[1..])
@@ -210,7 +259,7 @@ fn decode_column<'a>(row: &mut Row, idx: usize, is_last: bool, key: &'a [u8]) ->
                 row.set_unchecked(idx, BigEndian::read_i16(key) ^ i16::MIN);
                 Ok(&key[2..])
             }
-            DataType::Int32 => {
+            DataType::Int32 | DataType::Date => {
                 row.set_unchecked(idx, BigEndian::read_i32(key) ^ i32::MIN);
                 Ok(&key[4..])
             }
@@ -229,15 +278,17 @@ fn decode_column<'a>(row: &mut Row, idx: usize, is_last: bool, key: &'a [u8]) ->
                 Ok(remaining)
             }
             DataType::Bool | DataType::Float | DataType::Double => {
-                panic!(
-                    "illegal type {:?} in key",
-                    row.schema().columns()[idx].data_type()
-                );
+                panic!("illegal type {:?} in key", data_type);
             }
         }
     }
 }
 
+/// Decodes a binary column from the key, returning the remaining key and the decoded value.
+///
+/// Non-last columns are normally terminated by a `0x00 0x00` separator, however a truncated key
+/// prefix may end in the middle of a column's value. In that case the remainder of the key is
+/// taken as the value.
 fn decode_binary(mut key: &[u8], is_last: bool) -> Result<(&[u8], Vec<u8>)> {
     if is_last {
         Ok((&[], key.to_owned()))
@@ -271,9 +322,9 @@ fn decode_binary(mut key: &[u8], is_last: bool) -> Result<(&[u8], Vec<u8>)> {
                     }
                 },
                 None => {
-                    return Err(Error::Serialization(
-                        "expected binary column separator sequence".to_owned(),
-                    ))
+                    ret.extend_from_slice(key);
+                    key = &[];
+                    break;
                 }
             }
         }
@@ -312,7 +363,7 @@ fn increment_cell(row: &mut Row, idx: usize) -> bool {
             }
             row.set(idx, val + 1).unwrap();
         }
-        DataType::Int32 => {
+        DataType::Int32 | DataType::Date => {
             let val: i32 = row.get(idx).unwrap();
             if val == i32::MAX {
                 return false;
@@ -372,7 +423,7 @@ fn is_cell_equal(a: &Row, b: &Row, idx: usize) -> bool {
         DataType::Bool => cmp::<bool>(a, b, idx),
         DataType::Int8 => cmp::<i8>(a, b, idx),
         DataType::Int16 => cmp::<i16>(a, b, idx),
-        DataType::Int32 => cmp::<i32>(a, b, idx),
+        DataType::Int32 | DataType::Date => cmp::<i32>(a, b, idx),
         DataType::Int64 | DataType::Timestamp => cmp::<i64>(a, b, idx),
         DataType::Binary | DataType::String => cmp::<&[u8]>(a, b, idx),
         // TODO: do bitwise cmp for floats?
@@ -409,7 +460,7 @@ fn is_cell_incremented(lower: &Row, upper: &Row, idx: usize) -> bool {
             let upper: i16 = upper.get(idx).unwrap();
             lower < i16::MAX && lower + 1 == upper
         }
-        DataType::Int32 => {
+        DataType::Int32 | DataType::Date => {
             let lower: i32 = lower.get(idx).unwrap();
             let upper: i32 = upper.get(idx).unwrap();
             lower < i32::MAX && lower + 1 == upper
@@ -471,6 +522,89 @@ mod test {
     use DataType;
     use SchemaBuilder;
 
+    #[test]
+    fn test_increment_key() {
+        fn increment(key: &[u8]) -> Option<Vec<u8>> {
+            let mut key = key.to_owned();
+            if increment_key(&mut key) {
+                Some(key)
+            } else {
+                None
+            }
+        }
+
+        assert_eq!(Some(vec![1]), increment(&[0]));
+        assert_eq!(Some(vec![0, 0, 2]), increment(&[0, 0, 1]));
+        assert_eq!(Some(vec![0xFF]), increment(&[0xFE]));
+
+        // Carry.
+        assert_eq!(Some(vec![1]), increment(&[0, 0xFF]));
+        assert_eq!(Some(vec![0x13]), increment(&[0x12, 0xFF, 0xFF]));
+        assert_eq!(Some(vec![0xFF, 0x01]), increment(&[0xFF, 0x00, 0xFF]));
+
+        // Overflow.
+        assert_eq!(None, increment(&[]));
+        assert_eq!(None, increment(&[0xFF]));
+        assert_eq!(None, increment(&[0xFF, 0xFF, 0xFF]));
+
+        // Incrementing an encoded fixed-width key produces a key which sorts after the key, and no
+        // later than the encoding of the next value. Trailing `0xFF` bytes are truncated, so the
+        // result is not necessarily the encoding of the next value itself.
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("a", DataType::Int32).set_not_null())
+            .set_primary_key(vec!["a"])
+            .build()
+            .unwrap();
+        for &value in &[-256i32, -255, -1, 0, 1, 254, 255, 256, i32::MIN, i32::MAX - 1] {
+            let mut row = schema.new_row();
+            row.set(0, value).unwrap();
+            let key = encode_primary_key(&row).unwrap();
+            row.set(0, value + 1).unwrap();
+            let next_key = encode_primary_key(&row).unwrap();
+
+            let mut incremented = key.clone();
+            assert!(increment_key(&mut incremented));
+            assert!(key < incremented, "value: {}", value);
+            assert!(incremented <= next_key, "value: {}", value);
+        }
+        let mut row = schema.new_row();
+        row.set(0, i32::MAX).unwrap();
+        assert!(!increment_key(&mut encode_primary_key(&row).unwrap()));
+    }
+
+    #[test]
+    fn timestamp_key_order() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("ts", DataType::Timestamp).set_not_null())
+            .set_primary_key(vec!["ts"])
+            .build()
+            .unwrap();
+
+        let times = [
+            UNIX_EPOCH - Duration::from_secs(86_400 * 365 * 1000),
+            UNIX_EPOCH - Duration::from_secs(1),
+            UNIX_EPOCH - Duration::from_nanos(1_500),
+            UNIX_EPOCH - Duration::from_micros(1),
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::from_micros(1),
+            UNIX_EPOCH + Duration::from_secs(1),
+            UNIX_EPOCH + Duration::from_secs(86_400 * 365 * 1000),
+        ];
+        let keys = times
+            .iter()
+            .map(|&time| {
+                let mut row = schema.new_row();
+                row.set("ts", time).unwrap();
+                encode_primary_key(&row).unwrap()
+            }).collect::<Vec<_>>();
+
+        for pair in keys.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} >= {:?}", pair[0], pair[1]);
+        }
+    }
+
     #[test]
     fn test_murmur2_64() {
         assert_eq!(7115271465109541368, murmur2_64(b"ab", 0));
@@ -478,6 +612,22 @@ mod test {
         assert_eq!(3575930248840144026, murmur2_64(b"quick brown fox", 42));
     }
 
+    #[test]
+    fn test_murmur2_64_byte_order() {
+        // Build the block explicitly so that the expected hash does not depend on the host's byte
+        // order.
+        let mut block = [0u8; 8];
+        LittleEndian::write_u64(&mut block, 0x0807_0605_0403_0201);
+        assert_eq!([1, 2, 3, 4, 5, 6, 7, 8], block);
+        assert_eq!(9850117305080776375, murmur2_64(&block, 0));
+
+        LittleEndian::write_u64(&mut block, 1);
+        assert_eq!(10357027354812746030, murmur2_64(&block, 0));
+
+        BigEndian::write_u64(&mut block, 1);
+        assert_eq!(15864050386245233697, murmur2_64(&block, 0));
+    }
+
     #[test]
     fn primary_key_encode_decode() {
         let schema = SchemaBuilder::new()
@@ -500,4 +650,72 @@ mod test {
             assert_eq!(row, decoded_row);
         }
     }
+
+    #[test]
+    fn date_key_encode_decode() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("date", DataType::Date).set_not_null())
+            .add_column(Column::new("id", DataType::Int32).set_not_null())
+            .set_primary_key(vec!["date", "id"])
+            .build()
+            .unwrap();
+
+        let mut previous_key = None;
+        for &days in &[i32::MIN, -719_162, -1000, -1, 0, 1, 1000, 2_932_896, i32::MAX] {
+            let mut row = schema.new_row();
+            row.set("date", days).unwrap();
+            row.set("id", 7i32).unwrap();
+            let key = encode_primary_key(&row).unwrap();
+
+            let decoded_row = decode_primary_key(&schema, &key).unwrap();
+            assert_eq!(row, decoded_row);
+            assert_eq!(days, decoded_row.get::<_, i32>("date").unwrap());
+
+            if let Some(previous_key) = previous_key {
+                assert!(previous_key < key, "days: {}", days);
+            }
+            previous_key = Some(key);
+        }
+    }
+
+    #[test]
+    fn partial_primary_key_decode() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("a", DataType::String).set_not_null())
+            .add_column(Column::new("b", DataType::Int32).set_not_null())
+            .add_column(Column::new("c", DataType::String).set_not_null())
+            .set_primary_key(vec!["a", "b", "c"])
+            .build()
+            .unwrap();
+
+        let mut row = schema.new_row();
+        row.set(0, "foo\0bar").unwrap();
+        row.set(1, 99).unwrap();
+        row.set(2, "baz").unwrap();
+        let key = encode_primary_key(&row).unwrap();
+
+        // The empty key decodes to an empty row.
+        assert_eq!(schema.new_row(), decode_primary_key(&schema, &[]).unwrap());
+
+        // A key truncated at a column boundary decodes to the prefix columns.
+        let mut prefix = schema.new_row();
+        prefix.set(0, "foo\0bar").unwrap();
+        prefix.set(1, 99).unwrap();
+        let decoded = decode_primary_key(&schema, &key[..key.len() - 3]).unwrap();
+        assert_eq!(prefix, decoded);
+        assert!(!decoded.is_set(2).unwrap());
+
+        let mut prefix = schema.new_row();
+        prefix.set(0, "foo\0bar").unwrap();
+        assert_eq!(prefix, decode_primary_key(&schema, &key[..10]).unwrap());
+
+        // A key truncated in the middle of a non-terminal binary column decodes the available
+        // bytes.
+        let mut prefix = schema.new_row();
+        prefix.set(0, "foo").unwrap();
+        assert_eq!(prefix, decode_primary_key(&schema, &key[..3]).unwrap());
+
+        // A key truncated in the middle of a fixed-width column is an error.
+        assert!(decode_primary_key(&schema, &key[..11]).is_err());
+    }
 }
@@ -32,18 +32,29 @@ extern crate tempdir;
 #[macro_use]
 extern crate proptest;
 
+#[cfg(feature = "serde")]
+extern crate base64;
+#[cfg(feature = "serde")]
+#[cfg_attr(test, macro_use)]
+extern crate serde_json;
+
+mod aggregate;
 mod backoff;
 mod bitmap;
 mod bounds;
 mod client;
+mod connection_pool;
 mod error;
 mod filter;
+mod hybrid_time;
 mod key;
 mod meta_cache;
+mod metrics;
 mod operation;
 mod partition;
 mod pb;
 mod replica;
+mod request_id;
 mod retry;
 mod row;
 mod scanner;
@@ -58,30 +69,47 @@ mod writer;
 
 #[cfg(test)]
 mod mini_cluster;
+#[cfg(test)]
+mod rpc_proxy;
 
 #[cfg(any(feature = "proptest", test))]
 pub mod prop;
 
+pub use aggregate::{AggResult, AggSpec};
 pub use client::*;
+pub use connection_pool::ConnectionPool;
 pub use error::*;
 pub use filter::*;
+pub use hybrid_time::HybridTime;
+pub use metrics::ClientMetrics;
 pub use operation::*;
 pub use partition::*;
-pub use row::Row;
+pub use request_id::RequestIdGenerator;
+pub use row::{OwnedRow, Row, RowPool};
 pub use scanner::*;
 pub use schema::*;
 pub use server::*;
 pub use table::*;
 pub use tablet::*;
+pub use timestamp::TimestampFormat;
+pub use value::Datum;
 pub use writer::*;
 
 use value::Value;
 
 use std::fmt;
+use std::net::SocketAddr;
 use std::str;
+use std::sync::Arc;
 use std::time::Duration;
 
-pub use krpc::HostPort;
+use prost::Message;
+
+pub use krpc::{EncryptionPolicy, HostPort, Metrics, RequestId, RpcErrorCode};
+
+use krpc::Certificate;
+use metrics::MetricsCounters;
+use request_id::{RequestTracker, SequentialRequestIds};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DataType {
@@ -95,6 +123,9 @@ pub enum DataType {
     Double,
     Binary,
     String,
+    /// A calendar date, stored as the number of days since the Unix epoch in an `i32`. Requires
+    /// Kudu 1.12 or later.
+    Date,
 }
 
 impl DataType {
@@ -109,7 +140,7 @@ impl DataType {
         match self {
             DataType::Bool | DataType::Int8 => 1,
             DataType::Int16 => 2,
-            DataType::Int32 | DataType::Float => 4,
+            DataType::Int32 | DataType::Float | DataType::Date => 4,
             DataType::Int64 | DataType::Timestamp | DataType::Double => 8,
             DataType::Binary | DataType::String => 16,
         }
@@ -127,6 +158,7 @@ impl DataType {
             DataType::Double => pb::DataType::Double,
             DataType::Binary => pb::DataType::Binary,
             DataType::String => pb::DataType::String,
+            DataType::Date => pb::DataType::Date,
         };
         val as i32
     }
@@ -142,7 +174,8 @@ impl DataType {
             pb::DataType::Float => Ok(DataType::Float),
             pb::DataType::Double => Ok(DataType::Double),
             pb::DataType::Binary => Ok(DataType::Binary),
-            pb::DataType::String => Ok(DataType::String),
+            pb::DataType::String | pb::DataType::Varchar => Ok(DataType::String),
+            pb::DataType::Date => Ok(DataType::Date),
             _ => Err(Error::Serialization("unknown data type".to_string())),
         }
     }
@@ -152,7 +185,7 @@ impl DataType {
             DataType::Bool => PhysicalType::Bool,
             DataType::Int8 => PhysicalType::Int8,
             DataType::Int16 => PhysicalType::Int16,
-            DataType::Int32 => PhysicalType::Int32,
+            DataType::Int32 | DataType::Date => PhysicalType::Int32,
             DataType::Int64 | DataType::Timestamp => PhysicalType::Int64,
             DataType::Float => PhysicalType::Float,
             DataType::Double => PhysicalType::Double,
@@ -161,6 +194,24 @@ impl DataType {
     }
 }
 
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DataType::Bool => "BOOL",
+            DataType::Int8 => "INT8",
+            DataType::Int16 => "INT16",
+            DataType::Int32 => "INT32",
+            DataType::Int64 => "INT64",
+            DataType::Timestamp => "UNIXTIME_MICROS",
+            DataType::Float => "FLOAT",
+            DataType::Double => "DOUBLE",
+            DataType::Binary => "BINARY",
+            DataType::String => "STRING",
+            DataType::Date => "DATE",
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PhysicalType {
     Bool,
@@ -207,6 +258,23 @@ impl EncodingType {
             _ => Err(Error::Serialization("unknown encoding type".to_string())),
         }
     }
+
+    /// Returns `true` if columns of the data type may use the encoding.
+    fn supports(self, data_type: DataType) -> bool {
+        match (self, data_type.physical_type()) {
+            (EncodingType::Auto, _) | (EncodingType::Plain, _) => true,
+            (EncodingType::RunLength, PhysicalType::Float)
+            | (EncodingType::RunLength, PhysicalType::Double)
+            | (EncodingType::RunLength, PhysicalType::Binary) => false,
+            (EncodingType::RunLength, _) => true,
+            (EncodingType::BitShuffle, PhysicalType::Bool)
+            | (EncodingType::BitShuffle, PhysicalType::Binary) => false,
+            (EncodingType::BitShuffle, _) => true,
+            (EncodingType::Prefix, PhysicalType::Binary)
+            | (EncodingType::Dictionary, PhysicalType::Binary) => true,
+            (EncodingType::Prefix, _) | (EncodingType::Dictionary, _) => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -288,13 +356,16 @@ id!(TableId);
 id!(TabletId);
 id!(TabletServerId);
 
-// TODO: move this invocation to scanner.rs
-id!(ScannerId);
-
 #[derive(Clone)]
 pub struct Options {
     rpc: krpc::Options,
     admin_timeout: Duration,
+    operation_timeout: Duration,
+    meta_cache_ttl: Option<Duration>,
+    backoff_jitter: bool,
+    counters: Arc<MetricsCounters>,
+    connection_pool: Option<Arc<ConnectionPool>>,
+    request_ids: Arc<RequestTracker>,
 }
 
 impl Default for Options {
@@ -302,14 +373,232 @@ impl Default for Options {
         Options {
             rpc: krpc::Options::default(),
             admin_timeout: Duration::from_secs(60),
+            operation_timeout: Duration::from_secs(60),
+            meta_cache_ttl: None,
+            backoff_jitter: false,
+            counters: Arc::new(MetricsCounters::default()),
+            connection_pool: None,
+            request_ids: Arc::new(RequestTracker::new(Arc::new(SequentialRequestIds::new()))),
         }
     }
 }
 
+impl Options {
+    /// Sets the user name which the client authenticates as.
+    ///
+    /// The user name is sent to masters and tablet servers during SASL PLAIN negotiation, and
+    /// is used by the cluster to attribute operations in its audit logs. When built with the
+    /// `kerberos` feature, the client authenticates to Kerberized clusters by the principal in
+    /// its ticket cache instead, in which case the cluster ignores this setting.
+    ///
+    /// Defaults to `kudu-rs-user`.
+    pub fn set_user(&mut self, user: &str) -> &mut Options {
+        self.rpc.user = user.to_owned();
+        self
+    }
+
+    /// Imports serialized authentication credentials, as exported by another Kudu client.
+    ///
+    /// The credentials' real user, if present, becomes the user name which the client
+    /// authenticates as, and the credentials' CA certificates are trusted to sign server
+    /// certificates during the TLS handshake. Authentication tokens are not yet used. Kerberos
+    /// authentication, enabled by the `kerberos` feature, uses the credentials in the ambient
+    /// ticket cache rather than imported credentials.
+    ///
+    /// Returns an error if the credentials or their CA certificates can not be decoded.
+    pub fn import_authentication_credentials(
+        &mut self,
+        credentials: &[u8],
+    ) -> Result<&mut Options> {
+        let credentials = pb::client::AuthenticationCredentialsPb::decode(credentials)
+            .map_err(|error| {
+                Error::Serialization(format!(
+                    "failed to decode authentication credentials: {}",
+                    error
+                ))
+            })?;
+        for ca_cert in &credentials.ca_cert_ders {
+            self.add_trusted_certificate(ca_cert)?;
+        }
+        if let Some(user) = credentials.real_user {
+            self.rpc.user = user;
+        }
+        Ok(self)
+    }
+
+    /// Sets whether connections to masters and tablet servers are encrypted with TLS.
+    ///
+    /// Defaults to `EncryptionPolicy::Optional`, which encrypts connections to servers that
+    /// support TLS.
+    pub fn set_encryption(&mut self, encryption: EncryptionPolicy) -> &mut Options {
+        self.rpc.encryption = encryption;
+        self
+    }
+
+    /// Trusts a PEM or DER encoded CA certificate to sign server certificates.
+    ///
+    /// Once a certificate is trusted, the TLS handshake fails unless the server presents a
+    /// certificate signed by a trusted CA. Without trusted certificates, server certificates are
+    /// not verified. Kudu server certificates are signed by the cluster's CA, whose certificate
+    /// is included in exported authentication credentials.
+    ///
+    /// Returns an error if the certificate can not be parsed.
+    pub fn add_trusted_certificate(&mut self, certificate: &[u8]) -> Result<&mut Options> {
+        let certificate = Certificate::parse(certificate)?;
+        if !self.rpc.trusted_certificates.contains(&certificate) {
+            self.rpc.trusted_certificates.push(certificate);
+        }
+        Ok(self)
+    }
+
+    /// Sets the maximum duration for which tablet locations are cached before being refetched
+    /// from the master.
+    ///
+    /// The master specifies a TTL with every tablet locations response; this setting only
+    /// shortens it. Defaults to the TTL specified by the master.
+    pub fn set_meta_cache_ttl(&mut self, ttl: Duration) -> &mut Options {
+        self.meta_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Draws each retry backoff uniformly between zero and the exponential backoff duration,
+    /// rather than between one and two times the duration. The wider range spreads out the
+    /// retries of many clients which fail at the same moment, such as when an overloaded tablet
+    /// server starts rejecting requests, so that they don't retry in lockstep.
+    ///
+    /// Defaults to `false`.
+    pub fn set_backoff_jitter(&mut self, jitter: bool) -> &mut Options {
+        self.backoff_jitter = jitter;
+        self
+    }
+
+    /// Installs callbacks for observing the RPCs sent by the client, including their latency
+    /// and retries.
+    ///
+    /// Defaults to no callbacks.
+    pub fn set_metrics(&mut self, metrics: Arc<Metrics>) -> &mut Options {
+        self.rpc.metrics = Some(metrics);
+        self
+    }
+
+    /// Sets how often the hostnames of connected masters and tablet servers are re-resolved.
+    ///
+    /// When a hostname no longer resolves to the address of an established connection, such as
+    /// after a master fails over behind a DNS record, the connection is closed once its
+    /// in-flight RPCs complete, and the client reconnects to the newly resolved address.
+    /// Hostnames are always re-resolved when reconnecting after a connection failure.
+    ///
+    /// Defaults to no periodic re-resolution.
+    pub fn set_dns_refresh_interval(&mut self, interval: Duration) -> &mut Options {
+        self.rpc.dns_refresh_interval = Some(interval);
+        self
+    }
+
+    /// Sets the maximum duration allowed for negotiating a new connection to a master or tablet
+    /// server.
+    ///
+    /// RPCs waiting on a connection which fails to negotiate in time fail with
+    /// `Error::TimedOut`.
+    ///
+    /// Defaults to 3 seconds.
+    pub fn set_negotiation_timeout(&mut self, timeout: Duration) -> &mut Options {
+        self.rpc.negotiation_timeout = timeout;
+        self
+    }
+
+    /// Enables TCP keepalive on connections to masters and tablet servers, probing idle
+    /// connections at the provided interval.
+    ///
+    /// Defaults to TCP keepalive being disabled.
+    pub fn set_keep_alive_interval(&mut self, interval: Duration) -> &mut Options {
+        self.rpc.keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets the timeout of administrative operations sent to the masters, such as creating,
+    /// altering, opening, and listing tables.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn set_default_admin_operation_timeout(&mut self, timeout: Duration) -> &mut Options {
+        self.admin_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout of each RPC sent to tablet servers by scans, including scanner
+    /// keep-alive RPCs.
+    ///
+    /// Defaults to 60 seconds.
+    pub fn set_default_operation_timeout(&mut self, timeout: Duration) -> &mut Options {
+        self.operation_timeout = timeout;
+        self
+    }
+
+    /// Sets a pool of tablet server connections to share with other clients.
+    ///
+    /// Clients created with the same pool reuse each other's negotiated connections to tablet
+    /// servers. See `ConnectionPool` for the restrictions on sharing a pool.
+    ///
+    /// Defaults to a pool private to the client.
+    pub fn set_connection_pool(&mut self, pool: Arc<ConnectionPool>) -> &mut Options {
+        self.connection_pool = Some(pool);
+        self
+    }
+
+    /// Sets the generator of the request IDs sent with the client's RPCs.
+    ///
+    /// Every RPC of a logical operation, including its retries, carries the same client ID and
+    /// sequence number, which makes it possible to follow the operation through the server logs
+    /// and traces. Writes are the exception: tablet servers use their request IDs to apply
+    /// retried writes exactly once, so the client identifies writes by a random client ID of its
+    /// own and numbers them itself, rather than using the generator.
+    ///
+    /// Defaults to a generator which identifies the client by a random UUID, and numbers its
+    /// operations sequentially.
+    pub fn set_request_id_generator(
+        &mut self,
+        generator: Arc<RequestIdGenerator>,
+    ) -> &mut Options {
+        self.request_ids = Arc::new(RequestTracker::new(generator));
+        self
+    }
+
+    pub(crate) fn next_request_id(&self) -> RequestId {
+        self.request_ids.next_request_id()
+    }
+}
+
+/// Types which can be converted into the addresses of a cluster's masters.
+///
+/// Hostnames are kept unresolved, so that they are resolved anew when connecting to the masters.
+/// Host strings without a port use the default master port, 7051.
 pub trait IntoMasterAddrs {
     fn into_master_addrs(self) -> Result<Vec<HostPort>>;
 }
 
+impl IntoMasterAddrs for SocketAddr {
+    fn into_master_addrs(self) -> Result<Vec<HostPort>> {
+        Ok(vec![self.into()])
+    }
+}
+
+impl IntoMasterAddrs for Vec<SocketAddr> {
+    fn into_master_addrs(self) -> Result<Vec<HostPort>> {
+        Ok(self.into_iter().map(HostPort::from).collect())
+    }
+}
+
+impl<'a> IntoMasterAddrs for (&'a str, u16) {
+    fn into_master_addrs(self) -> Result<Vec<HostPort>> {
+        Ok(vec![HostPort::new(self.0.to_owned(), self.1)])
+    }
+}
+
+impl IntoMasterAddrs for (String, u16) {
+    fn into_master_addrs(self) -> Result<Vec<HostPort>> {
+        Ok(vec![self.into()])
+    }
+}
+
 impl IntoMasterAddrs for Vec<HostPort> {
     fn into_master_addrs(self) -> Result<Vec<HostPort>> {
         Ok(self)
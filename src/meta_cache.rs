@@ -1,3 +1,4 @@
+use std::cmp;
 use std::collections::{BTreeMap, Bound, HashMap};
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
@@ -5,6 +6,7 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use futures::sync::{mpsc, oneshot};
+use futures::future::{self, Either};
 use futures::{stream, Async, Future, Poll, Stream};
 use krpc;
 use parking_lot::Mutex;
@@ -12,6 +14,8 @@ use prost;
 use tokio;
 
 use backoff::Backoff;
+use connection_pool::ConnectionPool;
+use metrics::MetricsCounters;
 use partition::{IntoPartitionKey, PartitionKey};
 use pb::master::{
     GetTableLocationsRequestPb, GetTableLocationsResponsePb, GetTableSchemaRequestPb,
@@ -19,6 +23,7 @@ use pb::master::{
 };
 use pb::ExpectField;
 use replica::{Replica, ReplicaRpc, ReplicaSet, Selection, Speculation};
+use request_id::RequestTracker;
 use retry::Retriable;
 use table::Table;
 use tablet::{Tablet, TabletReplica};
@@ -27,6 +32,7 @@ use HostPort;
 use Options;
 use PartitionSchema;
 use RaftRole;
+use RequestId;
 use Result;
 use Schema;
 use TableId;
@@ -84,6 +90,14 @@ impl Entry {
         }
     }
 
+    /// Returns `true` if the entry has expired or been invalidated, and must be refetched.
+    fn is_stale(&self, now: Instant) -> bool {
+        match *self {
+            Entry::Tablet(ref tablet) => tablet.is_stale(now),
+            Entry::NonCoveredRange { deadline, .. } => deadline <= now,
+        }
+    }
+
     pub fn contains_partition_key(&self, partition_key: &[u8]) -> bool {
         let upper_bound = self.upper_bound();
         (upper_bound.is_empty() || partition_key < upper_bound)
@@ -125,9 +139,10 @@ impl Entry {
 #[derive(Clone)]
 pub(crate) struct MetaCache {
     tables: Arc<Mutex<HashMap<TableId, TableLocations>>>,
-    tablet_servers: Arc<Mutex<HashMap<TabletServerId, krpc::Proxy>>>,
+    connection_pool: Arc<ConnectionPool>,
     masters: Arc<Box<[MasterReplica]>>,
     options: Options,
+    is_closed: Arc<AtomicBool>,
 }
 
 // TODO: make fields private
@@ -199,6 +214,14 @@ impl ReplicaSet for Arc<Box<[MasterReplica]>> {
     fn replicas(&self) -> &[MasterReplica] {
         &*self
     }
+
+    /// Forgets the known leader, so that subsequent master RPCs are attempted at every master
+    /// until the new leader responds.
+    fn invalidate(&self) {
+        for master in self.iter() {
+            master.mark_follower();
+        }
+    }
 }
 
 impl MetaCache {
@@ -206,11 +229,13 @@ impl MetaCache {
         master_addrs: Vec<HostPort>,
         options: Options,
     ) -> impl Future<Item = MetaCache, Error = Error> {
+        let connection_pool = options.connection_pool.clone().unwrap_or_default();
         connect_to_cluster(master_addrs, &options).map(|master_replicas| MetaCache {
             tables: Arc::new(Mutex::new(HashMap::new())),
-            tablet_servers: Arc::new(Mutex::new(HashMap::new())),
+            connection_pool,
             masters: master_replicas,
             options,
+            is_closed: Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -218,21 +243,87 @@ impl MetaCache {
         &self.options
     }
 
+    /// Fails all subsequent master RPCs and table opens with `Error::ClientClosed`, drops the
+    /// cached table locations, and shuts down the master and tablet server connections.
+    ///
+    /// Returns a future which completes once the connections' in-flight RPCs complete and the
+    /// connections close. Connections in a pool shared with other clients are left open.
+    pub(crate) fn shutdown(&self) -> impl Future<Item = (), Error = Error> {
+        self.is_closed.store(true, Relaxed);
+        self.tables.lock().clear();
+        let mut proxies = self
+            .masters
+            .iter()
+            .map(|master| master.proxy.clone())
+            .collect::<Vec<_>>();
+        if self.options.connection_pool.is_none() {
+            proxies.extend(self.connection_pool.drain());
+        }
+        future::join_all(proxies.iter().map(krpc::Proxy::shutdown).collect::<Vec<_>>())
+            .map(|_| ())
+            .map_err(|()| unreachable!())
+    }
+
     pub(crate) fn master_rpc<Req, Resp>(
         &self,
-        call: krpc::Call<Req, Resp>,
+        mut call: krpc::Call<Req, Resp>,
     ) -> impl Future<Item = Resp, Error = Error>
     where
         Req: prost::Message + 'static,
         Resp: Retriable,
     {
-        ReplicaRpc::new(
-            self.masters.clone(),
-            call,
-            Speculation::Staggered(Duration::from_millis(32)),
-            Selection::Leader,
-            Backoff::with_duration_range(32, 2048),
-        ).map(|(_, resp, _)| resp)
+        if self.is_closed.load(Relaxed) {
+            return Either::A(future::err(Error::ClientClosed));
+        }
+        call.set_request_id(self.options.next_request_id());
+        Either::B(
+            ReplicaRpc::new(
+                self.masters.clone(),
+                call,
+                Speculation::Staggered(Duration::from_millis(32)),
+                Selection::Leader,
+                Backoff::with_duration_range(32, 2048)
+                    .with_full_jitter(self.options.backoff_jitter),
+            ).map(|(_, resp, _)| resp),
+        )
+    }
+
+    /// Asks every master for its role, and returns the address of the master which reports
+    /// itself as the leader. Masters which are unreachable or fail to respond by the deadline are
+    /// skipped.
+    pub(crate) fn leader_master(
+        &self,
+        deadline: Instant,
+    ) -> impl Future<Item = HostPort, Error = Error> {
+        if self.is_closed.load(Relaxed) {
+            return Either::A(future::err(Error::ClientClosed));
+        }
+        let roles = self.masters.iter().map(|master| {
+            let mut call = MasterService::connect_to_master(Default::default(), deadline);
+            call.set_required_feature_flags(&[MasterFeatures::ConnectToMaster as u32]);
+            let hostport = master.hostport.clone();
+            master.proxy().send(call).then(move |result| -> Result<Option<HostPort>> {
+                Ok(match result {
+                    Ok((ref response, _))
+                        if response.error.is_none() && response.role() == RaftRole::Leader =>
+                    {
+                        Some(hostport)
+                    }
+                    _ => None,
+                })
+            })
+        });
+        Either::B(
+            stream::futures_unordered(roles)
+                .filter_map(|leader| leader)
+                .into_future()
+                .map_err(|(error, _)| error)
+                .and_then(|(leader, _)| {
+                    leader.ok_or_else(|| {
+                        Error::NotFound("no master is currently the leader".to_string())
+                    })
+                }),
+        )
     }
 
     pub(crate) fn open_table(
@@ -244,7 +335,7 @@ impl MetaCache {
             MasterService::get_table_schema(Arc::new(GetTableSchemaRequestPb { table }), deadline);
 
         let tables = self.tables.clone();
-        let tablet_servers = self.tablet_servers.clone();
+        let connection_pool = self.connection_pool.clone();
         let options = self.options.clone();
         let masters = self.masters.clone();
 
@@ -265,11 +356,14 @@ impl MetaCache {
                     &schema,
                 );
                 let schema = Schema::from_pb(schema)?;
+                let owner = resp.owner.unwrap_or_default();
+                let comment = resp.comment.unwrap_or_default();
+                let extra_configs = resp.extra_configs;
 
                 let table_locations = tables
                     .lock()
                     .entry(id)
-                    .or_insert_with(|| TableLocations::new(options, id, masters, tablet_servers))
+                    .or_insert_with(|| TableLocations::new(options, id, masters, connection_pool))
                     .clone();
 
                 Ok(Table::new(
@@ -278,6 +372,9 @@ impl MetaCache {
                     schema,
                     partition_schema,
                     num_replicas,
+                    owner,
+                    comment,
+                    extra_configs,
                     table_locations,
                 ))
             })
@@ -290,6 +387,10 @@ impl MetaCache {
 #[derive(Clone)]
 pub(crate) struct TableLocations {
     entries: Arc<Mutex<BTreeMap<PartitionKey, Entry>>>,
+    counters: Arc<MetricsCounters>,
+    operation_timeout: Duration,
+    backoff_jitter: bool,
+    request_ids: Arc<RequestTracker>,
     sender: mpsc::UnboundedSender<(PartitionKey, oneshot::Sender<Result<Entry>>)>,
 }
 
@@ -298,22 +399,33 @@ impl TableLocations {
         options: Options,
         table_id: TableId,
         masters: Arc<Box<[MasterReplica]>>,
-        tablet_servers: Arc<Mutex<HashMap<TabletServerId, krpc::Proxy>>>,
+        connection_pool: Arc<ConnectionPool>,
     ) -> TableLocations {
         let (sender, receiver) = mpsc::unbounded();
         let entries = Arc::new(Mutex::new(BTreeMap::new()));
+        let counters = options.counters.clone();
+        let operation_timeout = options.operation_timeout;
+        let backoff_jitter = options.backoff_jitter;
+        let request_ids = options.request_ids.clone();
 
         tokio::spawn(TableLocationsTask {
             options,
             entries: entries.clone(),
-            tablet_servers,
+            connection_pool,
             table_id,
             masters,
             receiver,
             requests: BTreeMap::new(),
             in_flight: None,
         });
-        TableLocations { entries, sender }
+        TableLocations {
+            entries,
+            counters,
+            operation_timeout,
+            backoff_jitter,
+            request_ids,
+            sender,
+        }
     }
 
     pub(crate) fn entry(&self, partition_key: &[u8]) -> Lookup<Entry> {
@@ -329,8 +441,10 @@ impl TableLocations {
 
     fn extract<T>(&self, partition_key: &[u8], extractor: fn(&Entry) -> T) -> Lookup<T> {
         if let Some(entry) = get_entry(&self.entries.lock(), partition_key) {
+            self.counters.meta_cache_hit();
             Lookup::Hit(Some(extractor(entry)))
         } else {
+            self.counters.meta_cache_miss();
             let partition_key = partition_key.into_partition_key();
             let (send, recv) = oneshot::channel();
             self.sender
@@ -340,7 +454,41 @@ impl TableLocations {
         }
     }
 
-    #[cfg(test)]
+    pub(crate) fn counters(&self) -> &MetricsCounters {
+        &self.counters
+    }
+
+    /// Returns the timeout of individual tablet server RPCs sent on behalf of the table.
+    pub(crate) fn operation_timeout(&self) -> Duration {
+        self.operation_timeout
+    }
+
+    /// Returns the backoff policy for retrying tablet server RPCs sent on behalf of the table.
+    pub(crate) fn backoff(&self) -> Backoff {
+        Backoff::default().with_full_jitter(self.backoff_jitter)
+    }
+
+    /// Returns the generator of request IDs for tablet server RPCs sent on behalf of the table.
+    pub(crate) fn request_ids(&self) -> Arc<RequestTracker> {
+        self.request_ids.clone()
+    }
+
+    pub(crate) fn next_request_id(&self) -> RequestId {
+        self.request_ids.next_request_id()
+    }
+
+    /// Removes the cached entry containing the partition key, if any, so that the next lookup
+    /// of the partition key refetches its location from the master.
+    pub(crate) fn invalidate(&self, partition_key: &[u8]) {
+        let mut entries = self.entries.lock();
+        let lower_bound = match get_entry(&entries, partition_key) {
+            Some(entry) => entry.lower_bound().into_partition_key(),
+            None => return,
+        };
+        entries.remove(&lower_bound);
+    }
+
+    /// Removes all cached entries.
     pub(crate) fn clear(&self) {
         self.entries.lock().clear()
     }
@@ -376,12 +524,15 @@ fn get_entry<'a, 'b>(
     entries: &'a BTreeMap<PartitionKey, Entry>,
     partition_key: &'b [u8],
 ) -> Option<&'a Entry> {
-    // TODO: filter stale entries.
     match entries
         .range::<[u8], _>((Bound::Unbounded, Bound::Included(partition_key)))
         .next_back()
     {
-        Some((_, ref entry)) if entry.contains_partition_key(partition_key) => Some(entry),
+        Some((_, ref entry))
+            if entry.contains_partition_key(partition_key) && !entry.is_stale(Instant::now()) =>
+        {
+            Some(entry)
+        }
         _ => None,
     }
 }
@@ -397,7 +548,7 @@ struct TableLocationsTask {
     entries: Arc<Mutex<BTreeMap<PartitionKey, Entry>>>,
 
     /// Cache of tablet server connections.
-    tablet_servers: Arc<Mutex<HashMap<TabletServerId, krpc::Proxy>>>,
+    connection_pool: Arc<ConnectionPool>,
 
     table_id: TableId,
 
@@ -515,13 +666,7 @@ impl TableLocationsTask {
                         .collect::<Vec<_>>()
                         .into_boxed_slice();
 
-                    let proxy = self
-                        .tablet_servers
-                        .lock()
-                        .entry(id)
-                        .or_insert_with(|| {
-                            krpc::Proxy::spawn(rpc_addrs.clone(), self.options.rpc.clone())
-                        }).clone();
+                    let proxy = self.connection_pool.proxy(id, &rpc_addrs, &self.options.rpc);
 
                     Ok(TabletReplica {
                         id,
@@ -537,6 +682,8 @@ impl TableLocationsTask {
                 lower_bound,
                 upper_bound,
                 replicas,
+                deadline,
+                is_invalidated: AtomicBool::new(false),
             });
 
             if tablet.lower_bound() > &last_upper_bound {
@@ -598,7 +745,10 @@ impl Future for TableLocationsTask {
                     Ok(Async::Ready((_, response, _))) => {
                         // TODO: error handling
                         let now = Instant::now();
-                        let ttl = Duration::from_millis(u64::from(response.ttl_millis()));
+                        let mut ttl = Duration::from_millis(u64::from(response.ttl_millis()));
+                        if let Some(max_ttl) = self.options.meta_cache_ttl {
+                            ttl = cmp::min(ttl, max_ttl);
+                        }
                         let deadline = now + ttl;
                         let num_tablets = response.tablet_locations.len();
                         let entries = self
@@ -656,18 +806,21 @@ impl Future for TableLocationsTask {
                         partition_key_end: None,
                         max_returned_locations: Some(MAX_RETURNED_TABLE_LOCATIONS),
                         replica_type_filter: None,
+                        ..Default::default()
                     });
-                    let call = MasterService::get_table_locations(
+                    let mut call = MasterService::get_table_locations(
                         request,
                         Instant::now() + self.options.admin_timeout,
                     );
+                    call.set_request_id(self.options.next_request_id());
 
                     let resp = ReplicaRpc::new(
                         self.masters.clone(),
                         call,
                         Speculation::Staggered(Duration::from_millis(100)),
                         Selection::Leader,
-                        Backoff::with_duration_range(250, u32::max_value()),
+                        Backoff::with_duration_range(250, u32::max_value())
+                            .with_full_jitter(self.options.backoff_jitter),
                     );
 
                     self.in_flight = Some((partition_key.clone(), Instant::now(), resp));
@@ -683,6 +836,8 @@ pub(crate) fn connect_to_cluster(
     options: &Options,
 ) -> impl Future<Item = Arc<Box<[MasterReplica]>>, Error = Error> {
     let admin_timeout = options.admin_timeout;
+    let backoff_jitter = options.backoff_jitter;
+    let request_id = options.next_request_id();
     let start = Instant::now();
     stream::futures_unordered(
         master_addrs
@@ -694,13 +849,14 @@ pub(crate) fn connect_to_cluster(
         let mut call =
             MasterService::connect_to_master(Default::default(), Instant::now() + admin_timeout);
         call.set_required_feature_flags(&[MasterFeatures::ConnectToMaster as u32]);
+        call.set_request_id(request_id);
 
         ReplicaRpc::new(
             replica_set.clone(),
             call,
             Speculation::Full,
             Selection::Leader,
-            Backoff::with_duration_range(250, u32::max_value()),
+            Backoff::with_duration_range(250, u32::max_value()).with_full_jitter(backoff_jitter),
         ).map(move |_| replica_set)
     }).inspect(move |masters| {
         info!(
@@ -1052,6 +1208,124 @@ mod tests {
         assert_eq!(12, cache.entries.lock().len());
     }
 
+    #[test]
+    fn hit_miss_metrics() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("hit_miss_metrics", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+        let locations = table.table_locations().clone();
+
+        let before = client.metrics_snapshot();
+
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        let after_miss = client.metrics_snapshot();
+        assert_eq!(before.meta_cache_misses() + 1, after_miss.meta_cache_misses());
+        assert_eq!(before.meta_cache_hits(), after_miss.meta_cache_hits());
+        assert!(after_miss.rpcs() > before.rpcs());
+
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        let after_hit = client.metrics_snapshot();
+        assert_eq!(after_miss.meta_cache_misses(), after_hit.meta_cache_misses());
+        assert_eq!(after_miss.meta_cache_hits() + 1, after_hit.meta_cache_hits());
+        assert_eq!(after_miss.rpcs(), after_hit.rpcs());
+    }
+
+    #[test]
+    fn invalidate() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("invalidate", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+        let locations = table.table_locations().clone();
+
+        let tablet = match runtime.block_on(locations.entry(&[])).expect("entry") {
+            Entry::Tablet(tablet) => tablet,
+            entry => panic!("expected tablet entry: {:?}", entry),
+        };
+        let misses = client.metrics_snapshot().meta_cache_misses();
+
+        // A cached entry is not refetched.
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        assert_eq!(misses, client.metrics_snapshot().meta_cache_misses());
+
+        // An explicitly invalidated entry is refetched.
+        locations.invalidate(b"some-key");
+        let refetched = match runtime.block_on(locations.entry(&[])).expect("entry") {
+            Entry::Tablet(tablet) => tablet,
+            entry => panic!("expected tablet entry: {:?}", entry),
+        };
+        assert_eq!(misses + 1, client.metrics_snapshot().meta_cache_misses());
+        assert!(!Arc::ptr_eq(&tablet, &refetched));
+        assert_eq!(tablet.id(), refetched.id());
+
+        // A tablet invalidated by a replica RPC is refetched.
+        refetched.invalidate();
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        assert_eq!(misses + 2, client.metrics_snapshot().meta_cache_misses());
+
+        table.clear_location_cache();
+        assert!(locations.entries.lock().is_empty());
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        assert_eq!(misses + 3, client.metrics_snapshot().meta_cache_misses());
+    }
+
+    #[test]
+    fn ttl() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut options = Options::default();
+        options.set_meta_cache_ttl(Duration::from_millis(0));
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("ttl", simple_schema());
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+        let locations = table.table_locations().clone();
+
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        let before = client.metrics_snapshot();
+
+        // Entries expire immediately, so every lookup is refetched.
+        runtime.block_on(locations.entry(&[])).expect("entry");
+        let after = client.metrics_snapshot();
+        assert_eq!(before.meta_cache_misses() + 1, after.meta_cache_misses());
+        assert_eq!(before.meta_cache_hits(), after.meta_cache_hits());
+    }
+
     #[test]
     fn multi_tablet_concurrent() {
         let _ = env_logger::try_init();
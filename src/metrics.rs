@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use krpc::{self, Metrics, RpcErrorCode};
+use parking_lot::Mutex;
+
+/// A point-in-time snapshot of a client's cumulative counters.
+///
+/// Counters start at zero when the client is created and only ever increase, which makes them
+/// suitable for export as Prometheus counters. Snapshots are retrieved with
+/// `Client::metrics_snapshot`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClientMetrics {
+    rpcs: usize,
+    failed_rpcs: usize,
+    rpc_errors: HashMap<RpcErrorCode, usize>,
+    bytes_scanned: usize,
+    rows_written: usize,
+    meta_cache_hits: usize,
+    meta_cache_misses: usize,
+}
+
+impl ClientMetrics {
+    /// Returns the number of RPCs sent to masters and tablet servers, including retries.
+    pub fn rpcs(&self) -> usize {
+        self.rpcs
+    }
+
+    /// Returns the number of RPCs which failed for any reason, including RPC errors, I/O errors,
+    /// and timeouts.
+    pub fn failed_rpcs(&self) -> usize {
+        self.failed_rpcs
+    }
+
+    /// Returns the number of RPCs which failed with the provided RPC error code.
+    pub fn rpc_errors(&self, code: RpcErrorCode) -> usize {
+        self.rpc_errors.get(&code).cloned().unwrap_or(0)
+    }
+
+    /// Returns the number of failed RPCs for each RPC error code which has occurred.
+    pub fn rpc_errors_by_code(&self) -> &HashMap<RpcErrorCode, usize> {
+        &self.rpc_errors
+    }
+
+    /// Returns the number of row data bytes received by scans, including indirect data for
+    /// string and binary columns.
+    pub fn bytes_scanned(&self) -> usize {
+        self.bytes_scanned
+    }
+
+    /// Returns the number of rows successfully written by writers.
+    pub fn rows_written(&self) -> usize {
+        self.rows_written
+    }
+
+    /// Returns the number of tablet location lookups satisfied by the meta cache.
+    pub fn meta_cache_hits(&self) -> usize {
+        self.meta_cache_hits
+    }
+
+    /// Returns the number of tablet location lookups which required a master RPC.
+    pub fn meta_cache_misses(&self) -> usize {
+        self.meta_cache_misses
+    }
+}
+
+/// The live counters backing `ClientMetrics`.
+///
+/// A single instance is shared by every component of a client. It is installed as the client's
+/// RPC metrics callbacks, forwarding to any application-provided callbacks.
+#[derive(Default)]
+pub(crate) struct MetricsCounters {
+    rpcs: AtomicUsize,
+    failed_rpcs: AtomicUsize,
+    rpc_errors: Mutex<HashMap<RpcErrorCode, usize>>,
+    bytes_scanned: AtomicUsize,
+    rows_written: AtomicUsize,
+    meta_cache_hits: AtomicUsize,
+    meta_cache_misses: AtomicUsize,
+    delegate: Option<Arc<Metrics>>,
+}
+
+impl MetricsCounters {
+    pub fn new(delegate: Option<Arc<Metrics>>) -> MetricsCounters {
+        MetricsCounters {
+            delegate,
+            ..Default::default()
+        }
+    }
+
+    pub fn snapshot(&self) -> ClientMetrics {
+        ClientMetrics {
+            rpcs: self.rpcs.load(Ordering::Relaxed),
+            failed_rpcs: self.failed_rpcs.load(Ordering::Relaxed),
+            rpc_errors: self.rpc_errors.lock().clone(),
+            bytes_scanned: self.bytes_scanned.load(Ordering::Relaxed),
+            rows_written: self.rows_written.load(Ordering::Relaxed),
+            meta_cache_hits: self.meta_cache_hits.load(Ordering::Relaxed),
+            meta_cache_misses: self.meta_cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn add_bytes_scanned(&self, bytes: usize) {
+        self.bytes_scanned.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn add_rows_written(&self, rows: usize) {
+        self.rows_written.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    pub fn meta_cache_hit(&self) {
+        self.meta_cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn meta_cache_miss(&self) {
+        self.meta_cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics for MetricsCounters {
+    fn on_rpc_start(&self, service: &str, method: &str) {
+        self.rpcs.fetch_add(1, Ordering::Relaxed);
+        if let Some(ref delegate) = self.delegate {
+            delegate.on_rpc_start(service, method);
+        }
+    }
+
+    fn on_rpc_complete(
+        &self,
+        service: &str,
+        method: &str,
+        duration: Duration,
+        result: Result<(), &krpc::Error>,
+    ) {
+        if let Err(error) = result {
+            self.failed_rpcs.fetch_add(1, Ordering::Relaxed);
+            if let krpc::Error::Rpc(ref error) = *error {
+                *self.rpc_errors.lock().entry(error.code).or_insert(0) += 1;
+            }
+        }
+        if let Some(ref delegate) = self.delegate {
+            delegate.on_rpc_complete(service, method, duration, result);
+        }
+    }
+
+    fn on_retry(&self, service: &str, method: &str) {
+        if let Some(ref delegate) = self.delegate {
+            delegate.on_retry(service, method);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
 use std::env;
 use std::ffi::CString;
 use std::io::{BufRead, BufReader, Read, Write};
@@ -15,8 +16,9 @@ use pb::tools::control_shell_request_pb::Request;
 use pb::tools::control_shell_response_pb::Response;
 use pb::tools::{
     ControlShellRequestPb, ControlShellResponsePb, CreateClusterRequestPb, DaemonIdentifierPb,
-    DaemonType, GetMastersRequestPb, GetMastersResponsePb, StartClusterRequestPb,
-    StartDaemonRequestPb, StopClusterRequestPb, StopDaemonRequestPb,
+    DaemonType, GetKdcEnvVarsRequestPb, GetKdcEnvVarsResponsePb, GetMastersRequestPb,
+    GetMastersResponsePb, GetTServersRequestPb, GetTServersResponsePb, KinitRequestPb,
+    StartClusterRequestPb, StartDaemonRequestPb, StopClusterRequestPb, StopDaemonRequestPb,
 };
 use HostPort;
 
@@ -82,6 +84,41 @@ impl MiniCluster {
         }
     }
 
+    /// Returns the RPC addresses of the tablet servers, in index order.
+    pub fn tserver_addrs(&mut self) -> Vec<HostPort> {
+        let response = self.send_request(Request::GetTservers(GetTServersRequestPb::default()));
+
+        if let Some(Response::GetTservers(GetTServersResponsePb { mut tservers })) = response {
+            tservers.sort_by_key(|tserver| tserver.id.as_ref().and_then(|id| id.index));
+            tservers
+                .into_iter()
+                .map(|tserver| tserver.bound_rpc_address.unwrap().into())
+                .collect()
+        } else {
+            panic!("unexpected response: {:?}", response)
+        }
+    }
+
+    /// Returns the environment variables which configure Kerberos clients to use the cluster's
+    /// KDC and ticket cache. Only valid for clusters with Kerberos enabled.
+    pub fn kdc_env_vars(&mut self) -> HashMap<String, String> {
+        let response = self.send_request(Request::GetKdcEnvVars(GetKdcEnvVarsRequestPb::default()));
+
+        if let Some(Response::GetKdcEnvVars(GetKdcEnvVarsResponsePb { env_vars })) = response {
+            env_vars
+        } else {
+            panic!("unexpected response: {:?}", response)
+        }
+    }
+
+    /// Obtains a ticket for the user from the cluster's KDC, storing it in the cluster's ticket
+    /// cache. Only valid for clusters with Kerberos enabled.
+    pub fn kinit(&mut self, user: &str) {
+        self.send_request(Request::Kinit(KinitRequestPb {
+            username: Some(user.to_string()),
+        }));
+    }
+
     pub fn stop_master(&mut self, index: u32) {
         let mut id = DaemonIdentifierPb::default();
         id.set_type_(DaemonType::Master);
@@ -96,6 +133,20 @@ impl MiniCluster {
         self.send_request(Request::StartDaemon(StartDaemonRequestPb { id: Some(id) }));
     }
 
+    pub fn stop_tserver(&mut self, index: u32) {
+        let mut id = DaemonIdentifierPb::default();
+        id.set_type_(DaemonType::Tserver);
+        id.index = Some(index);
+        self.send_request(Request::StopDaemon(StopDaemonRequestPb { id: Some(id) }));
+    }
+
+    pub fn start_tserver(&mut self, index: u32) {
+        let mut id = DaemonIdentifierPb::default();
+        id.set_type_(DaemonType::Tserver);
+        id.index = Some(index);
+        self.send_request(Request::StartDaemon(StartDaemonRequestPb { id: Some(id) }));
+    }
+
     fn send_request(&mut self, request: Request) -> Option<Response> {
         let stdin = self.process.stdin.as_mut().expect("stdin");
         let stdout = self.process.stdout.as_mut().expect("stdout");
@@ -186,6 +237,10 @@ impl MiniClusterConfig {
         self
     }
 
+    pub fn require_encryption(&mut self) -> &mut MiniClusterConfig {
+        self.push_flag("--rpc-encryption=required".to_string())
+    }
+
     pub fn log_rpc_negotiation_trace(&mut self) -> &mut MiniClusterConfig {
         self.push_flag("--rpc-trace-negotiation=true".to_string())
     }
@@ -206,6 +261,26 @@ impl MiniClusterConfig {
         self
     }
 
+    /// Sets the tablet server scanner TTL, and checks for expired scanners every 100ms.
+    pub fn scanner_ttl(&mut self, millis: i32) -> &mut MiniClusterConfig {
+        self.pb
+            .extra_tserver_flags
+            .push(format!("--scanner-ttl-ms={}", millis));
+        self.pb
+            .extra_tserver_flags
+            .push("--scanner-gc-check-interval-us=100000".to_string());
+        self
+    }
+
+    /// Sets the size of the scan batches returned by the tablet servers, so that small tables
+    /// are scanned in multiple batches.
+    pub fn scanner_batch_size_bytes(&mut self, bytes: i32) -> &mut MiniClusterConfig {
+        self.pb
+            .extra_tserver_flags
+            .push(format!("--scanner-default-batch-size-bytes={}", bytes));
+        self
+    }
+
     pub fn disable_leader_failure_detection(&mut self) -> &mut MiniClusterConfig {
         self.push_flag("--enable-leader-failure-detection=false".to_string())
     }
@@ -20,15 +20,41 @@ pub enum OperationKind {
     Update,
     Upsert,
     Delete,
+    /// An insert which is silently dropped by the tablet server if a row with the same primary
+    /// key already exists. Requires Kudu 1.14 or later.
+    InsertIgnore,
+    /// An update which is silently dropped by the tablet server if no row with the primary key
+    /// exists. Requires Kudu 1.14 or later.
+    UpdateIgnore,
+    /// A delete which is silently dropped by the tablet server if no row with the primary key
+    /// exists. Requires Kudu 1.14 or later.
+    DeleteIgnore,
 }
 
 impl OperationKind {
-    pub(crate) fn as_pb(self) -> OperationTypePb {
+    /// Returns the operation type, as encoded in `RowOperationsPb`.
+    pub(crate) fn wire_type(self) -> u8 {
         match self {
-            OperationKind::Insert => OperationTypePb::Insert,
-            OperationKind::Update => OperationTypePb::Update,
-            OperationKind::Upsert => OperationTypePb::Upsert,
-            OperationKind::Delete => OperationTypePb::Delete,
+            OperationKind::Insert => OperationTypePb::Insert as u8,
+            OperationKind::Update => OperationTypePb::Update as u8,
+            OperationKind::Upsert => OperationTypePb::Upsert as u8,
+            OperationKind::Delete => OperationTypePb::Delete as u8,
+            OperationKind::InsertIgnore => OperationTypePb::InsertIgnore as u8,
+            OperationKind::UpdateIgnore => OperationTypePb::UpdateIgnore as u8,
+            OperationKind::DeleteIgnore => OperationTypePb::DeleteIgnore as u8,
+        }
+    }
+
+    fn from_wire_type(wire_type: u8) -> Option<OperationKind> {
+        match OperationTypePb::from_i32(i32::from(wire_type)) {
+            Some(OperationTypePb::Insert) => Some(OperationKind::Insert),
+            Some(OperationTypePb::Update) => Some(OperationKind::Update),
+            Some(OperationTypePb::Upsert) => Some(OperationKind::Upsert),
+            Some(OperationTypePb::Delete) => Some(OperationKind::Delete),
+            Some(OperationTypePb::InsertIgnore) => Some(OperationKind::InsertIgnore),
+            Some(OperationTypePb::UpdateIgnore) => Some(OperationKind::UpdateIgnore),
+            Some(OperationTypePb::DeleteIgnore) => Some(OperationKind::DeleteIgnore),
+            _ => None,
         }
     }
 }
@@ -48,6 +74,8 @@ impl<'data> Operation<'data> {
     }
 }
 
+/// A row operation which failed, along with the row it was applied to.
+#[derive(Clone, Debug)]
 pub struct OperationError {
     pub row: Row<'static>,
     pub kind: OperationKind,
@@ -94,12 +122,20 @@ impl OperationEncoder {
     }
 
     pub fn encode_row(&mut self, op_type: OperationTypePb, row: &Row) {
+        self.encode_row_with_type(op_type as u8, row)
+    }
+
+    pub fn encode_operation(&mut self, kind: OperationKind, row: &Row) {
+        self.encode_row_with_type(kind.wire_type(), row)
+    }
+
+    fn encode_row_with_type(&mut self, op_type: u8, row: &Row) {
         let schema = row.schema();
         let bitmap_len = schema.bitmap_len();
         self.data
             .reserve(1 + schema.row_len() + schema.has_nullable_columns() as usize * bitmap_len);
 
-        self.data.push(op_type as u8);
+        self.data.push(op_type);
 
         match row.is_set_bitmap() {
             Some(bitmap) => self.data.extend_from_slice(bitmap),
@@ -198,7 +234,7 @@ impl<'a> Iterator for OperationDecoder<'a> {
             return None;
         }
 
-        let op_type = i32::from(data[*offset]);
+        let op_type = data[*offset];
         *offset += 1;
 
         let mut row = schema.new_row();
@@ -234,7 +270,7 @@ impl<'a> Iterator for OperationDecoder<'a> {
                     DataType::Int16 => {
                         row.set_unchecked(idx, i16::read_cell(data).unwrap());
                     }
-                    DataType::Int32 => {
+                    DataType::Int32 | DataType::Date => {
                         row.set_unchecked(idx, i32::read_cell(data).unwrap());
                     }
                     DataType::Int64 | DataType::Timestamp => {
@@ -255,13 +291,8 @@ impl<'a> Iterator for OperationDecoder<'a> {
             *offset += column.data_type().size();
         }
 
-        let kind = match OperationTypePb::from_i32(op_type).unwrap_or_default() {
-            OperationTypePb::Insert => OperationKind::Insert,
-            OperationTypePb::Update => OperationKind::Update,
-            OperationTypePb::Delete => OperationKind::Delete,
-            OperationTypePb::Upsert => OperationKind::Upsert,
-            other => panic!("unexpected operation type: {:?}", other),
-        };
+        let kind = OperationKind::from_wire_type(op_type)
+            .unwrap_or_else(|| panic!("unexpected operation type: {}", op_type));
 
         Some(Operation { row, kind })
     }
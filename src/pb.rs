@@ -14,10 +14,17 @@ mod kudu {
     pub mod consensus {
         include!(concat!(env!("OUT_DIR"), "/kudu.consensus.rs"));
     }
+    pub mod hms {
+        include!(concat!(env!("OUT_DIR"), "/kudu.hms.rs"));
+    }
     pub mod master {
         include!(concat!(env!("OUT_DIR"), "/kudu.master.rs"));
 
     }
+    #[cfg(test)]
+    pub mod rpc {
+        include!(concat!(env!("OUT_DIR"), "/kudu.rpc.rs"));
+    }
     pub mod tablet {
         include!(concat!(env!("OUT_DIR"), "/kudu.tablet.rs"));
     }
@@ -25,6 +25,7 @@ impl DataType {
                 DataType::Double,
                 DataType::String,
                 DataType::Binary,
+                DataType::Date,
             ][..],
         )
     }
@@ -39,6 +40,7 @@ impl DataType {
                 DataType::Timestamp,
                 DataType::String,
                 DataType::Binary,
+                DataType::Date,
             ][..],
         )
     }
@@ -51,7 +53,8 @@ impl EncodingType {
             | DataType::Int16
             | DataType::Int32
             | DataType::Int64
-            | DataType::Timestamp => sample::select(
+            | DataType::Timestamp
+            | DataType::Date => sample::select(
                 &[
                     EncodingType::Auto,
                     EncodingType::Plain,
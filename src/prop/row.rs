@@ -40,6 +40,10 @@ impl Strategy for RowStrategy {
                         DataType::Int8 => row.set_unchecked(idx, rng.gen::<i8>()),
                         DataType::Int16 => row.set_unchecked(idx, rng.gen::<i16>()),
                         DataType::Int32 => row.set_unchecked(idx, rng.gen::<i32>()),
+                        // Kudu only accepts dates between 0001-01-01 and 9999-12-31.
+                        DataType::Date => {
+                            row.set_unchecked(idx, rng.gen_range::<i32>(-719_162, 2_932_897))
+                        }
                         DataType::Int64 | DataType::Timestamp => {
                             row.set_unchecked(idx, rng.gen::<i64>())
                         }
@@ -138,7 +142,7 @@ impl ColumnTree {
             DataType::Int16 => {
                 ColumnTree::Int16(num::i16::BinarySearch::new(row.get(idx).unwrap()))
             }
-            DataType::Int32 => {
+            DataType::Int32 | DataType::Date => {
                 ColumnTree::Int32(num::i32::BinarySearch::new(row.get(idx).unwrap()))
             }
             DataType::Int64 | DataType::Timestamp => {
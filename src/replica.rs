@@ -38,6 +38,8 @@ pub enum Speculation {
 pub(crate) enum Selection {
     Leader,
     Closest,
+    /// Only the replica at the index is attempted, regardless of its role.
+    Replica(usize),
 }
 
 impl Selection {
@@ -65,6 +67,9 @@ impl Selection {
                     queue.push_back(ReplicaState::new(idx, backoff.clone(), replica.proxy()));
                 }
             },
+            Selection::Replica(idx) => {
+                queue.push_back(ReplicaState::new(*idx, backoff, replicas[*idx].proxy()));
+            }
         }
         queue
     }
@@ -94,6 +99,10 @@ pub(crate) trait ReplicaSet {
     type Replica: Replica;
 
     fn replicas(&self) -> &[Self::Replica];
+
+    /// Marks the cached replica locations as out of date, so that they are refreshed before
+    /// subsequent RPCs are sent.
+    fn invalidate(&self) {}
 }
 
 /// A container holding the internal, intermediate state of a replica.
@@ -151,6 +160,15 @@ where
 
     /// Holds replicas which have failed with non-retriable errors.
     failures: Vec<ReplicaState>,
+
+    /// The number of RPCs which have been sent, including speculative and retried RPCs.
+    attempts: usize,
+
+    /// The maximum number of RPCs which may be sent before retriable failures are given up on.
+    max_attempts: Option<usize>,
+
+    /// The index of the replica which successfully responded, once the RPC has completed.
+    responder: Option<usize>,
 }
 
 impl<Set, Req, Resp> ReplicaRpc<Set, Req, Resp>
@@ -166,6 +184,7 @@ where
         selection: Selection,
         backoff: Backoff,
     ) -> ReplicaRpc<Set, Req, Resp> {
+        let max_attempts = backoff.max_attempts();
         let queue = selection.prioritize(replica_set.replicas(), backoff);
         ReplicaRpc {
             replica_set,
@@ -177,6 +196,40 @@ where
             backoff: FuturesUnordered::new(),
             speculative_timer: None,
             failures: Vec::new(),
+            attempts: 0,
+            max_attempts,
+            responder: None,
+        }
+    }
+
+    /// Returns the number of RPCs which have been sent, including speculative and retried RPCs.
+    pub(crate) fn attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Returns the index within the replica set of the replica which successfully responded, or
+    /// `None` if the RPC has not succeeded.
+    pub(crate) fn responder(&self) -> Option<usize> {
+        self.responder
+    }
+
+    /// Schedules a replica which failed with a retriable error to be retried after a backoff
+    /// period. If the retry budget is spent, the replica is failed instead.
+    fn retry(&mut self, mut replica: ReplicaState, error: Error) {
+        match self.max_attempts {
+            Some(max_attempts) if self.attempts >= max_attempts => {
+                replica.failure = Some(Error::RetriesExhausted {
+                    attempts: self.attempts,
+                    last_error: Box::new(error),
+                });
+                self.failures.push(replica);
+            }
+            _ => {
+                replica.failure = Some(error);
+                let backoff = Delay::new(Instant::now() + replica.backoff.next_backoff());
+                let context = ContextFuture::new(backoff, replica);
+                self.backoff.push(context);
+            }
         }
     }
 
@@ -202,16 +255,18 @@ where
             match self.speculation {
                 Speculation::Full => for mut replica in self.queue.drain(..) {
                     // Completely drain the queue and issue RPCs against all replicas.
-                    let rpc = replica.proxy.send(self.call.clone());
+                    let rpc = replica.proxy.send(attempt(&self.call, self.attempts));
                     let context = ContextFuture::new(rpc, replica);
                     self.in_flight.push(context);
+                    self.attempts += 1;
                 },
                 Speculation::Staggered(duration) => {
                     if self.speculation_timer_is_ready() {
                         let mut replica = self.queue.pop_front().unwrap();
-                        let rpc = replica.proxy.send(self.call.clone());
+                        let rpc = replica.proxy.send(attempt(&self.call, self.attempts));
                         let context = ContextFuture::new(rpc, replica);
                         self.in_flight.push(context);
+                        self.attempts += 1;
                         self.reset_speculation_timer(duration);
                     }
                 }
@@ -219,9 +274,13 @@ where
         }
 
         while let Async::Ready(Some((_, mut replica))) = self.backoff.poll().unwrap() {
-            let rpc = replica.proxy.send(self.call.clone());
+            if let Some(metrics) = replica.proxy.metrics() {
+                metrics.on_retry(self.call.service(), self.call.method());
+            }
+            let rpc = replica.proxy.send(attempt(&self.call, self.attempts));
             let context = ContextFuture::new(rpc, replica);
             self.in_flight.push(context);
+            self.attempts += 1;
         }
         in_flight_count != self.in_flight.len()
     }
@@ -240,8 +299,17 @@ where
             match response {
                 Ok((response, sidecars)) => {
                     if self.selection == Selection::Leader {
-                        self.replica_set.replicas()[replica.index].mark_leader();
+                        // Only one replica may lead at a time, so any other replica which was
+                        // previously the leader has since lost leadership.
+                        for (idx, other) in self.replica_set.replicas().iter().enumerate() {
+                            if idx == replica.index {
+                                other.mark_leader();
+                            } else {
+                                other.mark_follower();
+                            }
+                        }
                     }
+                    self.responder = Some(replica.index);
                     return Ok(Async::Ready((replica.proxy, response, sidecars)));
                 }
 
@@ -269,7 +337,7 @@ where
                             // TODO: implement the higher level retry mechanism mentioned above.
                             return Err(error);
                         }
-                        Selection::Closest => {
+                        Selection::Closest | Selection::Replica(_) => {
                             // If we aren't relying on finding the leader then we can continue
                             // trying the RPC at other replicas.
                             replica.failure = Some(error);
@@ -303,15 +371,51 @@ where
                     }),
                 ) => {
                     self.replica_set.replicas()[replica.index].mark_follower();
-                    // Retry the RPC after a backoff period.
-                    replica.failure = Some(error);
-                    let backoff = Delay::new(Instant::now() + replica.backoff.next_backoff());
-                    let context = ContextFuture::new(backoff, replica);
-                    self.backoff.push(context);
+                    match error {
+                        Error::TabletServer(TabletServerError {
+                            code: TabletServerErrorCode::NotTheLeader,
+                            ..
+                        })
+                        | Error::Master(MasterError {
+                            code: MasterErrorCode::NotTheLeader,
+                            ..
+                        }) => {
+                            // Leadership has moved, so the cached locations may not include the
+                            // new leader. Subsequent lookups will rediscover it.
+                            self.replica_set.invalidate();
+                        }
+                        _ => (),
+                    }
+                    self.retry(replica, error);
+                }
+
+                Err(
+                    error @ Error::TabletServer(TabletServerError {
+                        code: TabletServerErrorCode::Throttled,
+                        ..
+                    }),
+                ) => {
+                    // The tablet server is rate limiting writes to the tablet. Back off before
+                    // retrying, without assuming a leadership change.
+                    self.retry(replica, error);
+                }
+
+                Err(error @ Error::Rpc(..)) => {
+                    // Retry the RPC after a backoff period if the server is overloaded or
+                    // temporarily unavailable.
+                    if error.is_retriable() {
+                        self.retry(replica, error);
+                    } else {
+                        return Err(error);
+                    }
                 }
 
                 Err(error @ Error::Io(..)) => {
-                    // IO errors are non-retriable, however they are not fatal.
+                    // IO errors are non-retriable, however they are not fatal. An unreachable
+                    // leader, such as a leader master which has failed, is no longer preferred.
+                    if self.selection == Selection::Leader {
+                        self.replica_set.replicas()[replica.index].mark_follower();
+                    }
                     replica.failure = Some(error);
                     self.failures.push(replica);
                 }
@@ -406,3 +510,175 @@ impl Replica for Proxy {
         unimplemented!()
     }
 }
+
+/// Returns a copy of the call to send as the attempt with the (zero-based) attempt number.
+///
+/// Retries of a call carry the same request ID as the original attempt, apart from the attempt
+/// number, so that servers can recognize them as retries.
+fn attempt<Req, Resp>(call: &Call<Req, Resp>, attempt_no: usize) -> Call<Req, Resp>
+where
+    Req: Message + 'static,
+    Resp: Message + Default,
+{
+    let mut call = call.clone();
+    if let Some(mut request_id) = call.request_id().cloned() {
+        request_id.attempt_no = attempt_no as i64;
+        call.set_request_id(request_id);
+    }
+    call
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use krpc;
+    use mini_cluster::{MiniCluster, MiniClusterConfig};
+    use pb::master::MasterService;
+    use rpc_proxy::{Action, RpcProxy};
+    use Client;
+    use Options;
+    use RequestId;
+    use RpcErrorCode;
+
+    use env_logger;
+    use futures::future;
+    use tokio::runtime::current_thread::Runtime;
+
+    /// A replica which ignores leadership and staleness updates.
+    struct TestReplica(Proxy);
+
+    impl Replica for TestReplica {
+        fn proxy(&self) -> Proxy {
+            self.0.clone()
+        }
+        fn is_leader(&self) -> bool {
+            false
+        }
+        fn mark_leader(&self) {}
+        fn mark_follower(&self) {}
+        fn is_stale(&self) -> bool {
+            false
+        }
+        fn mark_stale(&self) {}
+    }
+
+    struct TestReplicaSet(Vec<TestReplica>);
+
+    impl ReplicaSet for TestReplicaSet {
+        type Replica = TestReplica;
+        fn replicas(&self) -> &[TestReplica] {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn retries_exhausted() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(3).num_tservers(0));
+        let mut runtime = Runtime::new().unwrap();
+
+        // Wait for a leader master to be elected.
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        runtime.block_on(client.masters()).expect("masters");
+
+        // Follower masters reject catalog RPCs with NOT_THE_LEADER, which is retriable, so RPCs
+        // sent directly to a follower are retried until the attempts are spent.
+        let mut leaders = 0;
+        let mut followers = 0;
+        for addr in cluster.master_addrs() {
+            let result = runtime.block_on(future::lazy(move || {
+                let proxy = Proxy::spawn(vec![addr].into_boxed_slice(), krpc::Options::default());
+                let call = MasterService::list_tables(
+                    Default::default(),
+                    Instant::now() + Duration::from_secs(10),
+                );
+                ReplicaRpc::new(
+                    TestReplicaSet(vec![TestReplica(proxy)]),
+                    call,
+                    Speculation::Full,
+                    Selection::Closest,
+                    Backoff::with_duration_range(10, 100).with_max_attempts(Some(3)),
+                )
+            }));
+
+            match result {
+                Ok(_) => leaders += 1,
+                Err(Error::RetriesExhausted {
+                    attempts,
+                    last_error,
+                }) => {
+                    assert_eq!(3, attempts);
+                    match *last_error {
+                        Error::Master(MasterError {
+                            code: MasterErrorCode::NotTheLeader,
+                            ..
+                        }) => (),
+                        error => panic!("unexpected error: {}", error),
+                    }
+                    followers += 1;
+                }
+                Err(error) => panic!("unexpected error: {}", error),
+            }
+        }
+        assert_eq!(1, leaders);
+        assert_eq!(2, followers);
+    }
+
+    #[test]
+    fn retries_keep_request_id() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(MiniClusterConfig::default().num_tservers(0));
+        let mut runtime = Runtime::new().unwrap();
+
+        // The master is too busy to handle the first two attempts.
+        let mut attempts = 0;
+        let busy_master = RpcProxy::spawn(cluster.master_addrs()[0].clone(), move |_| {
+            attempts += 1;
+            if attempts <= 2 {
+                Action::Fail(RpcErrorCode::ErrorServerTooBusy)
+            } else {
+                Action::Forward
+            }
+        });
+        let addr = busy_master.addr();
+
+        let request_id = RequestId {
+            client_id: "retries_keep_request_id".to_owned(),
+            seq_no: 7,
+            first_incomplete_seq_no: 5,
+            attempt_no: 0,
+        };
+        let call_request_id = request_id.clone();
+        let result = runtime.block_on(future::lazy(move || {
+            let proxy = Proxy::spawn(vec![addr].into_boxed_slice(), krpc::Options::default());
+            let mut call = MasterService::list_tables(
+                Default::default(),
+                Instant::now() + Duration::from_secs(10),
+            );
+            call.set_request_id(call_request_id);
+            ReplicaRpc::new(
+                TestReplicaSet(vec![TestReplica(proxy)]),
+                call,
+                Speculation::Full,
+                Selection::Closest,
+                Backoff::with_duration_range(10, 100).with_max_attempts(Some(3)),
+            )
+        }));
+        result.expect("list tables");
+
+        // Every attempt carries the same request ID, with an increasing attempt number.
+        let requests = busy_master.requests();
+        assert_eq!(3, requests.len());
+        for (attempt_no, header) in requests.iter().enumerate() {
+            let sent = header.request_id.as_ref().expect("request ID");
+            assert_eq!(request_id.client_id, sent.client_id);
+            assert_eq!(request_id.seq_no, sent.seq_no);
+            assert_eq!(request_id.first_incomplete_seq_no, sent.first_incomplete_seq_no);
+            assert_eq!(attempt_no as i64, sent.attempt_no);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use rand;
+use uuid::Uuid;
+
+use RequestId;
+
+/// Generates the request IDs which identify the logical operations of a client.
+///
+/// A request ID is generated for each logical operation, such as an admin RPC to the master or a
+/// tablet server scan or write request, and is sent in the header of every RPC of the operation,
+/// including retries. Kudu uses the ID to correlate the RPCs in its logs and traces, and tablet
+/// servers use it to apply each write exactly once, even if the write is retried after its
+/// response is lost.
+///
+/// Write requests are not identified by generated request IDs. Tablet servers rely on the client
+/// ID and sequence number of a write to apply it exactly once, so the client identifies its
+/// writes by a random client ID of its own, which is never shared with other clients, and
+/// numbers them itself.
+///
+/// Generators are installed with `Options::set_request_id_generator`.
+pub trait RequestIdGenerator: Send + Sync {
+    /// Returns the request ID of a new logical operation.
+    fn next_request_id(&self) -> RequestId;
+}
+
+/// The default request ID generator, which identifies the client with a random UUID and numbers
+/// its operations sequentially.
+pub(crate) struct SequentialRequestIds {
+    client_id: String,
+    seq_no: AtomicUsize,
+}
+
+impl SequentialRequestIds {
+    pub fn new() -> SequentialRequestIds {
+        SequentialRequestIds {
+            client_id: Uuid::from_random_bytes(rand::random()).simple().to_string(),
+            seq_no: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl RequestIdGenerator for SequentialRequestIds {
+    fn next_request_id(&self) -> RequestId {
+        RequestId {
+            client_id: self.client_id.clone(),
+            seq_no: self.seq_no.fetch_add(1, Ordering::Relaxed) as i64,
+            first_incomplete_seq_no: 0,
+            attempt_no: 0,
+        }
+    }
+}
+
+/// Assigns request IDs from the client's generator, and numbers and tracks the client's in-flight
+/// writes.
+pub(crate) struct RequestTracker {
+    generator: Arc<RequestIdGenerator>,
+    /// The client ID of writes, which is unique to the tracker regardless of the generator, so
+    /// that tablet servers never mistake the writes of different clients for retries.
+    write_client_id: String,
+    writes: Mutex<Writes>,
+}
+
+/// The sequence number of the next write, and the sequence numbers of the incomplete writes.
+struct Writes {
+    next_seq_no: i64,
+    incomplete: BTreeSet<i64>,
+}
+
+impl RequestTracker {
+    pub fn new(generator: Arc<RequestIdGenerator>) -> RequestTracker {
+        RequestTracker {
+            generator,
+            write_client_id: Uuid::from_random_bytes(rand::random()).simple().to_string(),
+            writes: Mutex::new(Writes {
+                next_seq_no: 0,
+                incomplete: BTreeSet::new(),
+            }),
+        }
+    }
+
+    /// Returns the request ID of an operation which is not tracked to completion.
+    pub fn next_request_id(&self) -> RequestId {
+        self.generator.next_request_id()
+    }
+}
+
+/// The request ID of a write, which is incomplete until the tracked request is dropped.
+///
+/// The first incomplete sequence number of the request ID is the lowest sequence number of the
+/// client's incomplete writes, which tells tablet servers that the responses of lower sequence
+/// numbers no longer need to be retained. Sequence numbers are assigned in increasing order while
+/// holding the tracker's lock, so a write is always tracked as incomplete before a later write
+/// can compute its first incomplete sequence number.
+pub(crate) struct TrackedRequest {
+    tracker: Arc<RequestTracker>,
+    request_id: RequestId,
+}
+
+impl TrackedRequest {
+    pub fn new(tracker: Arc<RequestTracker>) -> TrackedRequest {
+        let request_id = {
+            let mut writes = tracker.writes.lock();
+            let seq_no = writes.next_seq_no;
+            writes.next_seq_no += 1;
+            writes.incomplete.insert(seq_no);
+            RequestId {
+                client_id: tracker.write_client_id.clone(),
+                seq_no,
+                first_incomplete_seq_no: *writes.incomplete.iter().next().unwrap(),
+                attempt_no: 0,
+            }
+        };
+        TrackedRequest {
+            tracker,
+            request_id,
+        }
+    }
+
+    pub fn request_id(&self) -> &RequestId {
+        &self.request_id
+    }
+}
+
+impl Drop for TrackedRequest {
+    fn drop(&mut self) {
+        self.tracker
+            .writes
+            .lock()
+            .incomplete
+            .remove(&self.request_id.seq_no);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use env_logger;
+    use tokio::runtime::current_thread::Runtime;
+
+    use super::*;
+    use mini_cluster::{MiniCluster, MiniClusterConfig};
+    use Client;
+    use Options;
+
+    /// Records the request IDs generated by the default generator.
+    struct RecordingRequestIds {
+        generator: SequentialRequestIds,
+        ids: Mutex<Vec<RequestId>>,
+    }
+
+    impl RequestIdGenerator for RecordingRequestIds {
+        fn next_request_id(&self) -> RequestId {
+            let request_id = self.generator.next_request_id();
+            self.ids.lock().push(request_id.clone());
+            request_id
+        }
+    }
+
+    #[test]
+    fn sequential_request_ids() {
+        let generator = SequentialRequestIds::new();
+        let a = generator.next_request_id();
+        let b = generator.next_request_id();
+        assert_eq!(a.client_id, b.client_id);
+        assert_eq!(32, a.client_id.len());
+        assert_ne!(a.seq_no, b.seq_no);
+
+        let other = SequentialRequestIds::new();
+        assert_ne!(a.client_id, other.next_request_id().client_id);
+    }
+
+    #[test]
+    fn tracked_requests() {
+        let tracker = Arc::new(RequestTracker::new(Arc::new(SequentialRequestIds::new())));
+        let untracked = tracker.next_request_id();
+
+        let a = TrackedRequest::new(tracker.clone());
+        let b = TrackedRequest::new(tracker.clone());
+        assert_ne!(untracked.client_id, a.request_id().client_id);
+        assert_eq!(a.request_id().client_id, b.request_id().client_id);
+        assert_eq!(a.request_id().seq_no + 1, b.request_id().seq_no);
+        assert_eq!(a.request_id().seq_no, a.request_id().first_incomplete_seq_no);
+        assert_eq!(a.request_id().seq_no, b.request_id().first_incomplete_seq_no);
+
+        // Completing the oldest write advances the first incomplete sequence number.
+        drop(a);
+        let c = TrackedRequest::new(tracker.clone());
+        assert_eq!(b.request_id().seq_no, c.request_id().first_incomplete_seq_no);
+
+        drop(b);
+        drop(c);
+        let d = TrackedRequest::new(tracker);
+        assert_eq!(d.request_id().seq_no, d.request_id().first_incomplete_seq_no);
+    }
+
+    /// Returns the same request ID for every operation.
+    struct ConstantRequestIds;
+
+    impl RequestIdGenerator for ConstantRequestIds {
+        fn next_request_id(&self) -> RequestId {
+            RequestId {
+                client_id: "constant".to_owned(),
+                seq_no: 7,
+                first_incomplete_seq_no: 0,
+                attempt_no: 0,
+            }
+        }
+    }
+
+    #[test]
+    fn tracked_requests_ignore_generated_ids() {
+        let generator = Arc::new(ConstantRequestIds);
+        let tracker = Arc::new(RequestTracker::new(generator.clone()));
+
+        let a = TrackedRequest::new(tracker.clone());
+        let b = TrackedRequest::new(tracker.clone());
+        assert_ne!("constant", a.request_id().client_id);
+        assert_eq!(a.request_id().client_id, b.request_id().client_id);
+        assert!(a.request_id().seq_no < b.request_id().seq_no);
+
+        // Trackers sharing a generator identify their writes by different client IDs.
+        let other = TrackedRequest::new(Arc::new(RequestTracker::new(generator)));
+        assert_ne!(a.request_id().client_id, other.request_id().client_id);
+
+        // Sequence numbers are never reused, even after the writes complete.
+        let b_seq_no = b.request_id().seq_no;
+        drop(a);
+        drop(b);
+        let c = TrackedRequest::new(tracker);
+        assert!(b_seq_no < c.request_id().seq_no);
+        assert_eq!(c.request_id().seq_no, c.request_id().first_incomplete_seq_no);
+    }
+
+    #[test]
+    fn request_id_per_operation() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().num_masters(1).num_tservers(0));
+        let mut runtime = Runtime::new().unwrap();
+
+        let request_ids = Arc::new(RecordingRequestIds {
+            generator: SequentialRequestIds::new(),
+            ids: Mutex::new(Vec::new()),
+        });
+        let mut options = Options::default();
+        options.set_request_id_generator(request_ids.clone());
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+
+        let before = request_ids.ids.lock().len();
+        runtime.block_on(client.masters()).expect("masters");
+        runtime.block_on(client.tablet_servers()).expect("tablet_servers");
+
+        let ids = request_ids.ids.lock();
+        assert_eq!(before + 2, ids.len());
+        assert_ne!(ids[ids.len() - 2], ids[ids.len() - 1]);
+    }
+}
@@ -170,6 +170,7 @@ master_response!(IsCreateTableDoneResponsePb);
 master_response!(ListMastersResponsePb);
 master_response!(ListTablesResponsePb);
 master_response!(ListTabletServersResponsePb);
+master_response!(RecallDeletedTableResponsePb);
 
 macro_rules! tserver_response {
     ($type:ident) => {
@@ -2,15 +2,28 @@ use std::cmp;
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
+use std::ptr;
 use std::slice;
 use std::str;
 
+#[cfg(feature = "serde")]
+use serde_json;
+
 use util;
 use value::{read_var_len_value, write_var_len_value, Value};
 use ColumnSelector;
+use DataType;
+use Datum;
 use Error;
 use Result;
 use Schema;
+#[cfg(feature = "serde")]
+use TimestampFormat;
+
+/// A row which owns all of its values.
+///
+/// Rows borrowed from a `RowBatch` can be converted into an owned row with `Row::into_owned`.
+pub type OwnedRow = Row<'static>;
 
 /// Returns the size of the data array for a partial row with the given schema.
 fn partial_row_data_len(schema: &Schema) -> usize {
@@ -36,7 +49,6 @@ pub struct Row<'data> {
     _marker: PhantomData<&'data [u8]>,
 }
 
-// TODO: unset
 impl<'data> Row<'data> {
     /// Creates an empty mutable partial row.
     pub(crate) fn partial(schema: Schema) -> Row<'data> {
@@ -56,6 +68,48 @@ impl<'data> Row<'data> {
         }
     }
 
+    /// Creates an empty mutable partial row using a data array from `RowPool`. The data array
+    /// must be sized for the schema, and the bitmaps must be zeroed.
+    fn partial_from_buffer(schema: Schema, mut data: Vec<u8>) -> Row<'data> {
+        debug_assert_eq!(data.len(), partial_row_data_len(&schema));
+        debug_assert_eq!(data.capacity(), partial_row_data_len(&schema));
+        let ptr = data.as_mut_ptr() as i64;
+        mem::forget(data);
+
+        Row {
+            tagged_ptr: (ptr << 1) | 1,
+            schema,
+            _marker: PhantomData::default(),
+        }
+    }
+
+    /// Releases the owned values of a partial row, and returns its data array with the bitmaps
+    /// zeroed. Returns `None` if the row is contiguous, since the data array is borrowed.
+    fn into_buffer(mut self) -> Option<Vec<u8>> {
+        if self.is_contiguous_row() {
+            return None;
+        }
+
+        let data_len = partial_row_data_len(&self.schema);
+        let row_len = self.schema.row_len();
+        let mut data = unsafe {
+            for idx in 0..self.schema.columns().len() {
+                self.deallocate(idx);
+            }
+            let data = Vec::from_raw_parts(self.data_mut(), data_len, data_len);
+
+            // The data array is now owned by the Vec, so the row must not be dropped.
+            drop(ptr::read(&self.schema));
+            mem::forget(self);
+            data
+        };
+
+        for byte in &mut data[row_len..] {
+            *byte = 0;
+        }
+        Some(data)
+    }
+
     /// Creates a constant contiguous row referencing the provided data.
     pub(crate) fn contiguous(schema: Schema, data: &[u8]) -> Row {
         let tagged_ptr = (data.as_ptr() as i64) << 1;
@@ -133,10 +187,39 @@ impl<'data> Row<'data> {
         Ok(self)
     }
 
+    /// Unsets the column, releasing any owned value held by the row.
+    ///
+    /// Returns an error if the column does not exist.
+    pub fn unset<C>(&mut self, column: C) -> Result<&mut Row<'data>>
+    where
+        C: ColumnSelector,
+    {
+        let idx = column.column_index(&self.schema)?;
+
+        if self.is_contiguous_row() {
+            self.into_partial_row();
+        }
+
+        unsafe {
+            self.deallocate(idx);
+            bitmap_clear(self.data_mut().offset(self.is_set_offset()), idx);
+            if self.schema.has_nullable_columns() {
+                bitmap_clear(self.data_mut().offset(self.is_null_offset()), idx);
+            }
+        }
+        Ok(self)
+    }
+
     /// Gets the value of the column.
     ///
     /// Returns an error if the column does not exist, the column is unset, the column type
     /// does not match the value type.
+    ///
+    /// The value type is checked against the column type at runtime, so reading a column whose
+    /// type is not known statically fails with `Error::InvalidArgument` instead of panicking.
+    /// Value types may be read from any column with the same physical type; for instance, an
+    /// `i64` may be read from a `Timestamp` column, and a `&[u8]` may be read from a `String`
+    /// column.
     pub fn get<'self_, C, V>(&'self_ self, column: C) -> Result<V>
     where
         C: ColumnSelector,
@@ -170,6 +253,109 @@ impl<'data> Row<'data> {
         }
     }
 
+    /// Gets the value of a binary or string column as a byte slice.
+    ///
+    /// The slice borrows directly from the row's data; for rows returned by a scan, it points into
+    /// the `RowBatch`'s indirect data buffer, and no copy is made.
+    ///
+    /// Returns an error if the column does not exist, the column is unset or null, or the column
+    /// is not a binary or string column.
+    pub fn get_bytes<C>(&self, column: C) -> Result<&[u8]>
+    where
+        C: ColumnSelector,
+    {
+        self.get(column)
+    }
+
+    /// Gets the value of a string column as a string slice, without copying.
+    ///
+    /// Returns an error if the column does not exist, the column is unset or null, the column is
+    /// not a string or binary column, or the value is not valid UTF-8.
+    pub fn get_str<C>(&self, column: C) -> Result<&str>
+    where
+        C: ColumnSelector,
+    {
+        self.get(column)
+    }
+
+    /// Gets the value of the column as a dynamically-typed `Datum`.
+    ///
+    /// Returns an error if the column does not exist, or the column is unset.
+    pub fn get_datum<C>(&self, column: C) -> Result<Datum>
+    where
+        C: ColumnSelector,
+    {
+        let idx = column.column_index(&self.schema)?;
+        if self.is_null(idx)? {
+            return Ok(Datum::Null);
+        }
+        Ok(match self.schema.columns()[idx].data_type() {
+            DataType::Bool => Datum::Bool(self.get(idx)?),
+            DataType::Int8 => Datum::Int8(self.get(idx)?),
+            DataType::Int16 => Datum::Int16(self.get(idx)?),
+            DataType::Int32 => Datum::Int32(self.get(idx)?),
+            DataType::Int64 => Datum::Int64(self.get(idx)?),
+            DataType::Timestamp => Datum::Timestamp(self.get(idx)?),
+            DataType::Float => Datum::Float(self.get(idx)?),
+            DataType::Double => Datum::Double(self.get(idx)?),
+            DataType::Binary => Datum::Binary(self.get(idx)?),
+            DataType::String => Datum::String(self.get(idx)?),
+            DataType::Date => Datum::Date(self.get(idx)?),
+        })
+    }
+
+    /// Sets the value of the column from a dynamically-typed `Datum`.
+    ///
+    /// Returns an error if the column does not exist, or the datum's type is wrong.
+    pub fn set_datum<C>(&mut self, column: C, datum: Datum) -> Result<&mut Row<'data>>
+    where
+        C: ColumnSelector,
+    {
+        match datum {
+            Datum::Bool(value) => self.set(column, value),
+            Datum::Int8(value) => self.set(column, value),
+            Datum::Int16(value) => self.set(column, value),
+            Datum::Int32(value) => self.set(column, value),
+            Datum::Int64(value) => self.set(column, value),
+            Datum::Timestamp(value) => self.set(column, value),
+            Datum::Float(value) => self.set(column, value),
+            Datum::Double(value) => self.set(column, value),
+            Datum::Binary(value) => self.set(column, value),
+            Datum::String(value) => self.set(column, value),
+            Datum::Date(value) => self.set(column, value),
+            Datum::Null => self.set_null(column),
+        }
+    }
+
+    /// Converts the row to a JSON object with a field per set column.
+    ///
+    /// Binary values are base64 encoded, and timestamps are formatted as RFC 3339 strings.
+    /// Non-finite floating point values are converted to null.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Value {
+        self.to_json_with_timestamp_format(TimestampFormat::default())
+    }
+
+    /// Converts the row to a JSON object with a field per set column, formatting timestamps
+    /// according to `timestamp_format`.
+    #[cfg(feature = "serde")]
+    pub fn to_json_with_timestamp_format(
+        &self,
+        timestamp_format: TimestampFormat,
+    ) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        for (idx, column) in self.schema.columns().iter().enumerate() {
+            if !unsafe { self.is_set_unchecked(idx) } {
+                continue;
+            }
+            let datum = self
+                .get_datum(idx)
+                .expect("set column value must be readable as its own type");
+            object.insert(column.name().to_owned(), datum.into_json(timestamp_format));
+        }
+        serde_json::Value::Object(object)
+    }
+
     /// Returns `true` if the column at index `idx` is null.
     ///
     /// The result is undefined if the `idx` is not valid.
@@ -209,6 +395,14 @@ impl<'data> Row<'data> {
         Ok(unsafe { self.is_set_unchecked(idx) })
     }
 
+    /// Returns whether the row has been deleted, if the row was returned by a scan which projects
+    /// the `IS_DELETED` virtual column, such as a diff scan. Otherwise, returns `None`.
+    pub fn is_deleted(&self) -> Option<bool> {
+        self.schema
+            .is_deleted_column()
+            .map(|idx| self.get::<_, bool>(idx).expect("IS_DELETED column is not set"))
+    }
+
     /// Returns the schema of the row.
     pub fn schema(&self) -> &Schema {
         &self.schema
@@ -282,10 +476,32 @@ impl<'data> Row<'data> {
         (self.tagged_ptr >> 1) as _
     }
 
+    /// Converts a contiguous row into a mutable partial row by copying the row data into a new
+    /// data array with an is-set bitmap. Variable length values remain borrowed.
     #[inline(never)]
     fn into_partial_row(&mut self) {
-        debug_assert!(self.is_partial_row());
-        unimplemented!()
+        debug_assert!(self.is_contiguous_row());
+        let data_len = partial_row_data_len(&self.schema);
+        let contiguous_len = self.schema.row_len()
+            + self.schema.has_nullable_columns() as usize * self.schema.bitmap_len();
+
+        let mut data = vec![0u8; data_len];
+        debug_assert_eq!(data.len(), data_len);
+        debug_assert_eq!(data.capacity(), data_len);
+        data[..contiguous_len]
+            .copy_from_slice(unsafe { slice::from_raw_parts(self.data(), contiguous_len) });
+
+        // Every column in a contiguous row is set.
+        let ptr = data.as_mut_ptr();
+        mem::forget(data);
+        unsafe {
+            let is_set = ptr.offset(contiguous_len as isize);
+            for idx in 0..self.schema.columns().len() {
+                bitmap_set(is_set, idx);
+            }
+        }
+
+        self.tagged_ptr = ((ptr as i64) << 1) | 1;
     }
 
     /// Copies all borrowed values into a new row with a `'static` lifetime.
@@ -389,6 +605,7 @@ impl<'data> Row<'data> {
     where
         V: Value<'data>,
     {
+        self.schema.check_index(idx)?;
         let column = &self.schema.columns()[idx];
         if !V::can_read_from(column.data_type()) {
             return Err(Error::InvalidArgument(format!(
@@ -590,6 +807,67 @@ impl<'data> cmp::PartialOrd for Row<'data> {
     }
 }
 
+/// A pool of partial row data arrays, which allows new rows to be created without allocating.
+///
+/// Rows created by the pool are returned to it with `RowPool::recycle` once they are no longer
+/// needed, for instance after being applied to a writer. Only the fixed-length portion of the row
+/// is pooled; owned string and binary values are allocated and freed individually.
+pub struct RowPool {
+    schema: Schema,
+    buffers: Vec<Vec<u8>>,
+    max_size: usize,
+}
+
+impl RowPool {
+    /// Creates an empty pool for rows of the schema, which holds at most `max_size` data arrays.
+    pub fn new(schema: Schema, max_size: usize) -> RowPool {
+        RowPool {
+            schema,
+            buffers: Vec::new(),
+            max_size,
+        }
+    }
+
+    /// Returns an empty row, reusing a pooled data array if one is available.
+    pub fn new_row<'data>(&mut self) -> Row<'data> {
+        match self.buffers.pop() {
+            Some(buffer) => Row::partial_from_buffer(self.schema.clone(), buffer),
+            None => self.schema.new_row(),
+        }
+    }
+
+    /// Returns the row's data array to the pool. The row is dropped instead if the pool is full,
+    /// if the row's schema does not match the pool's schema, or if the row was not created as a
+    /// partial row.
+    pub fn recycle(&mut self, row: Row) {
+        if self.buffers.len() >= self.max_size || row.schema() != &self.schema {
+            return;
+        }
+        if let Some(buffer) = row.into_buffer() {
+            self.buffers.push(buffer);
+        }
+    }
+
+    /// Returns the number of data arrays in the pool.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Returns `true` if the pool holds no data arrays.
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+impl fmt::Debug for RowPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RowPool")
+            .field("len", &self.buffers.len())
+            .field("max_size", &self.max_size)
+            .finish()
+    }
+}
+
 impl<'data> Drop for Row<'data> {
     fn drop(&mut self) {
         if self.is_partial_row() {
@@ -631,6 +909,8 @@ unsafe fn bitmap_eq(a: *const u8, b: *const u8, bits: usize) -> bool {
 #[cfg(test)]
 mod tests {
 
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
     use super::*;
     use schema;
 
@@ -769,6 +1049,283 @@ mod tests {
         }
 
         assert!(!row.is_null("key").unwrap());
+        assert!(row.set_null("key").is_err());
+        assert!(row.set_null("string").is_err());
+        assert!(!row.is_set("string").unwrap());
+    }
+
+    #[test]
+    fn test_get_type_mismatch() {
+        let schema = schema::tests::all_types_schema();
+        let mut row = schema.new_row();
+        row.set("key", 1i32).unwrap();
+        row.set("bool", true).unwrap();
+        row.set("i8", 1i8).unwrap();
+        row.set("i16", 1i16).unwrap();
+        row.set("i32", 1i32).unwrap();
+        row.set("i64", 1i64).unwrap();
+        row.set("timestamp", UNIX_EPOCH).unwrap();
+        row.set("f32", 1f32).unwrap();
+        row.set("f64", 1f64).unwrap();
+        row.set("binary", &b"binary"[..]).unwrap();
+        row.set("string", "string").unwrap();
+
+        fn is_invalid_argument<T>(result: Result<T>) -> bool {
+            match result {
+                Err(Error::InvalidArgument(_)) => true,
+                _ => false,
+            }
+        }
+
+        assert!(is_invalid_argument(row.get::<_, i32>("bool")));
+        assert!(is_invalid_argument(row.get::<_, i16>("i8")));
+        assert!(is_invalid_argument(row.get::<_, i32>("i16")));
+        assert!(is_invalid_argument(row.get::<_, i64>("i32")));
+        assert!(is_invalid_argument(row.get::<_, SystemTime>("i32")));
+        assert!(is_invalid_argument(row.get::<_, f64>("i64")));
+        assert!(is_invalid_argument(row.get::<_, i32>("timestamp")));
+        assert!(is_invalid_argument(row.get::<_, f64>("f32")));
+        assert!(is_invalid_argument(row.get::<_, i64>("f64")));
+        assert!(is_invalid_argument(row.get::<_, &str>("binary")));
+        assert!(is_invalid_argument(row.get::<_, String>("binary")));
+        assert!(is_invalid_argument(row.get::<_, i32>("string")));
+        assert!(is_invalid_argument(row.get::<_, Option<i32>>("key")));
+        assert!(is_invalid_argument(row.get::<_, i32>("nullable_i32")));
+        assert!(is_invalid_argument(row.get::<_, i32>("bogus")));
+
+        // Types with the same physical type are compatible.
+        assert_eq!(1i64, row.get::<_, i64>("timestamp").unwrap() + 1);
+        assert_eq!(
+            UNIX_EPOCH + Duration::from_micros(1),
+            row.get::<_, SystemTime>("i64").unwrap()
+        );
+        assert_eq!(&b"string"[..], row.get::<_, &[u8]>("string").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_column() {
+        fn is_invalid_argument<T>(result: Result<T>) -> bool {
+            match result {
+                Err(Error::InvalidArgument(_)) => true,
+                _ => false,
+            }
+        }
+
+        let schema = schema::tests::all_types_schema();
+        let num_columns = schema.columns().len();
+        let mut row = schema.new_row();
+        assert!(is_invalid_argument(row.set(num_columns, 1i32)));
+        assert!(is_invalid_argument(row.set(usize::max_value(), 1i32)));
+        assert!(is_invalid_argument(row.set_null(num_columns)));
+        assert!(is_invalid_argument(row.unset(num_columns)));
+        assert!(is_invalid_argument(row.set_datum(num_columns, Datum::Int32(1))));
+        assert!(is_invalid_argument(row.get::<_, i32>(num_columns)));
+        assert!(is_invalid_argument(row.is_set(num_columns)));
+        assert!(is_invalid_argument(row.is_null(num_columns)));
+
+        // Columns outside of a projection are rejected, even though they exist in the table.
+        let projection = schema.primary_key_projection();
+        let mut row = projection.new_row();
+        row.set("key", 1i32).unwrap();
+        assert!(is_invalid_argument(row.set(1, 1i32)));
+        assert!(is_invalid_argument(row.set("string", "foo")));
+        assert!(is_invalid_argument(row.get::<_, &str>("string")));
+    }
+
+    #[test]
+    fn test_row_pool() {
+        let schema = schema::tests::all_types_schema();
+        let mut pool = RowPool::new(schema.clone(), 1);
+        assert!(pool.is_empty());
+
+        let mut row = pool.new_row();
+        row.set("key", 1i32).unwrap();
+        row.set("string", "borrowed").unwrap();
+        row.set("binary", b"owned".to_vec()).unwrap();
+        row.set_null("nullable_string").unwrap();
+        let data = row.data();
+
+        pool.recycle(row);
+        assert_eq!(1, pool.len());
+
+        // The data array is reused, and the recycled row's values are cleared.
+        let row = pool.new_row();
+        assert!(pool.is_empty());
+        assert_eq!(data, row.data());
+        for idx in 0..schema.columns().len() {
+            assert!(!row.is_set(idx).unwrap());
+        }
+
+        // The pool holds at most one data array.
+        pool.recycle(row);
+        pool.recycle(schema.new_row());
+        assert_eq!(1, pool.len());
+
+        // Contiguous rows and rows of other schemas are not pooled.
+        let mut pool = RowPool::new(schema.clone(), 8);
+        let contiguous_len = schema.row_len() + schema.bitmap_len();
+        let data = vec![0u8; contiguous_len];
+        pool.recycle(Row::contiguous(schema.clone(), &data));
+        pool.recycle(schema::tests::simple_schema().new_row());
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_contiguous_into_owned() {
+        let schema = schema::tests::all_types_schema();
+        let mut row = schema.new_row();
+        row.set("key", 1i32).unwrap();
+        row.set("bool", true).unwrap();
+        row.set("i8", 8i8).unwrap();
+        row.set("i16", 16i16).unwrap();
+        row.set("i32", 32i32).unwrap();
+        row.set("i64", 64i64).unwrap();
+        row.set("timestamp", UNIX_EPOCH).unwrap();
+        row.set("f32", 3.2f32).unwrap();
+        row.set("f64", 6.4f64).unwrap();
+        row.set("binary", &b"binary"[..]).unwrap();
+        row.set("string", "string").unwrap();
+        for idx in 11..schema.columns().len() {
+            row.set_null(idx).unwrap();
+        }
+        row.set("nullable_string", "nullable").unwrap();
+
+        let contiguous_len = schema.row_len() + schema.bitmap_len();
+        let data = unsafe { slice::from_raw_parts(row.data(), contiguous_len) }.to_owned();
+        let contiguous = Row::contiguous(schema.clone(), &data);
+
+        let mut owned = contiguous.clone().into_owned();
+        drop(data);
+        for idx in 0..schema.columns().len() {
+            assert!(owned.is_set(idx).unwrap());
+            assert_eq!(row.get_datum(idx).unwrap(), owned.get_datum(idx).unwrap());
+        }
+
+        owned.unset("string").unwrap();
+        assert!(!owned.is_set("string").unwrap());
+        assert_eq!("nullable", owned.get::<_, &str>("nullable_string").unwrap());
+    }
+
+    #[test]
+    fn test_datum() {
+        let schema = schema::tests::all_types_schema();
+        let mut row = schema.new_row();
+
+        let timestamp = UNIX_EPOCH + Duration::from_micros(1_514_768_523_456_789);
+        let data = vec![
+            ("key", Datum::Int32(42)),
+            ("bool", Datum::Bool(true)),
+            ("i8", Datum::Int8(-8)),
+            ("i16", Datum::Int16(-16)),
+            ("i32", Datum::Int32(-32)),
+            ("i64", Datum::Int64(-64)),
+            ("timestamp", Datum::Timestamp(timestamp)),
+            ("f32", Datum::Float(3.2)),
+            ("f64", Datum::Double(6.4)),
+            ("binary", Datum::Binary(vec![0, 1, 2])),
+            ("string", Datum::String("foo".to_string())),
+            ("nullable_i32", Datum::Null),
+            ("nullable_string", Datum::String("bar".to_string())),
+        ];
+
+        for (column, datum) in data.clone() {
+            row.set_datum(column, datum).unwrap();
+        }
+        for &(column, ref datum) in &data {
+            assert_eq!(datum, &row.get_datum(column).unwrap());
+        }
+
+        assert_eq!(row.get::<_, i32>("key").unwrap(), 42);
+        assert!(row.is_null("nullable_i32").unwrap());
+        assert_eq!(
+            Some(DataType::Timestamp),
+            row.get_datum("timestamp").unwrap().data_type()
+        );
+
+        // Unset columns and invalid types are errors.
+        assert!(row.get_datum("nullable_i64").is_err());
+        assert!(row.set_datum("i8", Datum::Int32(1)).is_err());
+        assert!(row.set_datum("string", Datum::Binary(vec![])).is_err());
+        assert!(row.set_datum("key", Datum::Null).is_err());
+        assert!(row.set_datum("bogus", Datum::Int32(1)).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_json_timestamp_format() {
+        let schema = schema::tests::all_types_schema();
+        let mut row = schema.new_row();
+        row.set("key", 1i32).unwrap();
+        row.set(
+            "timestamp",
+            UNIX_EPOCH + Duration::from_micros(1_514_768_523_456_789),
+        ).unwrap();
+
+        assert_eq!(
+            json!({"key": 1, "timestamp": "2018-01-01T01:02:03.456789Z"}),
+            row.to_json()
+        );
+        assert_eq!(
+            row.to_json(),
+            row.to_json_with_timestamp_format(TimestampFormat::Rfc3339)
+        );
+        assert_eq!(
+            json!({"key": 1, "timestamp": 1_514_768_523_456_789i64}),
+            row.to_json_with_timestamp_format(TimestampFormat::EpochMicros)
+        );
+    }
+
+    #[test]
+    fn test_unset() {
+        let schema = schema::tests::all_types_schema();
+        let mut row = schema.new_row();
+
+        row.set("key", 42i32).unwrap();
+        row.set("string", "foo".to_string()).unwrap();
+        row.set_null("nullable_string").unwrap();
+        assert!(row.is_set("key").unwrap());
+        assert!(row.is_set("string").unwrap());
+        assert!(row.is_set("nullable_string").unwrap());
+
+        row.unset("key").unwrap();
+        row.unset(10).unwrap();
+        row.unset("nullable_string").unwrap();
+        assert!(!row.is_set("key").unwrap());
+        assert!(!row.is_set("string").unwrap());
+        assert!(!row.is_set("nullable_string").unwrap());
+        assert!(!row.is_null("nullable_string").unwrap());
+        assert!(row.get::<_, &str>("string").is_err());
+        assert_eq!(schema.new_row(), row);
+
+        // Unsetting an unset column is a no-op.
+        row.unset("string").unwrap();
+        assert!(row.unset("bogus").is_err());
+
+        // The column can be set again after being unset.
+        row.set("string", "bar").unwrap();
+        assert_eq!("bar", row.get::<_, &str>("string").unwrap());
+    }
+
+    #[test]
+    fn test_unset_contiguous() {
+        let schema = schema::tests::all_types_schema();
+        let mut row = schema.new_row();
+        for idx in 0..schema.columns().len() {
+            if schema.columns()[idx].is_nullable() {
+                row.set_null(idx).unwrap();
+            }
+        }
+        row.set("key", 1i32).unwrap();
+        row.set("string", "string").unwrap();
+
+        let contiguous_len = schema.row_len() + schema.bitmap_len();
+        let data = unsafe { slice::from_raw_parts(row.data(), contiguous_len) }.to_owned();
+        let mut contiguous = Row::contiguous(schema.clone(), &data);
+
+        contiguous.unset("key").unwrap();
+        assert!(!contiguous.is_set("key").unwrap());
+        assert!(contiguous.is_set("string").unwrap());
+        assert_eq!("string", contiguous.get::<_, &str>("string").unwrap());
     }
 
     #[test]
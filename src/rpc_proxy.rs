@@ -0,0 +1,179 @@
+//! A TCP proxy which relays KRPC connections between the client and a Kudu server, for testing
+//! how the client handles responses which a healthy cluster does not readily produce.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use byteorder::{BigEndian, ByteOrder};
+use parking_lot::Mutex;
+use prost::{encode_length_delimiter, Message};
+
+use pb::rpc::{ErrorStatusPb, RequestHeader, ResponseHeader};
+use HostPort;
+use RpcErrorCode;
+
+/// The length of the connection header which clients send before their first message.
+const CONNECTION_HEADER_LEN: usize = 7;
+
+/// How an `RpcProxy` handles a request.
+pub(crate) enum Action {
+    /// Relays the request to the server, and the response to the client.
+    Forward,
+    /// Relays the request to the server, but replaces the response with an RPC error, as if the
+    /// response was lost after the server executed the request.
+    ForwardAndFail(RpcErrorCode),
+    /// Answers the request with an RPC error, without relaying it to the server.
+    Fail(RpcErrorCode),
+    /// Answers the request with an encoded response message, without relaying it to the server.
+    Respond(Vec<u8>),
+}
+
+type Intercept = Arc<Mutex<Box<FnMut(&RequestHeader) -> Action + Send>>>;
+
+/// A proxy to a Kudu server which records the header of every request sent through it, and
+/// handles each request according to a callback. Negotiation messages are always relayed.
+pub(crate) struct RpcProxy {
+    addr: HostPort,
+    requests: Arc<Mutex<Vec<RequestHeader>>>,
+}
+
+impl RpcProxy {
+    /// Spawns a proxy to the server, which handles each request as returned by `intercept`.
+    pub fn spawn<F>(server: HostPort, intercept: F) -> RpcProxy
+    where
+        F: FnMut(&RequestHeader) -> Action + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = HostPort::from(listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let intercept: Intercept = Arc::new(Mutex::new(Box::new(intercept)));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for client in listener.incoming() {
+                let client = client.unwrap();
+                let upstream = TcpStream::connect((server.host(), server.port())).unwrap();
+                relay(client, upstream, recorded.clone(), intercept.clone()).unwrap();
+            }
+        });
+
+        RpcProxy { addr, requests }
+    }
+
+    /// Returns the address of the proxy.
+    pub fn addr(&self) -> HostPort {
+        self.addr.clone()
+    }
+
+    /// Returns the headers of the requests sent through the proxy, in the order received.
+    pub fn requests(&self) -> Vec<RequestHeader> {
+        self.requests.lock().clone()
+    }
+}
+
+/// Spawns threads relaying requests from the client to the server, and responses back.
+fn relay(
+    mut client: TcpStream,
+    mut upstream: TcpStream,
+    requests: Arc<Mutex<Vec<RequestHeader>>>,
+    intercept: Intercept,
+) -> io::Result<()> {
+    let responses = Arc::new(Mutex::new(client.try_clone()?));
+    let mut upstream_responses = upstream.try_clone()?;
+    // The errors replacing the responses to requests forwarded with `Action::ForwardAndFail`,
+    // by call ID.
+    let failed = Arc::new(Mutex::new(HashMap::new()));
+
+    {
+        let responses = responses.clone();
+        let failed = failed.clone();
+        thread::spawn(move || -> io::Result<()> {
+            let mut connection_header = [0; CONNECTION_HEADER_LEN];
+            client.read_exact(&mut connection_header)?;
+            upstream.write_all(&connection_header)?;
+            loop {
+                let frame = read_frame(&mut client)?;
+                let header = RequestHeader::decode_length_delimited(&frame[4..]).unwrap();
+
+                // Negotiation messages have negative call IDs.
+                let action = if header.call_id < 0 {
+                    Action::Forward
+                } else {
+                    requests.lock().push(header.clone());
+                    let mut intercept = intercept.lock();
+                    (&mut **intercept)(&header)
+                };
+
+                match action {
+                    Action::Forward => upstream.write_all(&frame)?,
+                    Action::ForwardAndFail(code) => {
+                        failed.lock().insert(header.call_id, code);
+                        upstream.write_all(&frame)?;
+                    }
+                    Action::Fail(code) => responses
+                        .lock()
+                        .write_all(&error_frame(header.call_id, code))?,
+                    Action::Respond(body) => responses
+                        .lock()
+                        .write_all(&response_frame(header.call_id, false, &body))?,
+                }
+            }
+        });
+    }
+
+    thread::spawn(move || -> io::Result<()> {
+        loop {
+            let mut frame = read_frame(&mut upstream_responses)?;
+            let call_id = ResponseHeader::decode_length_delimited(&frame[4..])
+                .unwrap()
+                .call_id;
+            if let Some(code) = failed.lock().remove(&call_id) {
+                frame = error_frame(call_id, code);
+            }
+            responses.lock().write_all(&frame)?;
+        }
+    });
+
+    Ok(())
+}
+
+/// Reads a message frame, including its length prefix.
+fn read_frame(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut frame = vec![0; 4];
+    stream.read_exact(&mut frame)?;
+    let len = BigEndian::read_u32(&frame) as usize;
+    frame.resize(4 + len, 0);
+    stream.read_exact(&mut frame[4..])?;
+    Ok(frame)
+}
+
+/// Encodes a response frame with the call ID and encoded response body.
+fn response_frame(call_id: i32, is_error: bool, body: &[u8]) -> Vec<u8> {
+    let header = ResponseHeader {
+        call_id,
+        is_error: Some(is_error),
+        ..Default::default()
+    };
+    let mut frame = vec![0; 4];
+    header.encode_length_delimited(&mut frame).unwrap();
+    encode_length_delimiter(body.len(), &mut frame).unwrap();
+    frame.extend_from_slice(body);
+    let len = frame.len() - 4;
+    BigEndian::write_u32(&mut frame[..4], len as u32);
+    frame
+}
+
+/// Encodes an RPC error response frame with the call ID and error code.
+fn error_frame(call_id: i32, code: RpcErrorCode) -> Vec<u8> {
+    let error = ErrorStatusPb {
+        message: format!("{:?} injected by proxy", code),
+        code: Some(code as i32),
+        ..Default::default()
+    };
+    let mut body = Vec::new();
+    error.encode(&mut body).unwrap();
+    response_frame(call_id, true, &body)
+}
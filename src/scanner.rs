@@ -1,59 +1,164 @@
+use std::cmp;
+use std::collections::VecDeque;
 use std::fmt;
+#[cfg(feature = "csv")]
+use std::io;
 use std::iter::{FusedIterator, IntoIterator};
 use std::mem;
+use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use byteorder::{ByteOrder, LittleEndian};
 use bytes::{Bytes, BytesMut};
-use futures::{Async, Future, Poll, Stream};
-use krpc::Proxy;
+use futures::future::Either;
+use futures::sync::oneshot;
+use futures::{future, stream, Async, Future, Poll, Stream};
+use krpc::{Proxy, RpcFuture};
+use prost::Message;
+use tokio;
+use tokio_timer::Interval;
+use uuid::Uuid;
 use vec_map::{self, VecMap};
 
+use aggregate::Aggregator;
 use backoff::Backoff;
+use bitmap;
+use key;
 use meta_cache::{Entry, Lookup, TableLocations};
-use pb::tserver::{NewScanRequestPb, ScanRequestPb, ScanResponsePb, TabletServerService};
-use pb::{ColumnPredicatePb, ColumnSchemaPb, ExpectField, RowwiseRowBlockPb};
+use partition::{IntoPartitionKey, PartitionKey};
+use pb::client::ScanTokenPb;
+use pb::tserver::{
+    NewScanRequestPb, RowFormatFlags, ScanRequestPb, ScanResponsePb, ScannerKeepAliveRequestPb,
+    ScannerKeepAliveResponsePb, TabletServerService,
+};
+use pb::{
+    ColumnPredicatePb, ColumnSchemaPb, ColumnarRowBlockPb, ExpectField, OrderMode, ReadMode,
+    RowwiseRowBlockPb,
+};
 use replica::{ReplicaRpc, Selection, Speculation};
+use request_id::RequestTracker;
 use tablet::Tablet;
+use timestamp::{Date, DateTime};
+#[cfg(feature = "csv")]
+use util::time_to_us;
+use AggResult;
+use AggSpec;
 use Column;
 use ColumnSelector;
+use Datum;
 use Error;
 use Filter;
+use HybridTime;
+use MasterErrorCode;
+use OwnedRow;
+use PartitionSchema;
+use Predicate;
 use Result;
 use Row;
-use ScannerId;
 use Schema;
+use Table;
+use TableId;
 use TabletId;
+use TabletServerErrorCode;
+use TabletServerId;
+#[cfg(feature = "csv")]
+use TimestampFormat;
 
 #[derive(Clone)]
 pub struct ScanBuilder {
+    table_name: String,
+    table_id: TableId,
     table_schema: Schema,
+    partition_schema: PartitionSchema,
     table_locations: TableLocations,
     projected_columns: Vec<usize>,
     filters: VecMap<Filter>,
+    keep_alive: bool,
+    keep_alive_period: Duration,
+    report_non_covered_ranges: bool,
+    diff_scan: Option<(HybridTime, HybridTime)>,
+    project_is_deleted: bool,
+    max_attempts: Option<usize>,
+    prefetch: bool,
+    fault_tolerant: bool,
+    max_concurrent_tablets: Option<usize>,
+    target_replica: Option<TabletServerId>,
+    row_format: RowFormat,
+    /// The encoded inclusive lower bound primary key, and the corresponding inclusive lower bound
+    /// partition key.
+    lower_bound: Option<(Vec<u8>, PartitionKey)>,
+    /// The encoded exclusive upper bound primary key, and the corresponding exclusive upper bound
+    /// partition key, which is empty if the bound does not restrict the tablets scanned.
+    upper_bound: Option<(Vec<u8>, PartitionKey)>,
 }
 
 fn column_to_pb(column: &Column) -> ColumnSchemaPb {
     ColumnSchemaPb {
         name: column.name().to_owned(),
-        type_: column.data_type().to_pb(),
+        type_: column.type_pb(),
         is_nullable: Some(column.is_nullable()),
         ..Default::default()
     }
 }
 
+/// Converts a scan projection to the projected columns of a scan request.
+fn projection_to_pb(projected_schema: &Schema) -> Vec<ColumnSchemaPb> {
+    let is_deleted_column = projected_schema.is_deleted_column();
+    projected_schema
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            let mut pb = column_to_pb(column);
+            if Some(idx) == is_deleted_column {
+                // Virtual columns must have a read default.
+                pb.type_ = ::pb::DataType::IsDeleted as i32;
+                pb.read_default_value = Some(vec![0]);
+            }
+            pb
+        }).collect()
+}
+
 impl ScanBuilder {
-    pub(crate) fn new(table_schema: Schema, table_locations: TableLocations) -> ScanBuilder {
+    pub(crate) fn new(
+        table_name: String,
+        table_id: TableId,
+        table_schema: Schema,
+        partition_schema: PartitionSchema,
+        table_locations: TableLocations,
+    ) -> ScanBuilder {
         let num_columns = table_schema.columns().len();
         let projected_columns = (0..num_columns).collect::<Vec<_>>();
         ScanBuilder {
+            table_name,
+            table_id,
             table_schema,
+            partition_schema,
             table_locations,
             projected_columns,
             filters: VecMap::new(),
+            keep_alive: false,
+            keep_alive_period: Duration::from_secs(15),
+            report_non_covered_ranges: false,
+            diff_scan: None,
+            project_is_deleted: false,
+            max_attempts: None,
+            prefetch: false,
+            fault_tolerant: false,
+            max_concurrent_tablets: None,
+            target_replica: None,
+            row_format: RowFormat::Rowwise,
+            lower_bound: None,
+            upper_bound: None,
         }
     }
 
+    /// Sets the columns to project in the scan. The columns of the scan results will be in the
+    /// same order as the selected columns.
+    ///
+    /// Returns an error if a column does not exist, or if a column is selected more than once.
     pub fn select<I, C>(mut self, projected_columns: I) -> Result<ScanBuilder>
     where
         I: IntoIterator<Item = C>,
@@ -61,9 +166,34 @@ impl ScanBuilder {
     {
         self.projected_columns.clear();
         for column_selector in projected_columns {
-            self.projected_columns
-                .push(column_selector.column_index(&self.table_schema)?);
+            let idx = column_selector.column_index(&self.table_schema)?;
+            if self.projected_columns.contains(&idx) {
+                return Err(Error::InvalidArgument(format!(
+                    "column {} is projected more than once",
+                    self.table_schema.columns()[idx].name()
+                )));
+            }
+            self.projected_columns.push(idx);
+        }
+        Ok(self)
+    }
+
+    /// Projects every column of the table except the excluded columns, in table order. This is
+    /// the complement of `select`, and is convenient for wide tables.
+    ///
+    /// Returns an error if an excluded column does not exist.
+    pub fn projected_columns_except<I, C>(mut self, exclude: I) -> Result<ScanBuilder>
+    where
+        I: IntoIterator<Item = C>,
+        C: ColumnSelector,
+    {
+        let mut excluded = Vec::new();
+        for column_selector in exclude {
+            excluded.push(column_selector.column_index(&self.table_schema)?);
         }
+        self.projected_columns = (0..self.table_schema.columns().len())
+            .filter(|idx| !excluded.contains(idx))
+            .collect();
         Ok(self)
     }
 
@@ -75,6 +205,9 @@ impl ScanBuilder {
     /// Apply a filter to the scan.
     ///
     /// When multiple filters are applied to the scan they combine conjunctively, i.e. using `AND`.
+    /// Filters on the same column are merged into the tightest equivalent filter, so that a single
+    /// predicate is sent per column; for instance `>= 18` and `< 65` become the range `[18, 65)`.
+    /// Filters also combine conjunctively with the primary key bounds of the scan.
     pub fn filter<C>(mut self, column: C, filter: Filter) -> Result<ScanBuilder>
     where
         C: ColumnSelector,
@@ -96,73 +229,897 @@ impl ScanBuilder {
         Ok(self)
     }
 
+    /// Applies a predicate to the scan. Predicates combine conjunctively with other predicates
+    /// and filters.
+    ///
+    /// Returns an error if the predicate's column does not exist in the table.
+    pub fn predicate(self, predicate: Predicate) -> Result<ScanBuilder> {
+        let (column, filter) = predicate.into_parts();
+        self.filter(&*column, filter)
+    }
+
+    /// Applies an IN list filter with dynamically typed values to the scan. Only rows where the
+    /// column is equal to one of the values are returned.
+    ///
+    /// Returns an error if the column does not exist, or if a value is not of the column's type.
+    pub fn filter_in_list<C, I>(self, column: C, values: I) -> Result<ScanBuilder>
+    where
+        C: ColumnSelector,
+        I: IntoIterator<Item = Datum>,
+    {
+        let idx = column.column_index(&self.table_schema)?;
+        let data_type = self.table_schema.columns()[idx].data_type();
+        let filter = Filter::in_list_datums(data_type, values)?;
+        self.filter(idx, filter)
+    }
+
+    /// Sets whether the scan sends keep-alive requests to keep its tablet server scanners open
+    /// while the scan is not being polled.
+    ///
+    /// Tablet servers expire scanners which are idle for longer than the scanner TTL (60 seconds
+    /// by default), so scans feeding a slow consumer should enable keep-alive. The TTL is a
+    /// tablet server setting (`--scanner_ttl_ms`) rather than a property of the scan request, so
+    /// keep-alive is the only way for a scan to outlive it; each keep-alive request resets the
+    /// scanner's idle time. A scan whose scanner has expired fails with a
+    /// `TabletServerErrorCode::ScannerExpired` error. Keep-alive requests are sent from a
+    /// background task, so the scan must be polled from within a Tokio runtime. Defaults to
+    /// `false`.
+    pub fn set_keep_alive(mut self, keep_alive: bool) -> ScanBuilder {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Sets the period between keep-alive requests. Only applies if keep-alive is enabled. The
+    /// period should be well under the scanner TTL configured on the tablet servers.
+    ///
+    /// Defaults to 15 seconds.
+    pub fn set_keep_alive_period(mut self, period: Duration) -> ScanBuilder {
+        self.keep_alive_period = period;
+        self
+    }
+
+    /// Sets whether the scan reports the non-covered ranges it passes over as
+    /// `ScanEvent::Gap` events from `Scan::events`.
+    ///
+    /// Range partitioned tables may intentionally leave parts of the key space without
+    /// tablets, so by default non-covered ranges are skipped silently. Reporting them is useful
+    /// when diagnosing a misconfigured partitioning. Defaults to `false`.
+    pub fn report_non_covered_ranges(mut self, report_non_covered_ranges: bool) -> ScanBuilder {
+        self.report_non_covered_ranges = report_non_covered_ranges;
+        self
+    }
+
+    /// Sets the layout in which tablet servers return the scanned rows.
+    ///
+    /// Batches are iterated over in the same way regardless of the format, but batches returned
+    /// in the columnar format additionally expose the values of each column through
+    /// `RowBatch::column`. Defaults to `RowFormat::Rowwise`.
+    pub fn set_row_format(mut self, row_format: RowFormat) -> ScanBuilder {
+        self.row_format = row_format;
+        self
+    }
+
+    /// Sets whether the scan fetches the next batch from a tablet while the current batch is
+    /// being processed.
+    ///
+    /// With prefetching enabled, the scan sends the RPC for the next batch of a tablet as soon as
+    /// it returns the current batch, overlapping the network round trip with the processing of
+    /// the batch. At most one batch per scan is buffered. Defaults to `false`.
+    pub fn set_prefetch(mut self, prefetch: bool) -> ScanBuilder {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Sets whether the scan is fault tolerant, in which case a tablet scan whose tablet server
+    /// fails partway through the tablet is reopened at another replica.
+    ///
+    /// Fault tolerant scans read each tablet at a snapshot and in primary key order, so that the
+    /// reopened scan continues after the last row returned, at the same snapshot. This makes
+    /// them slower than regular scans, which fail if the tablet server fails once the tablet
+    /// scan is open. Defaults to `false`.
+    pub fn set_fault_tolerant(mut self, fault_tolerant: bool) -> ScanBuilder {
+        self.fault_tolerant = fault_tolerant;
+        self
+    }
+
+    /// Sets the maximum number of tablets which the scan reads from concurrently.
+    ///
+    /// By default tablets are scanned one at a time, in partition key order. When more than one
+    /// tablet is scanned concurrently, batches from different tablets are interleaved in the
+    /// scan's output, although the batches of each individual tablet are still returned in
+    /// order. Values less than one are treated as one. Defaults to `1`.
+    ///
+    /// For ordered scans this is the maximum number of tablets the scan may read from at once,
+    /// which defaults to `DEFAULT_MAX_ORDERED_SCAN_TABLETS`. See `ScanBuilder::build_ordered`.
+    pub fn set_max_concurrent_tablets(mut self, max_concurrent_tablets: usize) -> ScanBuilder {
+        self.max_concurrent_tablets = Some(cmp::max(1, max_concurrent_tablets));
+        self
+    }
+
+    /// Sets the inclusive lower bound of the primary keys returned by the scan. Every primary key
+    /// column must be set in `row`.
+    ///
+    /// Unlike filters, primary key bounds also limit which tablets the scan visits if the table
+    /// is range partitioned on a prefix of the primary key, and has no hash partitioning.
+    pub fn set_lower_bound_primary_key(mut self, row: &Row) -> Result<ScanBuilder> {
+        let (primary_key, range_key) = self.encode_bound(row)?;
+        self.lower_bound = Some((primary_key, range_key.into()));
+        Ok(self)
+    }
+
+    /// Sets the exclusive upper bound of the primary keys returned by the scan. Every primary key
+    /// column must be set in `row`.
+    ///
+    /// Unlike filters, primary key bounds also limit which tablets the scan visits if the table
+    /// is range partitioned on a prefix of the primary key, and has no hash partitioning.
+    pub fn set_upper_bound_primary_key(self, row: &Row) -> Result<ScanBuilder> {
+        let (primary_key, range_key) = self.encode_bound(row)?;
+        Ok(self.set_exclusive_upper_bound(primary_key, range_key))
+    }
+
+    /// Sets the inclusive upper bound of the primary keys returned by the scan. Every primary key
+    /// column must be set in `row`.
+    ///
+    /// Unlike filters, primary key bounds also limit which tablets the scan visits if the table
+    /// is range partitioned on a prefix of the primary key, and has no hash partitioning.
+    pub fn set_inclusive_upper_bound_primary_key(mut self, row: &Row) -> Result<ScanBuilder> {
+        let (mut primary_key, mut range_key) = self.encode_bound(row)?;
+        let is_full_key_range = self.is_full_key_range();
+        let last_column = self.table_schema.primary_key().last().unwrap();
+        if last_column.data_type().is_var_len() {
+            // The next key after a variable length key is the key with a zero byte appended.
+            primary_key.push(0);
+        } else if !key::increment_key(&mut primary_key) {
+            // The bound is the maximum key, so the scan is unbounded.
+            self.upper_bound = None;
+            return Ok(self);
+        }
+        if is_full_key_range {
+            range_key = primary_key.clone();
+        }
+        Ok(self.set_exclusive_upper_bound(primary_key, range_key))
+    }
+
+    fn set_exclusive_upper_bound(mut self, primary_key: Vec<u8>, mut range_key: Vec<u8>) -> Self {
+        if !range_key.is_empty() && !self.is_full_key_range() {
+            // Rows below the bound may share its range key, so the smallest partition key
+            // following the range key is the exclusive bound.
+            range_key.push(0);
+        }
+        self.upper_bound = Some((primary_key, range_key.into()));
+        self
+    }
+
+    /// Returns `true` if the table's partition key is a range partition key over every primary
+    /// key column, in which case encoded primary keys are also partition keys.
+    fn is_full_key_range(&self) -> bool {
+        let range_columns = self.partition_schema.range_partition_schema().columns();
+        self.partition_schema.hash_partition_schemas().is_empty()
+            && range_columns.len() == self.table_schema.primary_key().len()
+            && range_columns.iter().enumerate().all(|(i, &idx)| i == idx)
+    }
+
+    /// Encodes a primary key bound, returning the encoded primary key, and the encoded range
+    /// partition key if the bound can be used to limit the tablets scanned, or an empty key
+    /// otherwise.
+    fn encode_bound(&self, row: &Row) -> Result<(Vec<u8>, Vec<u8>)> {
+        if row.schema() != &self.table_schema {
+            return Err(Error::InvalidArgument(format!(
+                "primary key bound schema {:?} does not match the table schema",
+                row.schema()
+            )));
+        }
+        let primary_key = key::encode_primary_key(row)?;
+
+        let range_columns = self.partition_schema.range_partition_schema().columns();
+        let mut range_key = Vec::new();
+        let is_key_prefix = range_columns.iter().enumerate().all(|(i, &idx)| i == idx);
+        if self.partition_schema.hash_partition_schemas().is_empty() && is_key_prefix {
+            key::encode_range_partition_key(
+                self.partition_schema.range_partition_schema(),
+                row,
+                &mut range_key,
+            )?;
+        }
+        Ok((primary_key, range_key))
+    }
+
+    /// Sets the maximum number of attempts made for each scan RPC, including the initial attempt.
+    /// If an RPC keeps failing with retriable errors, such as an overloaded tablet server, the
+    /// scan fails with `Error::RetriesExhausted` once the attempts are spent.
+    ///
+    /// By default attempts are only limited by the RPC deadline.
+    pub fn set_max_attempts(mut self, max_attempts: usize) -> ScanBuilder {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Directs the scan of every tablet to its replica on the tablet server with the provided
+    /// UUID, whether or not the replica is the leader. This is a diagnostic tool, for example to
+    /// inspect the data held by a lagging follower; since followers may not have applied the
+    /// most recent writes, the results may be stale.
+    ///
+    /// Scanning a tablet which has no replica on the tablet server fails with `Error::NotFound`.
+    /// Returns an error if the UUID is malformed.
+    pub fn set_target_replica(mut self, tserver_uuid: &str) -> Result<ScanBuilder> {
+        let id = TabletServerId::parse(tserver_uuid).map_err(|_| {
+            Error::InvalidArgument(format!("invalid tablet server UUID: {}", tserver_uuid))
+        })?;
+        self.target_replica = Some(id);
+        Ok(self)
+    }
+
+    /// Projects the `IS_DELETED` virtual column, so that `Row::is_deleted` returns whether each
+    /// row has been deleted. The virtual column is appended to the projected columns.
+    ///
+    /// Regular scans only return live rows, so the virtual column is mostly useful in
+    /// combination with diff scans, which project it automatically. Requires Kudu 1.10 or later.
+    pub fn project_is_deleted(mut self) -> ScanBuilder {
+        self.project_is_deleted = true;
+        self
+    }
+
+    /// Configures the scan as a diff scan, which returns the rows inserted, updated, or deleted
+    /// after `start_timestamp`, up to and including `end_timestamp`. Each row is returned with its
+    /// state as of `end_timestamp`, and `Row::is_deleted` indicates whether the row was deleted.
+    ///
+    /// Diff scans are returned in primary key order within each tablet. The start timestamp must
+    /// be before the end timestamp, and must be more recent than the tablet history retention
+    /// period. Scan tokens can not be built for diff scans. Requires Kudu 1.10 or later.
+    pub fn set_diff_scan(
+        mut self,
+        start_timestamp: HybridTime,
+        end_timestamp: HybridTime,
+    ) -> ScanBuilder {
+        self.diff_scan = Some((start_timestamp, end_timestamp));
+        self.project_is_deleted = true;
+        self
+    }
+
     pub fn build(self) -> Scan {
-        let ScanBuilder {
-            table_schema,
-            table_locations,
-            projected_columns,
-            filters,
-        } = self;
+        let (projected_schema, predicates) = self.projection_and_predicates();
+        let keep_alive = if self.keep_alive {
+            Some(self.keep_alive_period)
+        } else {
+            None
+        };
+        let (lower_bound_partition_key, upper_bound_partition_key) = self.partition_key_range();
+        let mut scan = Scan::new(
+            projected_schema,
+            predicates,
+            self.table_locations,
+            lower_bound_partition_key,
+            upper_bound_partition_key,
+            keep_alive,
+            self.report_non_covered_ranges,
+        );
+        scan.diff_scan = self.diff_scan;
+        scan.max_attempts = self.max_attempts;
+        scan.prefetch = self.prefetch;
+        scan.ordered = self.fault_tolerant;
+        scan.max_concurrent_tablets = self.max_concurrent_tablets.unwrap_or(1);
+        scan.target_replica = self.target_replica;
+        scan.row_format = self.row_format;
+        scan.lower_bound_primary_key = self.lower_bound.map(|(primary_key, _)| primary_key);
+        scan.upper_bound_primary_key = self.upper_bound.map(|(primary_key, _)| primary_key);
+        scan.table_name = self.table_name;
+        scan.table_id = Some(self.table_id);
+        scan
+    }
+
+    /// Builds a scan which returns rows in primary key order across all of the tablets it covers.
+    ///
+    /// Each tablet is read with an ordered snapshot scan. If the table is range partitioned on a
+    /// prefix of the primary key, the tablets of each hash bucket hold consecutive ranges of
+    /// primary keys, so they are read one after another, and one tablet of each hash bucket is
+    /// scanned at a time. Otherwise every tablet is scanned concurrently. The rows of the tablets
+    /// being scanned are merged by primary key as they are received. The projection must include
+    /// the primary key columns.
+    ///
+    /// Every tablet is looked up before any is scanned. The scan fails with an invalid argument
+    /// error, without scanning any tablets, if it would read from more tablets at once than the
+    /// maximum set with `ScanBuilder::set_max_concurrent_tablets`.
+    ///
+    /// Returns an error if a primary key column is not projected.
+    pub fn build_ordered(self) -> Result<OrderedScan> {
+        let mut key_columns = Vec::with_capacity(self.table_schema.primary_key_indexes().len());
+        for &idx in self.table_schema.primary_key_indexes() {
+            match self.projected_columns.iter().position(|&projected| projected == idx) {
+                Some(position) => key_columns.push(position),
+                None => {
+                    return Err(Error::InvalidArgument(format!(
+                        "ordered scans must project primary key column {}",
+                        self.table_schema.columns()[idx].name()
+                    )))
+                }
+            }
+        }
+
+        let range_columns = self.partition_schema.range_partition_schema().columns();
+        let concatenate = range_columns.iter().enumerate().all(|(i, &idx)| i == idx);
+        let max_tablets = self
+            .max_concurrent_tablets
+            .unwrap_or(DEFAULT_MAX_ORDERED_SCAN_TABLETS);
+        let mut scan = self.build();
+        scan.ordered = true;
+        scan.max_concurrent_tablets = max_tablets;
+        scan.report_non_covered_ranges = false;
+        Ok(OrderedScan {
+            scan,
+            key_columns,
+            concatenate,
+            tablets: Vec::new(),
+            started: false,
+            inputs: Vec::new(),
+        })
+    }
+
+    /// Builds a set of scan tokens which together cover the scan, one per tablet.
+    ///
+    /// Scan tokens may be serialized and sent to other processes, which can each independently
+    /// execute a portion of the scan with `Client::scan_from_token`.
+    ///
+    /// Fails with an invalid argument error if the scan is a diff scan, since scan tokens do not
+    /// carry the diff scan timestamps.
+    pub fn build_scan_tokens(self) -> impl Future<Item = Vec<ScanToken>, Error = Error> {
+        if self.diff_scan.is_some() {
+            return Either::A(future::err(Error::InvalidArgument(
+                "scan tokens can not be built for diff scans".to_owned(),
+            )));
+        }
+        let (projected_schema, predicates) = self.projection_and_predicates();
+        let (lower_bound_partition_key, upper_bound_partition_key) = self.partition_key_range();
+
+        // If the predicates can never match there's no need to scan any tablets.
+        let start = predicates.as_ref().map(|_| lower_bound_partition_key);
+
+        let template = ScanTokenPb {
+            table_id: Some(self.table_id.to_string()),
+            table_name: Some(self.table_name),
+            projected_columns: projection_to_pb(&projected_schema),
+            column_predicates: predicates.unwrap_or_default(),
+            lower_bound_primary_key: self.lower_bound.map(|(primary_key, _)| primary_key),
+            upper_bound_primary_key: self.upper_bound.map(|(primary_key, _)| primary_key),
+            fault_tolerant: Some(self.fault_tolerant),
+            ..Default::default()
+        };
+
+        let table_locations = self.table_locations;
+        let tokens = stream::unfold(start, move |partition_key| {
+            partition_key.map(|partition_key| {
+                table_locations.entry(&partition_key).map(|entry| {
+                    let upper_bound = entry.upper_bound();
+                    let next = if upper_bound.is_empty()
+                        || (!upper_bound_partition_key.is_empty()
+                            && upper_bound >= &*upper_bound_partition_key)
+                    {
+                        None
+                    } else {
+                        Some(upper_bound.into_partition_key())
+                    };
+                    (entry, next)
+                })
+            })
+        }).filter_map(move |entry| match entry {
+            Entry::Tablet(tablet) => {
+                let mut pb = template.clone();
+                pb.lower_bound_partition_key = Some(tablet.lower_bound().to_vec());
+                pb.upper_bound_partition_key = Some(tablet.upper_bound().to_vec());
+                Some(ScanToken { pb })
+            }
+            Entry::NonCoveredRange { .. } => None,
+        }).collect();
+        Either::B(tokens)
+    }
 
+    /// Returns the inclusive lower bound and exclusive upper bound partition keys of the tablets
+    /// which may hold rows within the primary key bounds of the scan.
+    fn partition_key_range(&self) -> (PartitionKey, PartitionKey) {
+        let lower = self
+            .lower_bound
+            .as_ref()
+            .map_or_else(PartitionKey::empty, |&(_, ref key)| key.clone());
+        let upper = self
+            .upper_bound
+            .as_ref()
+            .map_or_else(PartitionKey::empty, |&(_, ref key)| key.clone());
+        (lower, upper)
+    }
+
+    /// Returns the projected schema, and the predicates of the scan. If the predicates or the
+    /// primary key bounds can never match, `None` is returned in place of the predicates.
+    fn projection_and_predicates(&self) -> (Schema, Option<Vec<ColumnPredicatePb>>) {
         let mut columns = Vec::new();
-        for idx in projected_columns {
-            columns.push(table_schema.columns()[idx].clone());
+        for &idx in &self.projected_columns {
+            columns.push(self.table_schema.columns()[idx].clone());
+        }
+        let projected_schema = if self.project_is_deleted {
+            Schema::projection_with_is_deleted(columns)
+        } else {
+            Schema::new(columns, 0)
+        };
+
+        // Encoded primary keys sort in primary key order, so inverted bounds match no rows.
+        if let (&Some((ref lower, _)), &Some((ref upper, _))) =
+            (&self.lower_bound, &self.upper_bound)
+        {
+            if lower >= upper {
+                return (projected_schema, None);
+            }
+        }
+
+        let mut predicates = Vec::with_capacity(self.filters.len());
+        for (idx, filter) in &self.filters {
+            if *filter == Filter::None {
+                return (projected_schema, None);
+            } else if *filter != Filter::All {
+                predicates.push(filter.clone().into_pb(&self.table_schema.columns()[idx]));
+            }
         }
-        let projected_schema = Schema::new(columns, 0);
+        (projected_schema, Some(predicates))
+    }
+}
+
+/// A serializable portion of a scan, covering a single tablet.
+///
+/// Scan tokens are created with `ScanBuilder::build_scan_tokens`, and are executed with
+/// `Client::scan_from_token`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanToken {
+    pb: ScanTokenPb,
+}
+
+impl ScanToken {
+    /// Returns the encoded lower-bound partition key of the tablet covered by the token.
+    pub fn lower_bound(&self) -> &[u8] {
+        self.pb
+            .lower_bound_partition_key
+            .as_ref()
+            .map_or(&[], |key| &key[..])
+    }
+
+    /// Returns the encoded upper-bound partition key of the tablet covered by the token.
+    pub fn upper_bound(&self) -> &[u8] {
+        self.pb
+            .upper_bound_partition_key
+            .as_ref()
+            .map_or(&[], |key| &key[..])
+    }
+
+    /// Serializes the scan token.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.pb.encoded_len());
+        self.pb
+            .encode(&mut buf)
+            .expect("failed to serialize scan token");
+        buf
+    }
 
-        let mut short_circuit = false;
-        let mut predicates = Vec::with_capacity(filters.len());
-        for (idx, filter) in filters {
-            if filter == Filter::None {
-                short_circuit = true;
-            } else if filter != Filter::All {
-                predicates.push(filter.into_pb(&table_schema.columns()[idx]));
+    /// Deserializes a scan token which was serialized with `ScanToken::serialize`.
+    pub fn deserialize(token: &[u8]) -> Result<ScanToken> {
+        let pb = ScanTokenPb::decode(token)
+            .map_err(|error| Error::Serialization(format!("invalid scan token: {}", error)))?;
+        Ok(ScanToken { pb })
+    }
+
+    /// Returns the name of the table which the token scans.
+    pub(crate) fn table_name(&self) -> Result<&str> {
+        self.pb
+            .table_name
+            .as_ref()
+            .map(|name| &name[..])
+            .expect_field("ScanTokenPb", "table_name")
+    }
+
+    /// Returns the ID of the table which the token scans.
+    pub(crate) fn table_id(&self) -> Result<TableId> {
+        self.pb
+            .table_id
+            .as_ref()
+            .expect_field("ScanTokenPb", "table_id")
+            .and_then(|id| TableId::parse(id))
+    }
+
+    /// Creates a scan of the portion of the table covered by the token.
+    ///
+    /// Returns a not found error if the table is not the table the token was built for, and an
+    /// invalid argument error if the projected columns are not present in the table's schema.
+    pub(crate) fn into_scan(self, table: &Table) -> Result<Scan> {
+        let table_id = self.table_id()?;
+        if table.id() != table_id {
+            return Err(Error::NotFound(format!(
+                "scan token table {} ({}) does not exist",
+                self.table_name()?,
+                table_id
+            )));
+        }
+        let schema = table.schema();
+        let mut columns = Vec::with_capacity(self.pb.projected_columns.len());
+        let mut project_is_deleted = false;
+        for pb in &self.pb.projected_columns {
+            // The IS_DELETED virtual column is always projected last.
+            if pb.type_ == ::pb::DataType::IsDeleted as i32 {
+                project_is_deleted = true;
+                continue;
+            }
+            match schema.column_by_name(&pb.name) {
+                Some(column) if column.type_pb() == pb.type_ => columns.push(column.clone()),
+                _ => {
+                    return Err(Error::InvalidArgument(format!(
+                        "scan token column {} does not exist in table {}",
+                        pb.name,
+                        table.name()
+                    )))
+                }
             }
         }
 
-        let state = if short_circuit {
-            ScannerState::Finished
+        let lower_bound = self.lower_bound().into_partition_key();
+        let upper_bound = self.upper_bound().into_partition_key();
+        let ScanTokenPb {
+            column_predicates,
+            lower_bound_primary_key,
+            upper_bound_primary_key,
+            fault_tolerant,
+            ..
+        } = self.pb;
+        let projected_schema = if project_is_deleted {
+            Schema::projection_with_is_deleted(columns)
         } else {
-            ScannerState::Lookup(table_locations.entry(&[]))
+            Schema::new(columns, 0)
         };
-        Scan {
+        let mut scan = Scan::new(
             projected_schema,
-            predicates,
-            table_locations,
-            state,
-        }
+            Some(column_predicates),
+            table.table_locations().clone(),
+            lower_bound,
+            upper_bound,
+            None,
+            false,
+        );
+        scan.lower_bound_primary_key = lower_bound_primary_key;
+        scan.upper_bound_primary_key = upper_bound_primary_key;
+        scan.ordered = fault_tolerant.unwrap_or(false);
+        scan.table_name = table.name().to_owned();
+        scan.table_id = Some(table_id);
+        Ok(scan)
+    }
+}
+
+/// The serialized form of a continuation token. Tokens of partially scanned tablets identify
+/// the open scanner of the tablet.
+#[derive(Clone, PartialEq, Message)]
+struct ContinuationTokenPb {
+    /// The remainder of the scan, beginning with the partially scanned tablet.
+    #[prost(message, required, tag = "1")]
+    scan: ScanTokenPb,
+    #[prost(bytes, optional, tag = "2")]
+    tablet_id: Option<Vec<u8>>,
+    #[prost(bytes, optional, tag = "3")]
+    tserver_uuid: Option<Vec<u8>>,
+    #[prost(bytes, optional, tag = "4")]
+    scanner_id: Option<Vec<u8>>,
+    #[prost(uint32, optional, tag = "5")]
+    call_seq_id: Option<u32>,
+}
+
+/// A deserialized continuation token.
+///
+/// Continuation tokens are created with `Scan::continuation_token`, and are resumed with
+/// `Client::resume_scan`.
+pub(crate) struct ContinuationToken {
+    token: ScanToken,
+    position: Option<ScannerPosition>,
+}
+
+impl ContinuationToken {
+    pub(crate) fn deserialize(token: &[u8]) -> Result<ContinuationToken> {
+        let pb = ContinuationTokenPb::decode(token).map_err(|error| {
+            Error::Serialization(format!("invalid continuation token: {}", error))
+        })?;
+        let position = match pb.scanner_id {
+            Some(scanner_id) => Some(ScannerPosition {
+                tablet_id: TabletId::parse_bytes(
+                    &pb.tablet_id
+                        .expect_field("ContinuationTokenPb", "tablet_id")?,
+                )?,
+                tserver: TabletServerId::parse_bytes(
+                    &pb.tserver_uuid
+                        .expect_field("ContinuationTokenPb", "tserver_uuid")?,
+                )?,
+                scanner_id: ScannerId::parse_bytes(&scanner_id)?,
+                call_seq_id: pb
+                    .call_seq_id
+                    .expect_field("ContinuationTokenPb", "call_seq_id")?,
+            }),
+            None => None,
+        };
+        Ok(ContinuationToken {
+            token: ScanToken { pb: pb.scan },
+            position,
+        })
+    }
+
+    pub(crate) fn table_id(&self) -> Result<TableId> {
+        self.token.table_id()
+    }
+
+    /// Creates a scan of the remainder of the table covered by the token, continuing the open
+    /// scanner of the partially scanned tablet, if any.
+    pub(crate) fn into_scan(self, table: &Table) -> Result<Scan> {
+        let mut scan = self.token.into_scan(table)?;
+        scan.resumed_scanner = self.position;
+        Ok(scan)
     }
 }
 
 pub struct Scan {
+    table_name: String,
+    table_id: Option<TableId>,
     projected_schema: Schema,
     predicates: Vec<ColumnPredicatePb>,
     table_locations: TableLocations,
-    state: ScannerState,
+    /// The partition key at which the scan ends, or empty if the scan covers the remainder of the
+    /// table.
+    upper_bound: PartitionKey,
+    /// The keep-alive period, if keep-alive is enabled.
+    keep_alive: Option<Duration>,
+    report_non_covered_ranges: bool,
+    /// The start and end timestamps, if the scan is a diff scan.
+    diff_scan: Option<(HybridTime, HybridTime)>,
+    /// The maximum number of attempts per scan RPC.
+    max_attempts: Option<usize>,
+    /// Whether to fetch the next batch of a tablet while the current batch is processed.
+    prefetch: bool,
+    /// Whether each tablet is scanned in primary key order.
+    ordered: bool,
+    /// The tablet server whose replicas are scanned, if not the closest replica.
+    target_replica: Option<TabletServerId>,
+    /// The layout in which tablet servers return rows.
+    row_format: RowFormat,
+    /// The encoded inclusive lower bound primary key.
+    lower_bound_primary_key: Option<Vec<u8>>,
+    /// The encoded exclusive upper bound primary key.
+    upper_bound_primary_key: Option<Vec<u8>>,
+    stats: ScanStats,
+    /// The maximum number of tablets scanned concurrently.
+    max_concurrent_tablets: usize,
+    /// The in-progress lookup of the next tablet or non-covered range to scan.
+    lookup: Option<Lookup<Entry>>,
+    /// The partition key at which to look up the next tablet once there is room for another
+    /// concurrent tablet scan. `None` once the scan has passed its upper bound.
+    next_partition_key: Option<PartitionKey>,
+    /// The tablets being scanned, in the order they are polled.
+    tablet_scans: VecDeque<ActiveTabletScan>,
+    /// The open tablet server scanner to continue, if the scan was resumed from a continuation
+    /// token and the scanner's tablet has not yet been looked up.
+    resumed_scanner: Option<ScannerPosition>,
 }
 
-enum ScannerState {
-    Lookup(Lookup<Entry>),
-    Scan {
-        tablet: Arc<Tablet>,
-        tablet_scan: TabletScan,
-    },
-    Finished,
+/// A tablet being scanned as part of a `Scan`.
+struct ActiveTabletScan {
+    tablet: Arc<Tablet>,
+    tablet_scan: TabletScan,
+    /// The result of polling the tablet scan ahead of the caller, if prefetching is enabled and
+    /// the next batch or an error was immediately available.
+    prefetched: Option<Result<RowBatch>>,
+}
+
+impl ActiveTabletScan {
+    fn poll(&mut self, stats: &mut ScanStats, prefetch: bool) -> Poll<Option<RowBatch>, Error> {
+        let batch = match self.prefetched.take() {
+            Some(prefetched) => prefetched?,
+            None => match try_ready!(self.tablet_scan.poll(stats)) {
+                Some(batch) => batch,
+                None => return Ok(Async::Ready(None)),
+            },
+        };
+
+        // Polling the tablet scan sends the RPC for the next batch, which then proceeds while the
+        // caller processes this batch. Errors are deferred until the next poll so that this batch
+        // is not lost.
+        if prefetch {
+            self.prefetched = match self.tablet_scan.poll(stats) {
+                Ok(Async::Ready(Some(next))) => Some(Ok(next)),
+                Ok(Async::Ready(None)) | Ok(Async::NotReady) => None,
+                Err(error) => Some(Err(error)),
+            };
+        }
+        Ok(Async::Ready(Some(batch)))
+    }
 }
 
 impl Scan {
+    /// Creates a new scan over the `[lower_bound, upper_bound)` partition key range. If
+    /// `predicates` is `None`, the scan is known to match no rows, and finishes immediately.
+    fn new(
+        projected_schema: Schema,
+        predicates: Option<Vec<ColumnPredicatePb>>,
+        table_locations: TableLocations,
+        lower_bound: PartitionKey,
+        upper_bound: PartitionKey,
+        keep_alive: Option<Duration>,
+        report_non_covered_ranges: bool,
+    ) -> Scan {
+        let (predicates, lookup) = match predicates {
+            Some(predicates) => {
+                let lookup = table_locations.entry(&lower_bound);
+                (predicates, Some(lookup))
+            }
+            None => (Vec::new(), None),
+        };
+        Scan {
+            table_name: String::new(),
+            table_id: None,
+            projected_schema,
+            predicates,
+            table_locations,
+            upper_bound,
+            keep_alive,
+            report_non_covered_ranges,
+            diff_scan: None,
+            max_attempts: None,
+            prefetch: false,
+            ordered: false,
+            target_replica: None,
+            row_format: RowFormat::Rowwise,
+            lower_bound_primary_key: None,
+            upper_bound_primary_key: None,
+            stats: ScanStats::default(),
+            max_concurrent_tablets: 1,
+            lookup,
+            next_partition_key: None,
+            tablet_scans: VecDeque::new(),
+            resumed_scanner: None,
+        }
+    }
+
+    /// Returns statistics about the data transferred by the scan so far. Once the scan has
+    /// completed, the statistics cover the entire scan.
+    pub fn stats(&self) -> &ScanStats {
+        &self.stats
+    }
+
+    /// Flattens the scan into a stream of individual rows.
+    ///
+    /// Each row is copied out of its batch, so prefer iterating over the `RowBatch` items of the
+    /// scan directly when the rows do not need to outlive the batch.
+    pub fn rows(self) -> impl Stream<Item = OwnedRow, Error = Error> {
+        self.map(|batch| {
+            let rows = batch.into_iter().map(Row::into_owned).collect::<Vec<_>>();
+            stream::iter_ok(rows)
+        }).flatten()
+    }
+
+    /// Aggregates a projected column over every row of the scan in a single pass, without
+    /// retaining the scanned rows.
+    ///
+    /// The returned future fails immediately if the column is not projected, or if a sum is
+    /// requested over a column which is not an integer or floating point column.
+    pub fn aggregate(self, spec: AggSpec) -> impl Future<Item = AggResult, Error = Error> {
+        future::result(Aggregator::new(spec, &self.projected_schema))
+            .and_then(move |aggregator| {
+                self.fold(aggregator, |mut aggregator, batch| {
+                    aggregator.add_batch(&batch).map(|_| aggregator)
+                })
+            }).map(Aggregator::finish)
+    }
+
+    /// Converts the scan into a stream of scan events, which includes the non-covered ranges
+    /// passed over by the scan in addition to the row batches, if enabled with
+    /// `ScanBuilder::report_non_covered_ranges`.
+    pub fn events(mut self) -> impl Stream<Item = ScanEvent, Error = Error> {
+        stream::poll_fn(move || self.poll_event())
+    }
+
+    /// Returns a token from which the remainder of the scan can be resumed with
+    /// `Client::resume_scan`, for instance by an export process restarting after a crash.
+    ///
+    /// If a tablet has been partially scanned, the token refers to the tablet server scanner
+    /// which holds the scan's position in the tablet. The token expires along with the scanner,
+    /// once the scanner has been idle for the tablet server's scanner TTL (60 seconds by
+    /// default); resuming an expired token fails with a `ScannerExpired` tablet server error.
+    /// Like scan tokens, continuation tokens only carry the projection, filters, primary key
+    /// bounds, and fault tolerance of the scan.
+    ///
+    /// Returns `None` if the scan is complete, or if it can not currently be paused: when it is a
+    /// diff scan, when more than one tablet is being scanned, when a tablet is being looked up,
+    /// when a partially scanned tablet is being reopened at another replica, or when the request
+    /// for the tablet's next batch has already been sent, which is always the case with
+    /// prefetching enabled. Once sent, the request advances the tablet server scanner past the
+    /// rows of the next batch, so they could not be resumed from the token.
+    pub fn continuation_token(&self) -> Option<Vec<u8>> {
+        if self.diff_scan.is_some() || self.lookup.is_some() || self.tablet_scans.len() > 1 {
+            return None;
+        }
+        let mut pb = ContinuationTokenPb {
+            scan: ScanTokenPb {
+                table_id: self.table_id.map(|id| id.to_string()),
+                table_name: Some(self.table_name.clone()),
+                projected_columns: projection_to_pb(&self.projected_schema),
+                column_predicates: self.predicates.clone(),
+                lower_bound_primary_key: self.lower_bound_primary_key.clone(),
+                upper_bound_primary_key: self.upper_bound_primary_key.clone(),
+                upper_bound_partition_key: Some(self.upper_bound.to_vec()),
+                fault_tolerant: Some(self.ordered),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let lower_bound = match self.tablet_scans.front() {
+            None => self.next_partition_key.as_ref()?.to_vec(),
+            Some(active) => {
+                if active.prefetched.is_some() {
+                    return None;
+                }
+                match active.tablet_scan {
+                    TabletScan::New { ref scan, .. } | TabletScan::Relocating { ref scan, .. } => {
+                        // A reopened fault tolerant scan has already returned rows.
+                        if scan.request.last_primary_key.is_some() {
+                            return None;
+                        }
+                        // The tablet has not returned any rows yet, so it is rescanned from the
+                        // start.
+                        active.tablet.lower_bound().to_vec()
+                    }
+                    TabletScan::Continue {
+                        ref position,
+                        ref rpc,
+                        ..
+                    } => {
+                        if rpc.attempts() > 0 {
+                            return None;
+                        }
+                        pb.tablet_id = Some(position.tablet_id.to_string().into_bytes());
+                        pb.tserver_uuid = Some(position.tserver.to_string().into_bytes());
+                        pb.scanner_id = Some(position.scanner_id.to_string().into_bytes());
+                        pb.call_seq_id = Some(position.call_seq_id);
+                        active.tablet.lower_bound().to_vec()
+                    }
+                    TabletScan::Finished => self.next_partition_key.as_ref()?.to_vec(),
+                }
+            }
+        };
+        pb.scan.lower_bound_partition_key = Some(lower_bound);
+
+        let mut buf = Vec::with_capacity(pb.encoded_len());
+        pb.encode(&mut buf)
+            .expect("failed to serialize continuation token");
+        Some(buf)
+    }
+
+    /// Returns `true` if the scan continues past the provided partition key, which is the upper
+    /// bound of a tablet or non-covered range.
+    fn continues_past(&self, partition_key: &[u8]) -> bool {
+        !partition_key.is_empty()
+            && (self.upper_bound.is_empty() || partition_key < &*self.upper_bound)
+    }
+
     fn new_scan_request(&self, tablet: TabletId) -> NewScanRequestPb {
-        NewScanRequestPb {
+        let mut request = NewScanRequestPb {
             tablet_id: tablet.to_string().into_bytes(),
-            projected_columns: self
-                .projected_schema
-                .columns()
-                .iter()
-                .map(column_to_pb)
-                .collect(),
+            projected_columns: projection_to_pb(&self.projected_schema),
             column_predicates: self.predicates.clone(),
+            start_primary_key: self.lower_bound_primary_key.clone(),
+            stop_primary_key: self.upper_bound_primary_key.clone(),
             ..Default::default()
+        };
+        if self.ordered {
+            // Ordered scans must be snapshot scans.
+            request.read_mode = Some(ReadMode::ReadAtSnapshot as i32);
+            request.order_mode = Some(OrderMode::Ordered as i32);
         }
+        if let Some((start_timestamp, end_timestamp)) = self.diff_scan {
+            // Diff scans are only supported by ordered snapshot scans.
+            request.read_mode = Some(ReadMode::ReadAtSnapshot as i32);
+            request.order_mode = Some(OrderMode::Ordered as i32);
+            request.snap_start_timestamp = Some(start_timestamp.encoded());
+            request.snap_timestamp = Some(end_timestamp.encoded());
+        }
+        if self.row_format == RowFormat::Columnar {
+            request.row_format_flags = Some(RowFormatFlags::ColumnarLayout as u64);
+        }
+        request
     }
 
     pub fn projected_schema(&self) -> &Schema {
@@ -170,80 +1127,592 @@ impl Scan {
     }
 }
 
-impl Stream for Scan {
-    type Item = RowBatch;
-    type Error = Error;
-
-    fn poll(&mut self) -> Poll<Option<RowBatch>, Error> {
-        trace!("Scan::poll");
-        loop {
-            match mem::replace(&mut self.state, ScannerState::Finished) {
-                ScannerState::Lookup(mut lookup) => match lookup.poll()? {
-                    Async::Ready(Entry::Tablet(tablet)) => {
-                        let tablet_scan = TabletScan::new(
-                            self.projected_schema.clone(),
-                            tablet.clone(),
-                            self.new_scan_request(tablet.id()),
-                        );
-                        self.state = ScannerState::Scan {
-                            tablet,
-                            tablet_scan,
-                        };
-                    }
-                    Async::Ready(Entry::NonCoveredRange { upper_bound, .. }) => {
-                        if !upper_bound.is_empty() {
-                            let lookup = self.table_locations.entry(&upper_bound);
-                            self.state = ScannerState::Lookup(lookup);
-                        }
-                    }
-                    Async::NotReady => {
-                        self.state = ScannerState::Lookup(lookup);
-                        return Ok(Async::NotReady);
-                    }
-                },
-                ScannerState::Scan {
-                    tablet,
-                    mut tablet_scan,
-                } => match tablet_scan.poll()? {
-                    Async::Ready(Some(batch)) => {
-                        self.state = ScannerState::Scan {
-                            tablet,
-                            tablet_scan,
-                        };
-                        return Ok(Async::Ready(Some(batch)));
-                    }
-                    Async::Ready(None) => if !tablet.upper_bound().is_empty() {
-                        let lookup = self.table_locations.entry(tablet.upper_bound());
-                        self.state = ScannerState::Lookup(lookup);
-                    },
-                    Async::NotReady => {
-                        self.state = ScannerState::Scan {
-                            tablet,
-                            tablet_scan,
-                        };
-                        return Ok(Async::NotReady);
-                    }
-                },
-                ScannerState::Finished => return Ok(Async::Ready(None)),
-            }
+/// Writes a CSV field, quoting it if `force_quote` is set or if it contains a delimiter, quote,
+/// or line break. Quotes within the field are escaped by doubling them.
+#[cfg(feature = "csv")]
+fn write_csv_string<W>(writer: &mut W, value: &str, force_quote: bool) -> io::Result<()>
+where
+    W: io::Write,
+{
+    if !force_quote && !value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        return writer.write_all(value.as_bytes());
+    }
+    writer.write_all(b"\"")?;
+    for (idx, part) in value.split('"').enumerate() {
+        if idx > 0 {
+            writer.write_all(b"\"\"")?;
         }
+        writer.write_all(part.as_bytes())?;
     }
+    writer.write_all(b"\"")
 }
 
-impl fmt::Debug for Scan {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Scan").finish()
-    }
+/// The layout in which tablet servers return the rows of a scan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RowFormat {
+    /// Each row is returned in turn, with string and binary values in a shared buffer.
+    Rowwise,
+    /// The values of each column are returned together, which is more compact for analytical
+    /// scans of many rows. Requires Kudu 1.12 or later.
+    Columnar,
 }
 
-pub struct RowBatch {
-    projected_schema: Schema,
+/// An item produced by `Scan::events`.
+pub enum ScanEvent {
+    /// A batch of rows.
+    Batch(RowBatch),
+
+    /// A range of the partition key space which is not covered by any tablet, and therefore
+    /// holds no rows. The bounds are encoded partition keys; an empty lower bound is the start of
+    /// the table, and an empty upper bound is the end of the table.
+    Gap { lower: Vec<u8>, upper: Vec<u8> },
+}
+
+impl Scan {
+    /// Looks up tablets and starts scanning them while fewer than the maximum number of tablets
+    /// are being scanned. Stops early to return a non-covered range, if they are reported.
+    fn open_tablet_scans(&mut self) -> Result<Option<ScanEvent>> {
+        while self.tablet_scans.len() < self.max_concurrent_tablets {
+            if self.lookup.is_none() {
+                match self.next_partition_key.take() {
+                    Some(partition_key) => {
+                        self.lookup = Some(self.table_locations.entry(&partition_key))
+                    }
+                    None => break,
+                }
+            }
+            let entry = match self.lookup.as_mut().unwrap().poll().map_err(lookup_error)? {
+                Async::Ready(entry) => entry,
+                Async::NotReady => break,
+            };
+            self.lookup = None;
+            match entry {
+                Entry::Tablet(tablet) => {
+                    if self.continues_past(tablet.upper_bound()) {
+                        self.next_partition_key = Some(tablet.upper_bound().clone());
+                    }
+                    let tablet_scan = match self.resumed_scanner.take() {
+                        Some(position) => self.continue_tablet_scan(&tablet, position)?,
+                        None => self.new_tablet_scan(&tablet)?,
+                    };
+                    self.tablet_scans.push_back(ActiveTabletScan {
+                        tablet,
+                        tablet_scan,
+                        prefetched: None,
+                    });
+                }
+                Entry::NonCoveredRange {
+                    lower_bound,
+                    upper_bound,
+                    ..
+                } => {
+                    if self.continues_past(&upper_bound) {
+                        self.next_partition_key = Some(upper_bound.clone());
+                    }
+                    if self.report_non_covered_ranges {
+                        return Ok(Some(ScanEvent::Gap {
+                            lower: lower_bound.to_vec(),
+                            upper: upper_bound.to_vec(),
+                        }));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Looks up the next tablet covered by the scan, without scanning it. Non-covered ranges are
+    /// passed over. Returns `None` once every tablet has been looked up.
+    fn poll_next_tablet(&mut self) -> Poll<Option<Arc<Tablet>>, Error> {
+        loop {
+            if self.lookup.is_none() {
+                match self.next_partition_key.take() {
+                    Some(partition_key) => {
+                        self.lookup = Some(self.table_locations.entry(&partition_key))
+                    }
+                    None => return Ok(Async::Ready(None)),
+                }
+            }
+            let entry = try_ready!(self.lookup.as_mut().unwrap().poll().map_err(lookup_error));
+            self.lookup = None;
+            match entry {
+                Entry::Tablet(tablet) => {
+                    if self.continues_past(tablet.upper_bound()) {
+                        self.next_partition_key = Some(tablet.upper_bound().clone());
+                    }
+                    return Ok(Async::Ready(Some(tablet)));
+                }
+                Entry::NonCoveredRange { upper_bound, .. } => {
+                    if self.continues_past(&upper_bound) {
+                        self.next_partition_key = Some(upper_bound.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    fn new_tablet_scan(&self, tablet: &Arc<Tablet>) -> Result<TabletScan> {
+        let selection = self.replica_selection(tablet)?;
+        // Scans targeting a specific replica are never moved to another replica.
+        let table_locations = if selection == Selection::Closest {
+            Some(self.table_locations.clone())
+        } else {
+            None
+        };
+        let new_scan = NewTabletScan {
+            projected_schema: self.projected_schema.clone(),
+            request: self.new_scan_request(tablet.id()),
+            keep_alive_period: self.keep_alive,
+            backoff: self.backoff(),
+            timeout: self.table_locations.operation_timeout(),
+            request_ids: self.table_locations.request_ids(),
+            table_locations,
+            relocations: 0,
+        };
+        Ok(TabletScan::new(new_scan, tablet.clone(), selection))
+    }
+
+    /// Returns the backoff policy for retrying the scan's RPCs.
+    fn backoff(&self) -> Backoff {
+        self.table_locations
+            .backoff()
+            .with_max_attempts(self.max_attempts)
+    }
+
+    /// Continues the scanner of a resumed scan at the tablet server which opened it.
+    fn continue_tablet_scan(
+        &self,
+        tablet: &Tablet,
+        position: ScannerPosition,
+    ) -> Result<TabletScan> {
+        if tablet.id() != position.tablet_id {
+            return Err(Error::NotFound(format!(
+                "tablet {} of the continuation token no longer exists",
+                position.tablet_id
+            )));
+        }
+        let proxy = tablet
+            .replicas
+            .iter()
+            .find(|replica| replica.id() == position.tserver)
+            .map(|replica| replica.proxy.clone())
+            .ok_or_else(|| {
+                Error::NotFound(format!(
+                    "tablet {} has no replica on tablet server {}",
+                    tablet.id(),
+                    position.tserver
+                ))
+            })?;
+        let timeout = self.table_locations.operation_timeout();
+        let keep_alive = self
+            .keep_alive
+            .map(|period| KeepAlive::spawn(proxy.clone(), position.scanner_id, period, timeout));
+        Ok(TabletScan::cont(
+            self.projected_schema.clone(),
+            position,
+            keep_alive,
+            self.backoff(),
+            timeout,
+            self.table_locations.request_ids(),
+            None,
+            proxy,
+        ))
+    }
+
+    /// Returns the policy for selecting which replica of the tablet to scan.
+    fn replica_selection(&self, tablet: &Tablet) -> Result<Selection> {
+        let target = match self.target_replica {
+            Some(target) => target,
+            None => return Ok(Selection::Closest),
+        };
+        tablet
+            .replicas
+            .iter()
+            .position(|replica| replica.id() == target)
+            .map(Selection::Replica)
+            .ok_or_else(|| {
+                Error::NotFound(format!(
+                    "tablet {} has no replica on tablet server {}",
+                    tablet.id(),
+                    target
+                ))
+            })
+    }
+
+    /// Returns `true` once every tablet in the scan has been looked up.
+    fn all_tablets_opened(&self) -> bool {
+        self.lookup.is_none() && self.next_partition_key.is_none()
+    }
+
+    fn poll_event(&mut self) -> Poll<Option<ScanEvent>, Error> {
+        trace!("Scan::poll_event");
+        loop {
+            if let Some(gap) = self.open_tablet_scans()? {
+                return Ok(Async::Ready(Some(gap)));
+            }
+
+            // Poll each tablet scan in turn, moving the polled scan to the back of the queue so
+            // that concurrent tablet scans take turns returning batches.
+            let mut tablet_finished = false;
+            for _ in 0..self.tablet_scans.len() {
+                let mut active = self.tablet_scans.pop_front().unwrap();
+                match active.poll(&mut self.stats, self.prefetch)? {
+                    Async::Ready(Some(batch)) => {
+                        self.table_locations
+                            .counters()
+                            .add_bytes_scanned(batch.received_len());
+                        self.tablet_scans.push_back(active);
+                        return Ok(Async::Ready(Some(ScanEvent::Batch(batch))));
+                    }
+                    Async::Ready(None) => {
+                        trace!("finished scanning tablet {:?}", active.tablet.id());
+                        tablet_finished = true;
+                    }
+                    Async::NotReady => self.tablet_scans.push_back(active),
+                }
+            }
+
+            if self.tablet_scans.is_empty() && self.all_tablets_opened() {
+                return Ok(Async::Ready(None));
+            } else if !tablet_finished {
+                return Ok(Async::NotReady);
+            }
+        }
+    }
+}
+
+impl Stream for Scan {
+    type Item = RowBatch;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<RowBatch>, Error> {
+        loop {
+            match try_ready!(self.poll_event()) {
+                Some(ScanEvent::Batch(batch)) => return Ok(Async::Ready(Some(batch))),
+                Some(ScanEvent::Gap { .. }) => continue,
+                None => return Ok(Async::Ready(None)),
+            }
+        }
+    }
+}
+
+/// A scan which returns rows in primary key order across all of the tablets it covers.
+///
+/// Ordered scans are created with `ScanBuilder::build_ordered`.
+pub struct OrderedScan {
+    scan: Scan,
+    /// The indexes of the primary key columns in the projection, in primary key order.
+    key_columns: Vec<usize>,
+    /// Whether the tablets of each hash bucket are read one after another, rather than merged,
+    /// which is possible when the table is range partitioned on a prefix of the primary key.
+    concatenate: bool,
+    /// The tablets looked up so far, before the scan has started.
+    tablets: Vec<Arc<Tablet>>,
+    /// Whether every tablet has been looked up, and the inputs created.
+    started: bool,
+    /// The inputs being merged.
+    inputs: Vec<MergeInput>,
+}
+
+/// The rows of one or more tablets being merged into an ordered scan. The tablets are read one
+/// after another, so their primary key ranges must be in order and must not overlap.
+struct MergeInput {
+    /// The tablets which have not been scanned yet.
+    tablets: VecDeque<Arc<Tablet>>,
+    /// The tablet being scanned, or `None` if the next tablet has not been opened.
+    tablet_scan: Option<ActiveTabletScan>,
+    /// The current batch of the tablet, or `None` if the next batch has not been received.
+    batch: Option<RowBatch>,
+    /// The index of the next row in the current batch.
+    row: usize,
+    /// The encoded primary key of the next row.
+    key: Vec<u8>,
+    finished: bool,
+}
+
+impl MergeInput {
+    fn new(tablets: VecDeque<Arc<Tablet>>) -> MergeInput {
+        MergeInput {
+            tablets,
+            tablet_scan: None,
+            batch: None,
+            row: 0,
+            key: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Polls for the next batch, moving on to the next tablet once the current tablet is
+    /// finished.
+    fn poll_batch(&mut self, scan: &mut Scan) -> Poll<Option<RowBatch>, Error> {
+        loop {
+            if self.tablet_scan.is_none() {
+                let tablet = match self.tablets.pop_front() {
+                    Some(tablet) => tablet,
+                    None => return Ok(Async::Ready(None)),
+                };
+                let tablet_scan = scan.new_tablet_scan(&tablet)?;
+                self.tablet_scan = Some(ActiveTabletScan {
+                    tablet,
+                    tablet_scan,
+                    prefetched: None,
+                });
+            }
+            let batch = {
+                let tablet_scan = self.tablet_scan.as_mut().unwrap();
+                try_ready!(tablet_scan.poll(&mut scan.stats, scan.prefetch))
+            };
+            match batch {
+                Some(batch) => return Ok(Async::Ready(Some(batch))),
+                None => self.tablet_scan = None,
+            }
+        }
+    }
+
+    /// Sets the current batch and encodes the key of its first row. Empty batches are skipped.
+    fn set_batch(&mut self, batch: RowBatch, key_columns: &[usize]) -> Result<()> {
+        if batch.num_rows() > 0 {
+            self.batch = Some(batch);
+            self.row = 0;
+            self.encode_key(key_columns)?;
+        }
+        Ok(())
+    }
+
+    fn encode_key(&mut self, key_columns: &[usize]) -> Result<()> {
+        let row = self.batch.as_ref().unwrap().row(self.row);
+        key::encode_projected_primary_key(&row, key_columns, &mut self.key)
+    }
+
+    /// Returns the next row, and moves on to the following row.
+    fn advance(&mut self, key_columns: &[usize]) -> Result<OwnedRow> {
+        let (row, num_rows) = {
+            let batch = self.batch.as_ref().unwrap();
+            let row = batch.row(self.row).into_owned();
+            (row, batch.num_rows())
+        };
+        self.row += 1;
+        if self.row == num_rows {
+            self.batch = None;
+        } else {
+            self.encode_key(key_columns)?;
+        }
+        Ok(row)
+    }
+}
+
+impl OrderedScan {
+    /// Returns statistics about the data transferred by the scan so far.
+    pub fn stats(&self) -> &ScanStats {
+        self.scan.stats()
+    }
+
+    /// Returns the schema of the rows returned by the scan, which includes the primary key
+    /// columns.
+    pub fn projected_schema(&self) -> &Schema {
+        self.scan.projected_schema()
+    }
+
+    /// Creates the merge inputs from the tablets covered by the scan, which are in partition key
+    /// order. Partition keys begin with the hash buckets, so the tablets of each hash bucket are
+    /// consecutive, in range partition order.
+    fn start(&mut self) -> Result<()> {
+        let mut groups: Vec<VecDeque<Arc<Tablet>>> = Vec::new();
+        for tablet in self.tablets.drain(..) {
+            let same_bucket = {
+                let last = groups.last().and_then(|group| group.back());
+                self.concatenate
+                    && last.map_or(false, |last| {
+                        last.partition().hash_partitions() == tablet.partition().hash_partitions()
+                    })
+            };
+            if same_bucket {
+                groups.last_mut().unwrap().push_back(tablet);
+            } else {
+                groups.push(VecDeque::from(vec![tablet]));
+            }
+        }
+        if groups.len() > self.scan.max_concurrent_tablets {
+            return Err(Error::InvalidArgument(format!(
+                "ordered scan reads from more than the maximum of {} tablets at once",
+                self.scan.max_concurrent_tablets
+            )));
+        }
+        self.inputs = groups.into_iter().map(MergeInput::new).collect();
+        self.started = true;
+        Ok(())
+    }
+}
+
+impl Stream for OrderedScan {
+    type Item = OwnedRow;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<OwnedRow>, Error> {
+        // Every tablet is looked up before any is scanned, so that a scan which would read from
+        // too many tablets at once fails without opening any tablet server scanners.
+        if !self.started {
+            while let Some(tablet) = try_ready!(self.scan.poll_next_tablet()) {
+                self.tablets.push(tablet);
+            }
+            self.start()?;
+        }
+
+        // The next row may come from any input, so every input must have a batch available or be
+        // finished before a row can be returned.
+        let mut ready = true;
+        for input in &mut self.inputs {
+            while input.batch.is_none() && !input.finished {
+                match input.poll_batch(&mut self.scan)? {
+                    Async::Ready(Some(batch)) => {
+                        self.scan
+                            .table_locations
+                            .counters()
+                            .add_bytes_scanned(batch.received_len());
+                        input.set_batch(batch, &self.key_columns)?;
+                    }
+                    Async::Ready(None) => input.finished = true,
+                    Async::NotReady => {
+                        ready = false;
+                        break;
+                    }
+                }
+            }
+        }
+        if !ready {
+            return Ok(Async::NotReady);
+        }
+
+        self.inputs.retain(|input| !input.finished);
+        let next = self
+            .inputs
+            .iter()
+            .enumerate()
+            .min_by(|&(_, a), &(_, b)| a.key.cmp(&b.key))
+            .map(|(idx, _)| idx);
+        match next {
+            Some(idx) => Ok(Async::Ready(Some(self.inputs[idx].advance(&self.key_columns)?))),
+            None => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+impl fmt::Debug for OrderedScan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OrderedScan").finish()
+    }
+}
+
+/// Statistics about the data transferred by a scan.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    rows: usize,
+    data: usize,
+    rpcs: usize,
+    retries: usize,
+}
+
+impl ScanStats {
+    /// Returns the number of rows returned by the scan.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of row data bytes received, including indirect data for string and
+    /// binary columns.
+    pub fn data(&self) -> usize {
+        self.data
+    }
+
+    /// Returns the number of successful scan RPCs made to tablet servers.
+    pub fn rpcs(&self) -> usize {
+        self.rpcs
+    }
+
+    /// Returns the number of additional scan RPCs sent due to retries or speculative execution.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+
+    fn add_batch(&mut self, batch: &RowBatch, attempts: usize) {
+        self.rows += batch.len;
+        self.data += batch.received_len();
+        self.rpcs += 1;
+        self.retries += attempts.saturating_sub(1);
+    }
+}
+
+impl fmt::Debug for Scan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Scan").finish()
+    }
+}
+
+/// A batch of rows returned by a scan.
+///
+/// Row batches are validated when they are received from the tablet server, so iterating over
+/// a batch is infallible.
+pub struct RowBatch {
+    projected_schema: Schema,
     len: usize,
     data: Bytes,
-    _indirect_data: Bytes,
+    indirect_data: Bytes,
+    /// The values of each column, if the batch was returned in the columnar format.
+    columns: Vec<ColumnData>,
+}
+
+/// The sidecars holding the values of a column of a columnar row batch.
+struct ColumnData {
+    data: Bytes,
+    varlen_data: Bytes,
+    non_null_bitmap: Option<Bytes>,
+}
+
+impl ColumnData {
+    fn len(&self) -> usize {
+        self.data.len()
+            + self.varlen_data.len()
+            + self.non_null_bitmap.as_ref().map_or(0, Bytes::len)
+    }
+}
+
+/// Takes the sidecar at the index from a scan response, or returns `None` if there is no index.
+fn take_sidecar(sidecars: &mut [BytesMut], idx: Option<i32>, field: &str) -> Result<Option<Bytes>> {
+    match idx {
+        Some(idx) if idx < 0 => Err(Error::Serialization(format!("{} is negative", field))),
+        Some(idx) => match sidecars.get_mut(idx as usize) {
+            Some(sidecar) => Ok(Some(mem::replace(sidecar, BytesMut::new()).freeze())),
+            None => Err(Error::Serialization(format!(
+                "ScanResponsePb does not include the sidecar for {}",
+                field
+            ))),
+        },
+        None => Ok(None),
+    }
 }
 
 impl RowBatch {
+    /// Decodes the row batch from a scan response in either the rowwise or columnar format.
+    fn from_response(
+        projected_schema: Schema,
+        response: &mut ScanResponsePb,
+        sidecars: Vec<BytesMut>,
+    ) -> Result<RowBatch> {
+        match response.columnar_data.take() {
+            Some(block) => RowBatch::from_columnar(projected_schema, &block, sidecars),
+            None => RowBatch::new(
+                projected_schema,
+                &response.data.take().unwrap_or_default(),
+                sidecars,
+            ),
+        }
+    }
+
+    /// Decodes a row batch from a scan response.
+    ///
+    /// This is the single point at which malformed row data is detected: the row data length is
+    /// checked against the number of rows, and every non-null string and binary value is checked
+    /// to fall within the indirect data sidecar. Rows are not checked again during iteration.
     fn new(
         projected_schema: Schema,
         block: &RowwiseRowBlockPb,
@@ -316,15 +1785,37 @@ impl RowBatch {
             }
         }
 
-        // Swizzle string and binary column pointers.
+        // Swizzle string and binary column pointers, checking that each value falls within the
+        // indirect data sidecar. Null values are skipped, since their cells are never read.
         if !projected_schema.var_len_column_offsets().is_empty() {
-            for row in data.chunks_mut(row_len) {
-                for &offset in projected_schema.var_len_column_offsets() {
+            let null_bitmap_offset = projected_schema.row_len();
+            for (row_idx, row) in data.chunks_mut(row_len).enumerate() {
+                for (idx, column) in projected_schema.columns().iter().enumerate() {
+                    if !column.data_type().is_var_len()
+                        || (column.is_nullable() && bitmap::get(&row[null_bitmap_offset..], idx))
+                    {
+                        continue;
+                    }
                     #[cfg_attr(feature = "cargo-clippy", allow(cast_ptr_alignment))]
                     unsafe {
-                        // TODO: sanity check the value length falls in the indirect data buf.
-                        let ptr = row.as_mut_ptr().offset(offset as isize) as *mut u64;
+                        let cell = row.as_mut_ptr().offset(projected_schema.column_offset(idx));
+                        let ptr = cell as *mut u64;
                         let offset = ptr.read_unaligned().to_le();
+                        let len = ptr.offset(1).read_unaligned().to_le();
+                        match offset.checked_add(len) {
+                            Some(end) if end <= indirect_data.len() as u64 => (),
+                            _ => {
+                                return Err(Error::Serialization(format!(
+                                    "row {} column {} value is out of bounds of the indirect \
+                                     data sidecar; offset: {}, len: {}, sidecar.len: {}",
+                                    row_idx,
+                                    column.name(),
+                                    offset,
+                                    len,
+                                    indirect_data.len()
+                                )));
+                            }
+                        }
                         *ptr = ((indirect_data.as_ptr() as u64) + offset).to_le();
                     }
                 }
@@ -335,192 +1826,960 @@ impl RowBatch {
             projected_schema,
             len: block.num_rows() as usize,
             data: data.freeze(),
-            _indirect_data: indirect_data,
+            indirect_data,
+            columns: Vec::new(),
         })
     }
 
-    pub fn num_rows(&self) -> usize {
-        self.len
-    }
+    /// Decodes a row batch returned in the columnar format.
+    ///
+    /// The columns are validated and transposed into rows, so that the rows of columnar and
+    /// rowwise batches are read in the same way. The column sidecars are retained by the batch,
+    /// both for `RowBatch::column`, and because string and binary cells point into them.
+    fn from_columnar(
+        projected_schema: Schema,
+        block: &ColumnarRowBlockPb,
+        mut sidecars: Vec<BytesMut>,
+    ) -> Result<RowBatch> {
+        trace!(
+            "RowBatch::from_columnar; block: {:?}, sidecars: {:?}",
+            block,
+            sidecars
+        );
+        if block.columns.len() != projected_schema.columns().len() {
+            return Err(Error::Serialization(format!(
+                "ColumnarRowBlockPb has {} columns, but the projection has {} columns",
+                block.columns.len(),
+                projected_schema.columns().len()
+            )));
+        }
+        if block.num_rows() < 0 {
+            return Err(Error::Serialization(format!(
+                "ColumnarRowBlockPb.num_rows is invalid: {}",
+                block.num_rows()
+            )));
+        }
+        let num_rows = block.num_rows() as usize;
 
-    pub fn projected_schema(&self) -> &Schema {
-        &self.projected_schema
-    }
-}
+        // Check the length of every sidecar before allocating the rows, since the length of the
+        // data sidecars is what bounds the number of rows.
+        let mut columns = Vec::with_capacity(block.columns.len());
+        for (column, pb) in projected_schema.columns().iter().zip(&block.columns) {
+            let data = take_sidecar(&mut sidecars, pb.data_sidecar, "data_sidecar")?
+                .unwrap_or_default();
+            let varlen_data =
+                take_sidecar(&mut sidecars, pb.varlen_data_sidecar, "varlen_data_sidecar")?
+                    .unwrap_or_default();
+            let non_null_bitmap = take_sidecar(
+                &mut sidecars,
+                pb.non_null_bitmap_sidecar,
+                "non_null_bitmap_sidecar",
+            )?;
 
-impl<'a> IntoIterator for &'a RowBatch {
-    type Item = Row<'a>;
-    type IntoIter = RowBatchIter<'a>;
-    fn into_iter(self) -> RowBatchIter<'a> {
-        let projected_schema = &self.projected_schema;
+            // String and binary values are delimited by offsets into the variable length data,
+            // including a final offset marking the end of the last value.
+            let data_len = if column.data_type().is_var_len() {
+                num_rows.checked_add(1).and_then(|offsets| offsets.checked_mul(4))
+            } else {
+                num_rows.checked_mul(column.data_type().size())
+            };
+            if data_len != Some(data.len()) && !(num_rows == 0 && data.is_empty()) {
+                return Err(Error::Serialization(format!(
+                    "column {} data_sidecar length does not match num_rows; num_rows: {}, \
+                     data_sidecar.len: {}",
+                    column.name(),
+                    num_rows,
+                    data.len()
+                )));
+            }
+            if let Some(ref non_null_bitmap) = non_null_bitmap {
+                if non_null_bitmap.len() < bitmap::len(num_rows) {
+                    return Err(Error::Serialization(format!(
+                        "column {} non_null_bitmap_sidecar is too short; num_rows: {}, \
+                         non_null_bitmap_sidecar.len: {}",
+                        column.name(),
+                        num_rows,
+                        non_null_bitmap.len()
+                    )));
+                }
+            }
+            columns.push(ColumnData {
+                data,
+                varlen_data,
+                non_null_bitmap,
+            });
+        }
+
+        let null_bitmap_offset = projected_schema.row_len();
         let row_len = projected_schema.row_len()
             + projected_schema.has_nullable_columns() as usize * projected_schema.bitmap_len();
-        let iter = self.data.chunks(row_len);
-        RowBatchIter {
-            projected_schema: &self.projected_schema,
-            iter,
+        let mut data = BytesMut::from(vec![0; num_rows * row_len]);
+
+        for (idx, (column, values)) in projected_schema.columns().iter().zip(&columns).enumerate() {
+            let offset = projected_schema.column_offset(idx) as usize;
+            let size = column.data_type().size();
+            for (row_idx, row) in data.chunks_mut(row_len).enumerate() {
+                if let Some(ref non_null_bitmap) = values.non_null_bitmap {
+                    if !bitmap::get(non_null_bitmap, row_idx) {
+                        bitmap::set(&mut row[null_bitmap_offset..], idx);
+                        continue;
+                    }
+                }
+                let cell = &mut row[offset..offset + size];
+                if column.data_type().is_var_len() {
+                    let start = LittleEndian::read_u32(&values.data[row_idx * 4..]) as usize;
+                    let end = LittleEndian::read_u32(&values.data[(row_idx + 1) * 4..]) as usize;
+                    if start > end || end > values.varlen_data.len() {
+                        return Err(Error::Serialization(format!(
+                            "row {} column {} value is out of bounds of the varlen data \
+                             sidecar; start: {}, end: {}, sidecar.len: {}",
+                            row_idx,
+                            column.name(),
+                            start,
+                            end,
+                            values.varlen_data.len()
+                        )));
+                    }
+                    let ptr = values.varlen_data.as_ptr() as u64 + start as u64;
+                    LittleEndian::write_u64(&mut cell[..8], ptr);
+                    LittleEndian::write_u64(&mut cell[8..], (end - start) as u64);
+                } else {
+                    cell.copy_from_slice(&values.data[row_idx * size..(row_idx + 1) * size]);
+                }
+            }
         }
+
+        Ok(RowBatch {
+            projected_schema,
+            len: num_rows,
+            data: data.freeze(),
+            indirect_data: Bytes::new(),
+            columns,
+        })
     }
-}
 
-pub struct RowBatchIter<'a> {
-    projected_schema: &'a Schema,
-    iter: ::std::slice::Chunks<'a, u8>,
-}
+    pub fn num_rows(&self) -> usize {
+        self.len
+    }
 
-impl<'a> Iterator for RowBatchIter<'a> {
-    type Item = Row<'a>;
-    fn next(&mut self) -> Option<Row<'a>> {
-        self.iter
-            .next()
-            .map(|data| Row::contiguous(self.projected_schema.clone(), data))
+    /// Returns the row at index `idx` in the batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is not less than the number of rows in the batch.
+    pub(crate) fn row(&self, idx: usize) -> Row {
+        assert!(idx < self.len, "row index {} out of bounds", idx);
+        let row_len = self.row_len();
+        Row::contiguous(
+            self.projected_schema.clone(),
+            &self.data[idx * row_len..(idx + 1) * row_len],
+        )
     }
-}
 
-impl<'a> ExactSizeIterator for RowBatchIter<'a> {
-    fn len(&self) -> usize {
-        self.iter.len()
+    /// Returns the length of each row in the batch's data, including the null bitmap.
+    fn row_len(&self) -> usize {
+        self.projected_schema.row_len()
+            + self.projected_schema.has_nullable_columns() as usize
+                * self.projected_schema.bitmap_len()
     }
-}
 
-impl<'a> DoubleEndedIterator for RowBatchIter<'a> {
-    fn next_back(&mut self) -> Option<Row<'a>> {
-        self.iter
-            .next_back()
-            .map(|data| Row::contiguous(self.projected_schema.clone(), data))
+    /// Returns the values of the projected column at the index, or `None` if the index is out
+    /// of range or the batch was not returned in the columnar format.
+    ///
+    /// See `ScanBuilder::set_row_format`.
+    pub fn column(&self, idx: usize) -> Option<ColumnSlice> {
+        let values = self.columns.get(idx)?;
+        Some(ColumnSlice {
+            column: &self.projected_schema.columns()[idx],
+            num_rows: self.len,
+            data: &values.data,
+            varlen_data: &values.varlen_data,
+            non_null_bitmap: values.non_null_bitmap.as_ref().map(|bitmap| &bitmap[..]),
+        })
     }
-}
 
-// TODO: compile-time assert that Chunks is fused.
-impl<'a> FusedIterator for RowBatchIter<'a> {}
+    /// Returns the number of row data bytes received from the tablet server for the batch.
+    fn received_len(&self) -> usize {
+        if self.columns.is_empty() {
+            self.data.len() + self.indirect_data.len()
+        } else {
+            self.columns.iter().map(ColumnData::len).sum()
+        }
+    }
 
-enum TabletScan {
-    New {
-        projected_schema: Schema,
-        rpc: ReplicaRpc<Arc<Tablet>, ScanRequestPb, ScanResponsePb>,
-    },
-    Continue {
+    pub fn projected_schema(&self) -> &Schema {
+        &self.projected_schema
+    }
+
+    /// Writes the rows in the batch to `writer` as an ASCII table, with a header row holding the
+    /// projected column names.
+    ///
+    /// Nulls are written as `NULL`, timestamps are formatted as ISO 8601, and binary values are
+    /// written in hex with a `0x` prefix, truncated with an ellipsis after 16 bytes.
+    pub fn fmt_table<W>(&self, writer: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        const MAX_BINARY_LEN: usize = 16;
+
+        let columns = self.projected_schema.columns();
+        let mut rows = Vec::with_capacity(self.len);
+        for row in self {
+            let mut cells = Vec::with_capacity(columns.len());
+            for idx in 0..columns.len() {
+                // Row batches are validated when received, so reading a value can not fail.
+                let cell = match row.get_datum(idx).map_err(|_| fmt::Error)? {
+                    Datum::Bool(value) => value.to_string(),
+                    Datum::Int8(value) => value.to_string(),
+                    Datum::Int16(value) => value.to_string(),
+                    Datum::Int32(value) => value.to_string(),
+                    Datum::Int64(value) => value.to_string(),
+                    Datum::Timestamp(value) => DateTime::from(value).to_string(),
+                    Datum::Float(value) => value.to_string(),
+                    Datum::Double(value) => value.to_string(),
+                    Datum::Binary(value) => {
+                        let mut cell = "0x".to_owned();
+                        for byte in value.iter().take(MAX_BINARY_LEN) {
+                            cell.push_str(&format!("{:02x}", byte));
+                        }
+                        if value.len() > MAX_BINARY_LEN {
+                            cell.push_str("...");
+                        }
+                        cell
+                    }
+                    Datum::String(value) => value,
+                    Datum::Date(value) => Date::from_days(value).to_string(),
+                    Datum::Null => "NULL".to_owned(),
+                };
+                cells.push(cell);
+            }
+            rows.push(cells);
+        }
+
+        let mut widths = columns
+            .iter()
+            .map(|column| column.name().chars().count())
+            .collect::<Vec<_>>();
+        for cells in &rows {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = cmp::max(*width, cell.chars().count());
+            }
+        }
+
+        fn write_separator<W>(writer: &mut W, widths: &[usize]) -> fmt::Result
+        where
+            W: fmt::Write,
+        {
+            for &width in widths {
+                write!(writer, "+{}", "-".repeat(width + 2))?;
+            }
+            writer.write_str("+\n")
+        }
+
+        fn write_cells<'a, W, I>(writer: &mut W, widths: &[usize], cells: I) -> fmt::Result
+        where
+            W: fmt::Write,
+            I: Iterator<Item = &'a str>,
+        {
+            for (&width, cell) in widths.iter().zip(cells) {
+                let padding = width - cell.chars().count();
+                write!(writer, "| {}{} ", cell, " ".repeat(padding))?;
+            }
+            writer.write_str("|\n")
+        }
+
+        write_separator(writer, &widths)?;
+        write_cells(writer, &widths, columns.iter().map(Column::name))?;
+        write_separator(writer, &widths)?;
+        for cells in &rows {
+            write_cells(writer, &widths, cells.iter().map(String::as_str))?;
+        }
+        write_separator(writer, &widths)
+    }
+
+    /// Writes the rows in the batch to `writer` in CSV format.
+    ///
+    /// Each row is written as a record terminated by a newline. Strings are always quoted,
+    /// binary values are written in hex with a `0x` prefix, timestamps are formatted as ISO 8601,
+    /// and nulls are written as empty fields. If `include_header` is `true`, a record containing
+    /// the projected column names is written first.
+    #[cfg(feature = "csv")]
+    pub fn write_csv<W>(&self, writer: &mut W, include_header: bool) -> Result<()>
+    where
+        W: io::Write,
+    {
+        self.write_csv_with_timestamp_format(writer, include_header, TimestampFormat::default())
+    }
+
+    /// Writes the rows in the batch to `writer` in CSV format, formatting timestamps according to
+    /// `timestamp_format`. See `RowBatch::write_csv`.
+    #[cfg(feature = "csv")]
+    pub fn write_csv_with_timestamp_format<W>(
+        &self,
+        writer: &mut W,
+        include_header: bool,
+        timestamp_format: TimestampFormat,
+    ) -> Result<()>
+    where
+        W: io::Write,
+    {
+        let num_columns = self.projected_schema.columns().len();
+        if include_header {
+            for (idx, column) in self.projected_schema.columns().iter().enumerate() {
+                if idx > 0 {
+                    writer.write_all(b",")?;
+                }
+                write_csv_string(writer, column.name(), false)?;
+            }
+            writer.write_all(b"\n")?;
+        }
+
+        for row in self {
+            for idx in 0..num_columns {
+                if idx > 0 {
+                    writer.write_all(b",")?;
+                }
+                match row.get_datum(idx)? {
+                    Datum::Bool(value) => write!(writer, "{}", value)?,
+                    Datum::Int8(value) => write!(writer, "{}", value)?,
+                    Datum::Int16(value) => write!(writer, "{}", value)?,
+                    Datum::Int32(value) => write!(writer, "{}", value)?,
+                    Datum::Int64(value) => write!(writer, "{}", value)?,
+                    Datum::Timestamp(value) => match timestamp_format {
+                        TimestampFormat::Rfc3339 => write!(writer, "{}", DateTime::from(value))?,
+                        TimestampFormat::EpochMicros => write!(writer, "{}", time_to_us(value))?,
+                    },
+                    Datum::Float(value) => write!(writer, "{}", value)?,
+                    Datum::Double(value) => write!(writer, "{}", value)?,
+                    Datum::Binary(value) => {
+                        writer.write_all(b"0x")?;
+                        for byte in value {
+                            write!(writer, "{:02x}", byte)?;
+                        }
+                    }
+                    Datum::String(value) => write_csv_string(writer, &value, true)?,
+                    Datum::Date(value) => write!(writer, "{}", Date::from_days(value))?,
+                    Datum::Null => (),
+                }
+            }
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// The values of a single column of a row batch returned in the columnar format.
+///
+/// Values are stored in little-endian byte order. Fixed-size values are packed one after
+/// another, with a zeroed cell in place of each null. String and binary values are concatenated
+/// in the variable length data, delimited by `u32` offsets in the cell data, of which there is
+/// one more than there are rows.
+#[derive(Clone, Copy)]
+pub struct ColumnSlice<'a> {
+    column: &'a Column,
+    num_rows: usize,
+    data: &'a [u8],
+    varlen_data: &'a [u8],
+    non_null_bitmap: Option<&'a [u8]>,
+}
+
+impl<'a> ColumnSlice<'a> {
+    pub fn column(&self) -> &'a Column {
+        self.column
+    }
+
+    pub fn len(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_rows == 0
+    }
+
+    /// Returns `true` if the value in the row is null.
+    ///
+    /// Panics if the row is out of range.
+    pub fn is_null(&self, row: usize) -> bool {
+        assert!(row < self.num_rows, "row {} is out of range", row);
+        self.non_null_bitmap
+            .map_or(false, |non_null_bitmap| !bitmap::get(non_null_bitmap, row))
+    }
+
+    /// Returns the cell data: the values of fixed-size columns, or the offsets of the values of
+    /// string and binary columns.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the concatenated values of a string or binary column.
+    pub fn varlen_data(&self) -> &'a [u8] {
+        self.varlen_data
+    }
+
+    /// Returns the bitmap of non-null values, in which the bit for each row is set if the value
+    /// is not null, or `None` if the column is not nullable.
+    pub fn non_null_bitmap(&self) -> Option<&'a [u8]> {
+        self.non_null_bitmap
+    }
+}
+
+impl<'a> fmt::Debug for ColumnSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ColumnSlice")
+            .field("column", &self.column.name())
+            .field("num_rows", &self.num_rows)
+            .finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a RowBatch {
+    type Item = Row<'a>;
+    type IntoIter = RowBatchIter<'a>;
+    fn into_iter(self) -> RowBatchIter<'a> {
+        let iter = self.data.chunks(self.row_len());
+        RowBatchIter {
+            projected_schema: &self.projected_schema,
+            iter,
+        }
+    }
+}
+
+pub struct RowBatchIter<'a> {
+    projected_schema: &'a Schema,
+    iter: ::std::slice::Chunks<'a, u8>,
+}
+
+impl<'a> Iterator for RowBatchIter<'a> {
+    type Item = Row<'a>;
+    fn next(&mut self) -> Option<Row<'a>> {
+        self.iter
+            .next()
+            .map(|data| Row::contiguous(self.projected_schema.clone(), data))
+    }
+}
+
+impl<'a> ExactSizeIterator for RowBatchIter<'a> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<'a> DoubleEndedIterator for RowBatchIter<'a> {
+    fn next_back(&mut self) -> Option<Row<'a>> {
+        self.iter
+            .next_back()
+            .map(|data| Row::contiguous(self.projected_schema.clone(), data))
+    }
+}
+
+// TODO: compile-time assert that Chunks is fused.
+impl<'a> FusedIterator for RowBatchIter<'a> {}
+
+/// The default maximum number of tablets an ordered scan reads from at once.
+pub const DEFAULT_MAX_ORDERED_SCAN_TABLETS: usize = 100;
+
+/// The maximum number of times a tablet scan is reopened after refreshing the tablet's location.
+const MAX_RELOCATIONS: usize = 3;
+
+/// The parameters for opening a scan of a tablet.
+///
+/// These are retained until the scan is open, so that it can be reopened against fresh replica
+/// locations if every cached replica fails because the tablet's leader or configuration changed.
+/// Fault tolerant scans also retain them once the scan is open, positioned after the last row
+/// returned, so that the scan can be reopened if its tablet server fails.
+#[derive(Clone)]
+struct NewTabletScan {
+    projected_schema: Schema,
+    request: NewScanRequestPb,
+    keep_alive_period: Option<Duration>,
+    backoff: Backoff,
+    timeout: Duration,
+    request_ids: Arc<RequestTracker>,
+    /// The locations to refresh the tablet from, or `None` if the scan may not be relocated.
+    table_locations: Option<TableLocations>,
+    relocations: usize,
+}
+
+impl NewTabletScan {
+    /// Invalidates the tablet's cached location and begins looking it up again, if the error
+    /// indicates that the tablet's replicas have moved and the relocation budget is not spent.
+    fn relocate(&mut self, tablet: &Tablet, error: &Error) -> Option<Lookup<Option<Arc<Tablet>>>> {
+        if self.relocations >= MAX_RELOCATIONS || !is_replica_error(error) {
+            return None;
+        }
+        let table_locations = self.table_locations.as_ref()?;
+        self.relocations += 1;
+        table_locations.invalidate(tablet.lower_bound());
+        Some(table_locations.tablet(tablet.lower_bound()))
+    }
+
+    /// Returns the parameters for reopening the scan after the rows of `response`, or `None` if
+    /// the scan can not be reopened part way through the tablet, because it may not be relocated
+    /// or because its rows are not returned in primary key order.
+    fn reopen_after(&self, response: &ScanResponsePb) -> Option<NewTabletScan> {
+        if self.table_locations.is_none()
+            || self.request.order_mode != Some(OrderMode::Ordered as i32)
+        {
+            return None;
+        }
+        let mut scan = self.clone();
+        scan.advance(response);
+        Some(scan)
+    }
+
+    /// Moves the position at which the scan is reopened past the rows of `response`. The reopened
+    /// scan reads at the snapshot chosen by the tablet server which opened the scan.
+    fn advance(&mut self, response: &ScanResponsePb) {
+        if self.request.snap_timestamp.is_none() {
+            self.request.snap_timestamp = response.snap_timestamp;
+        }
+        if response.last_primary_key.is_some() {
+            self.request.last_primary_key = response.last_primary_key.clone();
+        }
+    }
+}
+
+/// Converts the error of a failed tablet location lookup. The master only fails lookups with
+/// `TableNotFound` if the table was dropped after the scan started, which is reported as
+/// `Error::NotFound` to distinguish it from transient location failures.
+fn lookup_error(error: Error) -> Error {
+    match error {
+        Error::Master(ref error) if error.code == MasterErrorCode::TableNotFound => {
+            Error::NotFound("table was dropped during scan".to_owned())
+        }
+        error => error,
+    }
+}
+
+/// Returns `true` if the error indicates that a replica is unreachable, is no longer the leader,
+/// or no longer hosts the tablet, in which case fresh tablet locations may have better replicas.
+fn is_replica_error(error: &Error) -> bool {
+    match *error {
+        Error::TabletServer(ref error) => match error.code {
+            TabletServerErrorCode::NotTheLeader
+            | TabletServerErrorCode::TabletNotRunning
+            | TabletServerErrorCode::TabletNotFound
+            | TabletServerErrorCode::TabletFailed => true,
+            _ => false,
+        },
+        Error::Io(_) => true,
+        Error::RetriesExhausted { ref last_error, .. } => is_replica_error(last_error),
+        Error::Compound(_, ref errors) => errors.iter().all(is_replica_error),
+        _ => false,
+    }
+}
+
+/// The position of a tablet scan whose scanner is open on a tablet server.
+#[derive(Clone, Copy, Debug)]
+struct ScannerPosition {
+    tablet_id: TabletId,
+    /// The tablet server hosting the scanner.
+    tserver: TabletServerId,
+    scanner_id: ScannerId,
+    /// The sequence number of the next request to continue the scan.
+    call_seq_id: u32,
+}
+
+enum TabletScan {
+    New {
+        scan: NewTabletScan,
+        tablet: Arc<Tablet>,
+        rpc: ReplicaRpc<Arc<Tablet>, ScanRequestPb, ScanResponsePb>,
+    },
+    /// The scan failed to open at every cached replica, and the tablet is being looked up again.
+    Relocating {
+        scan: NewTabletScan,
+        tablet_id: TabletId,
+        lookup: Lookup<Option<Arc<Tablet>>>,
+        error: Error,
+    },
+    Continue {
         projected_schema: Schema,
-        scanner_id: ScannerId,
-        call_seq_id: u32,
+        position: ScannerPosition,
+        keep_alive: Option<KeepAlive>,
+        backoff: Backoff,
+        timeout: Duration,
+        request_ids: Arc<RequestTracker>,
+        /// The parameters for reopening the scan if its tablet server fails, if it is fault
+        /// tolerant, along with the tablet being scanned.
+        reopen: Option<(NewTabletScan, Arc<Tablet>)>,
         rpc: ReplicaRpc<Proxy, ScanRequestPb, ScanResponsePb>,
     },
     Finished,
 }
 
 impl TabletScan {
-    fn new(
-        projected_schema: Schema,
-        tablet: Arc<Tablet>,
-        new_scan_request: NewScanRequestPb,
-    ) -> TabletScan {
+    fn new(scan: NewTabletScan, tablet: Arc<Tablet>, selection: Selection) -> TabletScan {
         debug!("TabletScan::new; tablet: {:?}", &*tablet);
         let mut request = ScanRequestPb::default();
-        request.new_scan_request = Some(new_scan_request);
+        request.new_scan_request = Some(scan.request.clone());
 
-        let call =
-            TabletServerService::scan(Arc::new(request), Instant::now() + Duration::from_secs(60));
+        let mut call = TabletServerService::scan(Arc::new(request), Instant::now() + scan.timeout);
+        call.set_request_id(scan.request_ids.next_request_id());
         let rpc = ReplicaRpc::new(
-            tablet,
+            tablet.clone(),
             call,
             Speculation::Staggered(Duration::from_millis(100)),
-            Selection::Closest,
-            Backoff::default(),
+            selection,
+            scan.backoff.clone(),
         );
-        TabletScan::New {
-            projected_schema,
-            rpc,
-        }
+        TabletScan::New { scan, tablet, rpc }
     }
 
     fn cont(
         projected_schema: Schema,
-        scanner_id: ScannerId,
-        call_seq_id: u32,
+        position: ScannerPosition,
+        keep_alive: Option<KeepAlive>,
+        backoff: Backoff,
+        timeout: Duration,
+        request_ids: Arc<RequestTracker>,
+        reopen: Option<(NewTabletScan, Arc<Tablet>)>,
         proxy: Proxy,
     ) -> TabletScan {
         let mut request = ScanRequestPb::default();
-        request.scanner_id = Some(scanner_id.to_string().into_bytes());
-        request.call_seq_id = Some(call_seq_id);
+        request.scanner_id = Some(position.scanner_id.to_string().into_bytes());
+        request.call_seq_id = Some(position.call_seq_id);
 
-        let call =
-            TabletServerService::scan(Arc::new(request), Instant::now() + Duration::from_secs(60));
+        let mut call = TabletServerService::scan(Arc::new(request), Instant::now() + timeout);
+        call.set_request_id(request_ids.next_request_id());
 
         let rpc = ReplicaRpc::new(
             proxy,
             call,
             Speculation::Full,
             Selection::Closest,
-            Backoff::default(),
+            backoff.clone(),
         );
         TabletScan::Continue {
             projected_schema,
-            scanner_id,
-            call_seq_id,
+            position,
+            keep_alive,
+            backoff,
+            timeout,
+            request_ids,
+            reopen,
             rpc,
         }
     }
-}
-
-impl Stream for TabletScan {
-    type Item = RowBatch;
-    type Error = Error;
 
-    fn poll(&mut self) -> Poll<Option<RowBatch>, Error> {
+    /// Polls the tablet scan for the next batch, recording the batch in the scan statistics.
+    ///
+    /// If the scan can not be opened at any of the tablet's cached replicas because the tablet's
+    /// leadership or configuration has changed, the tablet's location is refreshed from the meta
+    /// cache and the scan is reopened. Once opened, a scan continues at the replica which opened
+    /// it, which may be a follower, so later leader elections do not interrupt it. If that
+    /// replica fails, a fault tolerant scan is reopened in the same way, after the last row it
+    /// returned.
+    fn poll(&mut self, stats: &mut ScanStats) -> Poll<Option<RowBatch>, Error> {
         trace!("TabletScan::poll");
-        match self {
-            TabletScan::New {
-                projected_schema,
-                rpc,
-            } => {
-                let (proxy, mut response, sidecars) = try_ready!(rpc.poll());
-                let batch = RowBatch::new(
-                    projected_schema.clone(),
-                    &response.data.take().unwrap_or_default(),
-                    sidecars,
-                )?;
-                *self = if response.has_more_results() {
-                    let scanner_id = ScannerId::parse_bytes(
-                        &response
-                            .scanner_id
-                            .expect_field("ScanResponsePb", "scanner_id")?,
+        loop {
+            match self {
+                TabletScan::New { scan, tablet, rpc } => {
+                    let (proxy, mut response, sidecars) = match rpc.poll() {
+                        Ok(Async::Ready(item)) => item,
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Err(error) => match scan.relocate(tablet, &error) {
+                            Some(lookup) => {
+                                debug!(
+                                    "relocating scan of tablet {:?} after error: {}",
+                                    tablet.id(),
+                                    error
+                                );
+                                *self = TabletScan::Relocating {
+                                    scan: scan.clone(),
+                                    tablet_id: tablet.id(),
+                                    lookup,
+                                    error,
+                                };
+                                continue;
+                            }
+                            None => return Err(error),
+                        },
+                    };
+                    let batch = RowBatch::from_response(
+                        scan.projected_schema.clone(),
+                        &mut response,
+                        sidecars,
                     )?;
-                    // NLL hack: these schema clones are nasty.
-                    TabletScan::cont(projected_schema.clone(), scanner_id, 1, proxy)
-                } else {
-                    TabletScan::Finished
-                };
+                    stats.add_batch(&batch, rpc.attempts());
+                    *self = if response.has_more_results() {
+                        let scanner_id = ScannerId::parse_bytes(
+                            &response
+                                .scanner_id
+                                .expect_field("ScanResponsePb", "scanner_id")?,
+                        )?;
+                        let timeout = scan.timeout;
+                        let keep_alive = scan.keep_alive_period.map(|period| {
+                            KeepAlive::spawn(proxy.clone(), scanner_id, period, timeout)
+                        });
+                        let position = ScannerPosition {
+                            tablet_id: tablet.id(),
+                            tserver: tablet.replicas[rpc.responder().unwrap()].id(),
+                            scanner_id,
+                            call_seq_id: 1,
+                        };
+                        let reopen = scan
+                            .reopen_after(&response)
+                            .map(|scan| (scan, tablet.clone()));
+                        // NLL hack: these schema clones are nasty.
+                        TabletScan::cont(
+                            scan.projected_schema.clone(),
+                            position,
+                            keep_alive,
+                            scan.backoff.clone(),
+                            timeout,
+                            scan.request_ids.clone(),
+                            reopen,
+                            proxy,
+                        )
+                    } else {
+                        TabletScan::Finished
+                    };
 
-                Ok(Async::Ready(Some(batch)))
-            }
-            TabletScan::Continue {
-                projected_schema,
-                scanner_id,
-                call_seq_id,
-                rpc,
-            } => {
-                let (proxy, mut response, sidecars) = try_ready!(rpc.poll());
-                let batch = RowBatch::new(
-                    projected_schema.clone(),
-                    &response.data.take().unwrap_or_default(),
-                    sidecars,
-                )?;
-
-                *self = if response.has_more_results() {
-                    TabletScan::cont(
+                    return Ok(Async::Ready(Some(batch)));
+                }
+                TabletScan::Relocating {
+                    scan,
+                    tablet_id,
+                    lookup,
+                    error,
+                } => {
+                    // Tablets are never split or merged, so if the partition is no longer covered
+                    // by the same tablet, it has been dropped and the original error stands.
+                    let tablet = match try_ready!(lookup.poll().map_err(lookup_error)) {
+                        Some(ref tablet) if tablet.id() == *tablet_id => tablet.clone(),
+                        _ => return Err(error.clone()),
+                    };
+                    *self = TabletScan::new(scan.clone(), tablet, Selection::Closest);
+                }
+                TabletScan::Continue {
+                    projected_schema,
+                    position,
+                    keep_alive,
+                    backoff,
+                    timeout,
+                    request_ids,
+                    reopen,
+                    rpc,
+                } => {
+                    if let Some(keep_alive) = keep_alive {
+                        keep_alive.mark_polled();
+                    }
+                    let (proxy, mut response, sidecars) = match rpc.poll() {
+                        Ok(Async::Ready(item)) => item,
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Err(error) => {
+                            let (mut scan, tablet) = match reopen.take() {
+                                Some(reopen) => reopen,
+                                None => return Err(error),
+                            };
+                            match scan.relocate(&tablet, &error) {
+                                Some(lookup) => {
+                                    debug!(
+                                        "reopening scan of tablet {:?} after error: {}",
+                                        tablet.id(),
+                                        error
+                                    );
+                                    *self = TabletScan::Relocating {
+                                        scan,
+                                        tablet_id: tablet.id(),
+                                        lookup,
+                                        error,
+                                    };
+                                    continue;
+                                }
+                                None => return Err(error),
+                            }
+                        }
+                    };
+                    if let Some((ref mut scan, _)) = *reopen {
+                        scan.advance(&response);
+                    }
+                    let batch = RowBatch::from_response(
                         projected_schema.clone(),
-                        *scanner_id,
-                        *call_seq_id + 1,
-                        proxy,
-                    )
-                } else {
-                    TabletScan::Finished
-                };
+                        &mut response,
+                        sidecars,
+                    )?;
+                    stats.add_batch(&batch, rpc.attempts());
+
+                    *self = if response.has_more_results() {
+                        TabletScan::cont(
+                            projected_schema.clone(),
+                            ScannerPosition {
+                                call_seq_id: position.call_seq_id + 1,
+                                ..*position
+                            },
+                            keep_alive.take(),
+                            backoff.clone(),
+                            *timeout,
+                            request_ids.clone(),
+                            reopen.take(),
+                            proxy,
+                        )
+                    } else {
+                        TabletScan::Finished
+                    };
+
+                    return Ok(Async::Ready(Some(batch)));
+                }
+                TabletScan::Finished => return Ok(Async::Ready(None)),
+            }
+        }
+    }
+}
+
+/// The ID of a scanner on a tablet server.
+///
+/// Scanner IDs are assigned by the tablet server when a scan is opened, and are sent back with
+/// each subsequent request to continue the scan.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ScannerId {
+    id: Uuid,
+}
+
+impl ScannerId {
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        self.id.as_bytes()
+    }
+
+    /// Parses a scanner ID returned by a tablet server.
+    ///
+    /// Returns a serialization error describing the malformed ID if it is not a valid UTF-8
+    /// encoded UUID string.
+    fn parse_bytes(input: &[u8]) -> Result<ScannerId> {
+        let invalid = |reason: String| {
+            // Limit the hex dump, since the server may have returned garbage of any length.
+            let prefix = &input[..input.len().min(16)];
+            let mut hex = String::with_capacity(2 * prefix.len());
+            for byte in prefix {
+                hex.push_str(&format!("{:02x}", byte));
+            }
+            if prefix.len() < input.len() {
+                hex.push_str("...");
+            }
+            Error::Serialization(format!(
+                "invalid scanner ID (length {}, bytes 0x{}): {}",
+                input.len(),
+                hex,
+                reason
+            ))
+        };
+        let input = str::from_utf8(input).map_err(|error| invalid(error.to_string()))?;
+        Uuid::parse_str(input)
+            .map(|id| ScannerId { id })
+            .map_err(|error| invalid(error.to_string()))
+    }
+}
+
+impl fmt::Debug for ScannerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.id.simple())
+    }
+}
+
+impl fmt::Display for ScannerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.id.simple())
+    }
+}
+
+/// A handle to a background task which sends keep-alive requests for a tablet server scanner
+/// whenever the scan has not been polled within the keep-alive period. The task stops when the
+/// handle is dropped.
+struct KeepAlive {
+    polled: Arc<AtomicBool>,
+    _cancel: oneshot::Sender<()>,
+}
+
+impl KeepAlive {
+    fn spawn(
+        proxy: Proxy,
+        scanner_id: ScannerId,
+        period: Duration,
+        timeout: Duration,
+    ) -> KeepAlive {
+        let polled = Arc::new(AtomicBool::new(true));
+        let (cancel, canceled) = oneshot::channel();
+        let closed = Box::new(proxy.closed());
+        tokio::spawn(KeepAliveTask {
+            proxy,
+            scanner_id,
+            polled: polled.clone(),
+            canceled,
+            closed,
+            interval: Interval::new(Instant::now() + period, period),
+            timeout,
+            rpc: None,
+        });
+        KeepAlive {
+            polled,
+            _cancel: cancel,
+        }
+    }
+
+    fn mark_polled(&self) {
+        self.polled.store(true, Ordering::Relaxed);
+    }
+}
+
+struct KeepAliveTask {
+    proxy: Proxy,
+    scanner_id: ScannerId,
+    polled: Arc<AtomicBool>,
+    canceled: oneshot::Receiver<()>,
+    closed: Box<Future<Item = (), Error = ()> + Send>,
+    interval: Interval,
+    timeout: Duration,
+    rpc: Option<RpcFuture<ScannerKeepAliveResponsePb>>,
+}
+
+impl Future for KeepAliveTask {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        // The handle is dropped when the tablet scan finishes or moves on to another batch.
+        match self.canceled.poll() {
+            Ok(Async::NotReady) => (),
+            _ => return Ok(Async::Ready(())),
+        }
+        // The connection is closed when the client shuts down.
+        match self.closed.poll() {
+            Ok(Async::NotReady) => (),
+            _ => return Ok(Async::Ready(())),
+        }
+
+        let scanner_id = self.scanner_id;
+        while let Async::Ready(_) = self
+            .interval
+            .poll()
+            .map_err(|error| warn!("scanner {} keep-alive timer failed: {}", scanner_id, error))?
+        {
+            if self.polled.swap(false, Ordering::Relaxed) || self.rpc.is_some() {
+                continue;
+            }
+            trace!("sending keep-alive for scanner {}", scanner_id);
+            let request = ScannerKeepAliveRequestPb {
+                scanner_id: scanner_id.to_string().into_bytes(),
+            };
+            let call = TabletServerService::scanner_keep_alive(
+                Arc::new(request),
+                Instant::now() + self.timeout,
+            );
+            self.rpc = Some(self.proxy.send(call));
+        }
 
-                Ok(Async::Ready(Some(batch)))
+        if let Some(mut rpc) = self.rpc.take() {
+            match rpc.poll() {
+                Ok(Async::NotReady) => self.rpc = Some(rpc),
+                Ok(Async::Ready((response, _))) => if let Some(error) = response.error {
+                    warn!("scanner {} keep-alive failed: {:?}", scanner_id, error);
+                },
+                Err(error) => warn!("scanner {} keep-alive failed: {}", scanner_id, error),
             }
-            TabletScan::Finished => Ok(Async::Ready(None)),
         }
+
+        Ok(Async::NotReady)
     }
 }
 
@@ -528,21 +2787,1857 @@ impl Stream for TabletScan {
 mod test {
 
     use super::*;
-    use mini_cluster::MiniCluster;
+    use mini_cluster::{MiniCluster, MiniClusterConfig};
     use Client;
     use Column;
     use DataType;
     use Options;
+    use RangePartitionBound;
     use SchemaBuilder;
     use TableBuilder;
     use WriterConfig;
 
-    use env_logger;
-    use futures::future;
-    use tokio::runtime::current_thread::Runtime;
+    use std::time::SystemTime;
+
+    use byteorder::{LittleEndian, WriteBytesExt};
+    use env_logger;
+    use futures::future;
+    use tokio::runtime::current_thread::Runtime;
+    use tokio_timer::Delay;
+
+    /// The partitioning of a table created by `create_table_with_rows`.
+    enum Partitioning {
+        /// Hash partitioned on `key` into the given number of buckets.
+        Hash(u32),
+        /// Range partitioned on `key`, split at the given keys.
+        Range(Vec<i32>),
+        /// Hash partitioned on `key` into the given number of buckets, each of which is range
+        /// partitioned on `key`, split at the given keys.
+        HashAndRange(u32, Vec<i32>),
+    }
+
+    fn create_table(runtime: &mut Runtime, client: &mut Client, builder: TableBuilder) -> Table {
+        let table_id = runtime.block_on(client.create_table(builder)).unwrap();
+        runtime.block_on(client.open_table_by_id(table_id)).unwrap()
+    }
+
+    /// Inserts `num_rows` rows into the table, each set by `set_row` with the index of the row,
+    /// and flushes them.
+    fn insert_rows<F>(runtime: &mut Runtime, table: &Table, num_rows: i32, mut set_row: F)
+    where
+        F: FnMut(i32, &mut Row<'static>),
+    {
+        let mut writer = table.new_writer(WriterConfig::default());
+
+        // TODO: remove lazy once apply no longer polls.
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                for i in 0..num_rows {
+                    let mut insert = table.schema().new_row();
+                    set_row(i, &mut insert);
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+    }
+
+    /// Creates a table with an `INT32` key column and a nullable `INT32` val column, and inserts
+    /// `num_rows` rows with keys and values `0..num_rows`.
+    fn create_table_with_rows(
+        runtime: &mut Runtime,
+        client: &mut Client,
+        name: &str,
+        num_rows: i32,
+        partitioning: Partitioning,
+    ) -> Table {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let (buckets, splits) = match partitioning {
+            Partitioning::Hash(buckets) => (Some(buckets), None),
+            Partitioning::Range(splits) => (None, Some(splits)),
+            Partitioning::HashAndRange(buckets, splits) => (Some(buckets), Some(splits)),
+        };
+        let mut table_builder = TableBuilder::new(name, schema.clone());
+        if let Some(buckets) = buckets {
+            table_builder.add_hash_partitions(vec!["key"], buckets);
+        }
+        if let Some(splits) = splits {
+            table_builder.set_range_partition_columns(vec!["key"]);
+            for split in splits {
+                let mut row = schema.new_row();
+                row.set(0, split).unwrap();
+                table_builder.add_range_partition_split(row);
+            }
+        }
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(runtime, client, table_builder);
+        insert_rows(runtime, &table, num_rows, |i, row| {
+            row.set("key", i).unwrap();
+            row.set("val", i).unwrap();
+        });
+        table
+    }
+
+    #[test]
+    fn scanner_id() {
+        let id = ScannerId::parse_bytes(b"0b5f6a3b8c1d4e2f9a7b6c5d4e3f2a1b").unwrap();
+        assert_eq!("0b5f6a3b8c1d4e2f9a7b6c5d4e3f2a1b", id.to_string());
+        assert_eq!("0b5f6a3b8c1d4e2f9a7b6c5d4e3f2a1b", format!("{:?}", id));
+
+        // Invalid UTF-8.
+        match ScannerId::parse_bytes(&[0xff, 0xfe, 0x00]) {
+            Err(Error::Serialization(message)) => {
+                assert!(message.contains("length 3, bytes 0xfffe00"), "{}", message)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // Valid UTF-8, but not a UUID. Long IDs are truncated in the message.
+        match ScannerId::parse_bytes(b"not a scanner id, not even close") {
+            Err(Error::Serialization(message)) => assert!(
+                message.contains("length 32, bytes 0x6e6f742061207363616e6e6572206964..."),
+                "{}",
+                message
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert!(ScannerId::parse_bytes(b"").is_err());
+    }
+
+    #[test]
+    fn row_batch_indirect_data() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String).set_nullable())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        fn encode_row(data: &mut Vec<u8>, key: i32, offset: u64, len: u64, is_null: bool) {
+            data.write_i32::<LittleEndian>(key).unwrap();
+            data.write_u64::<LittleEndian>(offset).unwrap();
+            data.write_u64::<LittleEndian>(len).unwrap();
+            data.push(if is_null { 0b10 } else { 0 });
+        }
+
+        fn decode(schema: &Schema, rows: Vec<u8>, num_rows: i32) -> Result<RowBatch> {
+            let mut block = RowwiseRowBlockPb::default();
+            block.num_rows = Some(num_rows);
+            block.rows_sidecar = Some(0);
+            block.indirect_data_sidecar = Some(1);
+            let sidecars = vec![BytesMut::from(rows), BytesMut::from(&b"foobar"[..])];
+            RowBatch::new(schema.clone(), &block, sidecars)
+        }
+
+        // Null values are not bounds checked.
+        let mut rows = Vec::new();
+        encode_row(&mut rows, 1, 0, 3, false);
+        encode_row(&mut rows, 2, 3, 3, false);
+        encode_row(&mut rows, 3, u64::max_value(), u64::max_value(), true);
+        let batch = decode(&schema, rows, 3).unwrap();
+        let values = batch
+            .into_iter()
+            .map(|row| row.get::<_, Option<String>>("val").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![Some("foo".to_string()), Some("bar".to_string()), None],
+            values
+        );
+
+        // A value which extends past the end of the sidecar fails the whole batch.
+        let mut rows = Vec::new();
+        encode_row(&mut rows, 1, 0, 3, false);
+        encode_row(&mut rows, 2, 4, 3, false);
+        match decode(&schema, rows, 2) {
+            Err(Error::Serialization(message)) => {
+                assert!(message.contains("row 1 column val"), "{}", message)
+            }
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("expected out of bounds indirect data to fail"),
+        }
+
+        // An offset which overflows is rejected rather than wrapping.
+        let mut rows = Vec::new();
+        encode_row(&mut rows, 1, u64::max_value(), 1, false);
+        assert!(decode(&schema, rows, 1).is_err());
+
+        // Byte and string slices borrow directly from the indirect data sidecar.
+        let mut rows = Vec::new();
+        encode_row(&mut rows, 1, 3, 3, false);
+        let batch = decode(&schema, rows, 1).unwrap();
+        let row = batch.into_iter().next().unwrap();
+        let bytes = row.get_bytes("val").unwrap();
+        assert_eq!(b"bar", bytes);
+        assert_eq!(batch.indirect_data[3..].as_ptr(), bytes.as_ptr());
+        assert_eq!(bytes.as_ptr(), row.get_str(1).unwrap().as_ptr());
+        assert!(row.get_bytes("key").is_err());
+    }
+
+    #[test]
+    fn count() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 100i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "count",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().count().build())
+            })).unwrap();
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+
+        assert_eq!(
+            num_rows as usize,
+            batches.into_iter().map(|batch| batch.len).sum()
+        );
+    }
+
+    #[test]
+    fn rows() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("rows", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 4);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        let num_rows = 100i32;
+        insert_rows(&mut runtime, &table, num_rows, |i, row| {
+            row.set("key", i).unwrap();
+            row.set("val", i.to_string()).unwrap();
+        });
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(::futures::future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        let rows: Vec<OwnedRow> = runtime
+            .block_on(::futures::future::lazy(|| {
+                table.scan_builder().build().rows().collect()
+            })).unwrap();
+
+        assert_eq!(
+            batches.iter().map(RowBatch::num_rows).sum::<usize>(),
+            rows.len()
+        );
+        assert_eq!(num_rows as usize, rows.len());
+
+        let mut values = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get::<_, i32>("key").unwrap(),
+                    row.get::<_, String>("val").unwrap(),
+                )
+            }).collect::<Vec<_>>();
+        values.sort();
+        let expected = (0..num_rows).map(|i| (i, i.to_string())).collect::<Vec<_>>();
+        assert_eq!(expected, values);
+    }
+
+    #[test]
+    fn primary_key_bounds() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        // Four tablets: (, 10), [10, 20), [20, 30), [30, ).
+        let mut table_builder = TableBuilder::new("scan_primary_key_bounds", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        for &split in &[10i32, 20, 30] {
+            let mut row = schema.new_row();
+            row.set(0, split).unwrap();
+            table_builder.add_range_partition_split(row);
+        }
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                for i in 0..40i32 {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", i).unwrap();
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        let mut lower = table.schema().new_row();
+        lower.set("key", 15i32).unwrap();
+        let mut upper = table.schema().new_row();
+        upper.set("key", 25i32).unwrap();
+
+        let builder = table
+            .scan_builder()
+            .set_lower_bound_primary_key(&lower)
+            .unwrap()
+            .set_upper_bound_primary_key(&upper)
+            .unwrap();
+
+        let mut scan = builder.clone().build();
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| scan.by_ref().collect()))
+            .unwrap();
+        let mut keys = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!((15..25).collect::<Vec<_>>(), keys);
+
+        // Only the two tablets overlapping the bounds are scanned.
+        assert_eq!(2, scan.stats().rpcs());
+        let tokens = runtime
+            .block_on(future::lazy(|| builder.build_scan_tokens()))
+            .unwrap();
+        assert_eq!(2, tokens.len());
+
+        // An inclusive upper bound of 24 is equivalent to an exclusive upper bound of 25.
+        let mut inclusive_upper = table.schema().new_row();
+        inclusive_upper.set("key", 24i32).unwrap();
+        let mut scan = table
+            .scan_builder()
+            .set_lower_bound_primary_key(&lower)
+            .unwrap()
+            .set_inclusive_upper_bound_primary_key(&inclusive_upper)
+            .unwrap()
+            .build();
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| scan.by_ref().collect()))
+            .unwrap();
+        let mut keys = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!((15..25).collect::<Vec<_>>(), keys);
+        assert_eq!(2, scan.stats().rpcs());
+
+        // The key bounds must include every primary key column.
+        assert!(
+            table
+                .scan_builder()
+                .set_lower_bound_primary_key(&table.schema().new_row())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn primary_key_bounds_with_filter() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        // Four tablets: (, 10), [10, 20), [20, 30), [30, ).
+        let mut table_builder =
+            TableBuilder::new("scan_primary_key_bounds_with_filter", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        for &split in &[10i32, 20, 30] {
+            let mut row = schema.new_row();
+            row.set(0, split).unwrap();
+            table_builder.add_range_partition_split(row);
+        }
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                for i in 0..40i32 {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", i).unwrap();
+                    insert.set("val", i % 4).unwrap();
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        // key >= 25 AND val = 1.
+        let mut lower = table.schema().new_row();
+        lower.set("key", 25i32).unwrap();
+        let mut scan = table
+            .scan_builder()
+            .set_lower_bound_primary_key(&lower)
+            .unwrap()
+            .filter("val", Filter::equals(1i32))
+            .unwrap()
+            .build();
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| scan.by_ref().collect()))
+            .unwrap();
+        let mut keys = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(vec![25, 29, 33, 37], keys);
+
+        // The bound prunes the first two tablets, while the filter is applied by the server.
+        assert_eq!(2, scan.stats().rpcs());
+
+        // Scan tokens carry both the bound and the filter.
+        let tokens = runtime
+            .block_on(future::lazy(|| {
+                table
+                    .scan_builder()
+                    .set_lower_bound_primary_key(&lower)
+                    .unwrap()
+                    .filter("val", Filter::equals(1i32))
+                    .unwrap()
+                    .build_scan_tokens()
+            })).unwrap();
+        assert_eq!(2, tokens.len());
+        let mut keys = Vec::new();
+        for token in tokens {
+            let scan = runtime
+                .block_on(future::lazy(|| client.scan_from_token(&token.serialize())))
+                .unwrap();
+            let batches: Vec<RowBatch> = runtime
+                .block_on(future::lazy(|| scan.collect()))
+                .unwrap();
+            keys.extend(
+                batches
+                    .iter()
+                    .flat_map(|batch| batch.into_iter())
+                    .map(|row| row.get::<_, i32>("key").unwrap()),
+            );
+        }
+        keys.sort();
+        assert_eq!(vec![25, 29, 33, 37], keys);
+    }
+
+    #[test]
+    fn non_covered_ranges() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::String).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("scan_non_covered_ranges", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        for &(lower, upper) in &[("a", "m"), ("p", "s")] {
+            let mut lower_bound = schema.new_row();
+            lower_bound.set(0, lower).unwrap();
+            let mut upper_bound = schema.new_row();
+            upper_bound.set(0, upper).unwrap();
+            table_builder.add_range_partition(
+                RangePartitionBound::Inclusive(lower_bound),
+                RangePartitionBound::Exclusive(upper_bound),
+            );
+        }
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                for key in &["b", "q"] {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", *key).unwrap();
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        // By default, non-covered ranges are skipped.
+        let events = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().events().collect()))
+            .unwrap();
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|event| match *event {
+            ScanEvent::Batch(..) => true,
+            ScanEvent::Gap { .. } => false,
+        }));
+
+        let events = runtime
+            .block_on(future::lazy(|| {
+                table
+                    .scan_builder()
+                    .report_non_covered_ranges(true)
+                    .build()
+                    .events()
+                    .collect()
+            })).unwrap();
+
+        let mut rows = 0;
+        let mut gaps = Vec::new();
+        for event in events {
+            match event {
+                ScanEvent::Batch(batch) => rows += batch.len,
+                ScanEvent::Gap { lower, upper } => gaps.push((lower, upper)),
+            }
+        }
+        assert_eq!(2, rows);
+        assert_eq!(
+            vec![
+                (b"".to_vec(), b"a".to_vec()),
+                (b"m".to_vec(), b"p".to_vec()),
+                (b"s".to_vec(), b"".to_vec()),
+            ],
+            gaps
+        );
+
+        // The row batch stream skips non-covered ranges even when they are reported.
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| {
+                table
+                    .scan_builder()
+                    .report_non_covered_ranges(true)
+                    .build()
+                    .collect()
+            })).unwrap();
+        assert_eq!(2, batches.iter().map(|batch| batch.len).sum::<usize>());
+    }
+
+    #[test]
+    fn keep_alive() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(
+            MiniClusterConfig::default()
+                .num_tservers(1)
+                .scanner_ttl(2000)
+                .scanner_batch_size_bytes(1024),
+        );
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        // A single tablet, scanned in multiple batches.
+        let num_rows = 4096i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "keep_alive",
+            num_rows,
+            Partitioning::Range(vec![]),
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table
+                    .scan_builder()
+                    .set_keep_alive(true)
+                    .set_keep_alive_period(Duration::from_millis(500))
+                    .build())
+            })).unwrap();
+
+        let (batch, scan) = runtime
+            .block_on(::futures::future::lazy(|| scan.into_future()))
+            .map_err(|(error, _)| error)
+            .unwrap();
+        let first_batch_rows = batch.expect("first batch").num_rows();
+        assert!(first_batch_rows < num_rows as usize);
+
+        // Idle for longer than the scanner TTL. The runtime continues to drive the keep-alive
+        // task in the meantime.
+        runtime
+            .block_on(Delay::new(Instant::now() + Duration::from_secs(5)))
+            .unwrap();
+
+        let batches: Vec<RowBatch> = runtime.block_on(scan.collect()).unwrap();
+        assert_eq!(
+            num_rows as usize,
+            first_batch_rows + batches.iter().map(RowBatch::num_rows).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn scanner_expired() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(
+            MiniClusterConfig::default()
+                .num_tservers(1)
+                .scanner_ttl(2000)
+                .scanner_batch_size_bytes(1024),
+        );
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        // A single tablet, scanned in multiple batches.
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "scanner_expired",
+            4096,
+            Partitioning::Range(vec![]),
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().build())
+            })).unwrap();
+
+        let (batch, scan) = runtime
+            .block_on(::futures::future::lazy(|| scan.into_future()))
+            .map_err(|(error, _)| error)
+            .unwrap();
+        assert!(batch.is_some());
+
+        // Without keep-alive, idling for longer than the scanner TTL expires the scanner.
+        runtime
+            .block_on(Delay::new(Instant::now() + Duration::from_secs(5)))
+            .unwrap();
+
+        let result: Result<Vec<RowBatch>> = runtime.block_on(scan.collect());
+        match result {
+            Err(Error::TabletServer(ref error))
+                if error.code == TabletServerErrorCode::ScannerExpired => {}
+            other => panic!("expected scanner expired error, got: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn prefetch() {
+        use std::sync::atomic::AtomicUsize;
+
+        use Metrics;
+
+        /// Counts the scan RPCs sent to tablet servers.
+        #[derive(Default)]
+        struct ScanRpcs(AtomicUsize);
+
+        impl Metrics for ScanRpcs {
+            fn on_rpc_start(&self, _service: &str, method: &str) {
+                if method == "Scan" {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(
+            MiniClusterConfig::default()
+                .num_tservers(1)
+                .scanner_batch_size_bytes(1024),
+        );
+        let mut runtime = Runtime::new().unwrap();
+
+        let scan_rpcs = Arc::new(ScanRpcs::default());
+        let mut options = Options::default();
+        options.set_metrics(scan_rpcs.clone());
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+
+        // A single tablet, scanned in multiple batches.
+        let num_rows = 4096i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "prefetch",
+            num_rows,
+            Partitioning::Range(vec![]),
+        );
+
+        // Returns the number of scan RPCs sent by the time the first batch is returned, and the
+        // total number of rows scanned.
+        let mut scan_first_batch = |prefetch: bool| {
+            let scan: Scan = runtime
+                .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                    Ok(table.scan_builder().set_prefetch(prefetch).build())
+                })).unwrap();
+            let start = scan_rpcs.0.load(Ordering::SeqCst);
+            let (batch, scan) = runtime
+                .block_on(::futures::future::lazy(|| scan.into_future()))
+                .map_err(|(error, _)| error)
+                .unwrap();
+            let sent = scan_rpcs.0.load(Ordering::SeqCst) - start;
+            let first_batch_rows = batch.expect("first batch").num_rows();
+            assert!(first_batch_rows < num_rows as usize);
+            let batches: Vec<RowBatch> = runtime.block_on(scan.collect()).unwrap();
+            let rows = first_batch_rows + batches.iter().map(RowBatch::num_rows).sum::<usize>();
+            (sent, rows)
+        };
+
+        // Without prefetching, the continue RPC is only sent once the scan is polled again.
+        assert_eq!((1, num_rows as usize), scan_first_batch(false));
+
+        // With prefetching, the continue RPC is in flight while the first batch is processed.
+        assert_eq!((2, num_rows as usize), scan_first_batch(true));
+    }
+
+    #[test]
+    fn max_concurrent_tablets() {
+        use krpc;
+        use parking_lot::Mutex;
+        use Metrics;
+
+        /// Tracks the number of scan RPCs in flight, and the maximum number in flight at once.
+        #[derive(Default)]
+        struct ScanConcurrency(Mutex<(usize, usize)>);
+
+        impl Metrics for ScanConcurrency {
+            fn on_rpc_start(&self, _service: &str, method: &str) {
+                if method == "Scan" {
+                    let mut counts = self.0.lock();
+                    counts.0 += 1;
+                    counts.1 = cmp::max(counts.0, counts.1);
+                }
+            }
+
+            fn on_rpc_complete(
+                &self,
+                _service: &str,
+                method: &str,
+                _duration: Duration,
+                _result: ::std::result::Result<(), &krpc::Error>,
+            ) {
+                if method == "Scan" {
+                    self.0.lock().0 -= 1;
+                }
+            }
+        }
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let concurrency = Arc::new(ScanConcurrency::default());
+        let mut options = Options::default();
+        options.set_metrics(concurrency.clone());
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), options))
+            .expect("client");
+
+        let num_rows = 100i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "max_concurrent_tablets",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        // Returns the keys scanned, and the maximum number of scan RPCs in flight at once.
+        let mut scan_keys = |max_concurrent_tablets: usize| {
+            concurrency.0.lock().1 = 0;
+            let scan: Scan = runtime
+                .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                    Ok(table
+                        .scan_builder()
+                        .set_max_concurrent_tablets(max_concurrent_tablets)
+                        .build())
+                })).unwrap();
+            let batches: Vec<RowBatch> = runtime
+                .block_on(::futures::future::lazy(|| scan.collect()))
+                .unwrap();
+            let mut keys = batches
+                .iter()
+                .flat_map(|batch| batch.into_iter())
+                .map(|row| row.get::<_, i32>("key").unwrap())
+                .collect::<Vec<_>>();
+            keys.sort();
+            let max_in_flight = concurrency.0.lock().1;
+            (keys, max_in_flight)
+        };
+
+        let expected = (0..num_rows).collect::<Vec<_>>();
+
+        let (keys, max_in_flight) = scan_keys(1);
+        assert_eq!(expected, keys);
+        assert_eq!(1, max_in_flight);
+
+        let (keys, max_in_flight) = scan_keys(4);
+        assert_eq!(expected, keys);
+        assert!(max_in_flight > 1, "max in flight: {}", max_in_flight);
+    }
+
+    #[test]
+    fn stats() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 100i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "stats",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let mut scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().build())
+            })).unwrap();
+        assert_eq!(&ScanStats::default(), scan.stats());
+        let batches: Vec<RowBatch> = runtime
+            .block_on(::futures::future::lazy(|| (&mut scan).collect()))
+            .unwrap();
+
+        let stats = scan.stats().clone();
+        assert_eq!(num_rows as usize, stats.rows());
+        assert_eq!(batches.len(), stats.rpcs());
+        assert!(stats.rpcs() >= 4);
+        assert!(stats.data() > 0);
+
+        // Projecting fewer columns transfers less data.
+        let mut scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().select(vec!["key"])?.build())
+            })).unwrap();
+        runtime
+            .block_on(::futures::future::lazy(|| (&mut scan).collect()))
+            .unwrap();
+        assert_eq!(num_rows as usize, scan.stats().rows());
+        assert!(scan.stats().data() < stats.data());
+    }
+
+    #[test]
+    fn projected_row_size() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String).set_nullable())
+            .add_column(Column::new("ts", DataType::Int64).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("projected_row_size", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        insert_rows(&mut runtime, &table, 10, |i, row| {
+            row.set("key", i).unwrap();
+            if i % 2 == 0 {
+                row.set("val", format!("val-{}", i)).unwrap();
+            }
+            row.set("ts", i64::from(i)).unwrap();
+        });
+
+        // 4 + 8 bytes, and no null bitmap.
+        assert_eq!(12, schema.projected_row_size(&[0, 2]));
+        // 16 + 4 bytes, and a 1 byte null bitmap.
+        assert_eq!(21, schema.projected_row_size(&[1, 0]));
+
+        for projection in &[vec![0, 2], vec![1, 0], vec![0, 1, 2]] {
+            let batches: Vec<RowBatch> = runtime
+                .block_on(::futures::future::lazy(|| {
+                    table
+                        .scan_builder()
+                        .select(projection.clone())
+                        .unwrap()
+                        .build()
+                        .collect()
+                })).unwrap();
+            assert_eq!(10, batches.iter().map(RowBatch::num_rows).sum::<usize>());
+            for batch in &batches {
+                assert_eq!(
+                    batch.num_rows() * schema.projected_row_size(projection),
+                    batch.data.len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn columnar_row_format() {
+        use std::time::UNIX_EPOCH;
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("bool", DataType::Bool).set_not_null())
+            .add_column(Column::new("int64", DataType::Int64).set_nullable())
+            .add_column(Column::new("double", DataType::Double).set_not_null())
+            .add_column(Column::new("string", DataType::String).set_nullable())
+            .add_column(Column::new("binary", DataType::Binary).set_not_null())
+            .add_column(Column::new("timestamp", DataType::Timestamp).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("columnar_row_format", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        let num_rows = 100i32;
+        insert_rows(&mut runtime, &table, num_rows, |i, row| {
+            row.set("key", i).unwrap();
+            row.set("bool", i % 2 == 0).unwrap();
+            if i % 3 != 0 {
+                row.set("int64", i64::from(i) * 1000).unwrap();
+            }
+            row.set("double", f64::from(i) / 2.0).unwrap();
+            if i % 5 != 0 {
+                row.set("string", "x".repeat(i as usize)).unwrap();
+            }
+            row.set("binary", vec![i as u8; i as usize % 7]).unwrap();
+            row.set(
+                "timestamp",
+                UNIX_EPOCH + Duration::from_millis(u64::from(i as u32)),
+            ).unwrap();
+        });
+
+        let mut scan_rows = |row_format: RowFormat| {
+            let batches: Vec<RowBatch> = runtime
+                .block_on(::futures::future::lazy(|| {
+                    table
+                        .scan_builder()
+                        .set_row_format(row_format)
+                        .build()
+                        .collect()
+                })).unwrap();
+            let mut rows = Vec::new();
+            for batch in &batches {
+                // Only columnar batches expose their columns.
+                assert_eq!(row_format == RowFormat::Columnar, batch.column(0).is_some());
+                for row in batch {
+                    let row = (0..schema.columns().len())
+                        .map(|idx| row.get_datum(idx).unwrap())
+                        .collect::<Vec<_>>();
+                    rows.push(row);
+                }
+            }
+            rows.sort_by_key(|row| match row[0] {
+                Datum::Int32(key) => key,
+                _ => unreachable!(),
+            });
+            (batches, rows)
+        };
+
+        let (_, rowwise_rows) = scan_rows(RowFormat::Rowwise);
+        let (columnar_batches, columnar_rows) = scan_rows(RowFormat::Columnar);
+        assert_eq!(num_rows as usize, rowwise_rows.len());
+        assert_eq!(rowwise_rows, columnar_rows);
+
+        // The column slices hold the same values as the rows of the batch.
+        for batch in &columnar_batches {
+            let keys = batch.column(0).unwrap();
+            let strings = batch.column(4).unwrap();
+            assert_eq!(batch.num_rows(), keys.len());
+            for (idx, row) in batch.into_iter().enumerate() {
+                let key = LittleEndian::read_i32(&keys.data()[idx * 4..]);
+                assert_eq!(row.get::<_, i32>(0).unwrap(), key);
+                assert_eq!(row.is_null(4).unwrap(), strings.is_null(idx));
+            }
+        }
+    }
+
+    #[test]
+    fn projected_columns_except() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("a", DataType::String))
+            .add_column(Column::new("b", DataType::Int64))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("projected_columns_except", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let scan = table
+            .scan_builder()
+            .projected_columns_except(vec!["a"])
+            .unwrap()
+            .build();
+        let names = scan
+            .projected_schema()
+            .columns()
+            .iter()
+            .map(|column| column.name().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["key", "b"], names);
+
+        assert!(
+            table
+                .scan_builder()
+                .projected_columns_except(vec!["a", "missing"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn select() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 10i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "select",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().build())
+            })).unwrap();
+
+        let batches = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+
+        let mut rows = Vec::new();
+        for batch in batches {
+            for row in batch.into_iter() {
+                rows.push((
+                    row.get::<_, i32>("key").unwrap(),
+                    row.get::<_, i32>("val").unwrap(),
+                ));
+            }
+        }
+
+        rows.sort();
+
+        let expected = (0..num_rows).map(|i| (i, i)).collect::<Vec<_>>();
+
+        assert_eq!(rows, expected);
+
+        // Select the columns in reverse order.
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().select(vec![1, 0])?.build())
+            })).unwrap();
+        assert_eq!("val", scan.projected_schema().columns()[0].name());
+        assert_eq!("key", scan.projected_schema().columns()[1].name());
+
+        // Selecting a column more than once is an error.
+        assert!(table.scan_builder().select(vec![1, 1]).is_err());
+        assert!(table.scan_builder().select(vec!["key", "val", "key"]).is_err());
+        assert!(table.scan_builder().select(vec![2]).is_err());
+    }
+
+    #[test]
+    fn filter() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 10i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "filter",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table
+                    .scan_builder()
+                    .filter("key", Filter::range(5i32..))?
+                    .build())
+            })).unwrap();
+
+        let batches = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+
+        let mut rows = Vec::new();
+        for batch in batches {
+            for row in batch.into_iter() {
+                rows.push((
+                    row.get::<_, i32>("key").unwrap(),
+                    row.get::<_, i32>("val").unwrap(),
+                ));
+            }
+        }
+
+        rows.sort();
+
+        let expected = (0..num_rows)
+            .filter(|&i| i >= 5)
+            .map(|i| (i, i))
+            .collect::<Vec<_>>();
+
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn ordered() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 1000i32;
+        // Hash partitioning spreads consecutive keys across the tablets.
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "ordered",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let scan: OrderedScan = runtime
+            .block_on(::futures::future::lazy::<_, Result<OrderedScan>>(|| {
+                table.scan_builder().build_ordered()
+            })).unwrap();
+        let rows: Vec<OwnedRow> = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+        let keys = rows
+            .iter()
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!((0..num_rows).collect::<Vec<_>>(), keys);
+
+        // The scan fails if it covers more tablets than it may read at once.
+        let scan: OrderedScan = runtime
+            .block_on(::futures::future::lazy::<_, Result<OrderedScan>>(|| {
+                table.scan_builder().set_max_concurrent_tablets(3).build_ordered()
+            })).unwrap();
+        match runtime.block_on(::futures::future::lazy(|| scan.collect())) {
+            Err(Error::InvalidArgument(_)) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("expected ordered scan of too many tablets to fail"),
+        }
+
+        // The primary key must be projected.
+        match table.scan_builder().select(vec!["val"]).unwrap().build_ordered() {
+            Err(Error::InvalidArgument(_)) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("expected ordered scan without the primary key to fail"),
+        }
+    }
+
+    #[test]
+    fn ordered_range_partitioned() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        // Six tablets: two hash buckets, each split into three ranges.
+        let num_rows = 300i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "ordered_range_partitioned",
+            num_rows,
+            Partitioning::HashAndRange(2, vec![100, 200]),
+        );
+
+        // The ranges of each hash bucket are read one after another, so only one tablet of each
+        // bucket is read at once.
+        let scan: OrderedScan = runtime
+            .block_on(::futures::future::lazy::<_, Result<OrderedScan>>(|| {
+                table.scan_builder().set_max_concurrent_tablets(2).build_ordered()
+            })).unwrap();
+        let rows: Vec<OwnedRow> = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+        let keys = rows
+            .iter()
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!((0..num_rows).collect::<Vec<_>>(), keys);
+
+        // The scan fails before scanning any tablets if it would read from too many at once.
+        let mut scan: OrderedScan = runtime
+            .block_on(::futures::future::lazy::<_, Result<OrderedScan>>(|| {
+                table.scan_builder().set_max_concurrent_tablets(1).build_ordered()
+            })).unwrap();
+        match runtime.block_on(future::poll_fn(|| scan.poll())) {
+            Err(Error::InvalidArgument(_)) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("expected ordered scan of too many tablets to fail"),
+        }
+        assert_eq!(0, scan.stats().rpcs());
+    }
+
+    #[test]
+    fn filter_merge() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 100i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "filter_merge",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let key = table.schema().column_by_name("key").unwrap().clone();
+        let builder = table
+            .scan_builder()
+            .predicate(key.greater_equal(18i32).unwrap())
+            .unwrap()
+            .predicate(key.less_than(65i32).unwrap())
+            .unwrap();
+
+        // Both bounds are sent as a single range predicate.
+        let (_, predicates) = builder.projection_and_predicates();
+        assert_eq!(
+            vec![Filter::range(18i32..65).into_pb(&key)],
+            predicates.unwrap()
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| Ok(builder.build())))
+            .unwrap();
+        let batches = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+
+        let mut keys = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!((18..65).collect::<Vec<_>>(), keys);
+    }
+
+    #[test]
+    fn contradictory_filters() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "contradictory_filters",
+            20,
+            Partitioning::Hash(4),
+        );
+
+        let mut scan_empty = |builder: ScanBuilder| {
+            let mut scan: Scan = runtime
+                .block_on(::futures::future::lazy::<_, Result<Scan>>(|| Ok(builder.build())))
+                .unwrap();
+            let batches: Vec<RowBatch> = runtime
+                .block_on(::futures::future::lazy(|| (&mut scan).collect()))
+                .unwrap();
+            assert!(batches.is_empty());
+            assert_eq!(0, scan.stats().rpcs());
+        };
+
+        // key > 10 AND key < 5.
+        let key = table.schema().column_by_name("key").unwrap().clone();
+        scan_empty(
+            table
+                .scan_builder()
+                .predicate(key.greater_than(10i32).unwrap())
+                .unwrap()
+                .predicate(key.less_than(5i32).unwrap())
+                .unwrap(),
+        );
+
+        // key = 3 AND key IN (4, 5).
+        scan_empty(
+            table
+                .scan_builder()
+                .filter("key", Filter::equals(3i32))
+                .unwrap()
+                .filter("key", Filter::in_list(vec![4i32, 5]))
+                .unwrap(),
+        );
+
+        // Inverted primary key bounds.
+        let mut lower = table.schema().new_row();
+        lower.set("key", 10i32).unwrap();
+        let mut upper = table.schema().new_row();
+        upper.set("key", 5i32).unwrap();
+        scan_empty(
+            table
+                .scan_builder()
+                .set_lower_bound_primary_key(&lower)
+                .unwrap()
+                .set_upper_bound_primary_key(&upper)
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn tablet_server_failure_mid_scan() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(
+            MiniClusterConfig::default()
+                .num_tservers(3)
+                .scanner_batch_size_bytes(1024),
+        );
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        // Two tablets, each returned in multiple batches.
+        let num_rows = 4000i32;
+        let mut table_builder = TableBuilder::new("tablet_server_failure_mid_scan", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        let mut split = schema.new_row();
+        split.set(0, num_rows / 2).unwrap();
+        table_builder.add_range_partition_split(split);
+        table_builder.set_num_replicas(3);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        insert_rows(&mut runtime, &table, num_rows, |i, row| {
+            row.set("key", i).unwrap();
+            row.set("val", i.to_string()).unwrap();
+        });
+
+        // Scan one tablet at a time, so that the second tablet is opened after the tablet server
+        // fails, using the replica locations cached before the failure.
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table
+                    .scan_builder()
+                    .set_fault_tolerant(true)
+                    .set_max_concurrent_tablets(1)
+                    .build())
+            })).unwrap();
+
+        let (batch, scan) = runtime
+            .block_on(::futures::future::lazy(|| scan.into_future()))
+            .map_err(|(error, _)| error)
+            .unwrap();
+        let batch = batch.expect("first batch");
+        assert!(batch.num_rows() < num_rows as usize / 2);
+        let mut keys = batch
+            .into_iter()
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+
+        // Stop the tablet server holding the scanner of the partially scanned first tablet. Every
+        // tablet has a replica on each tablet server, so this also forces a leader election for
+        // the tablets it led.
+        let tserver = match scan.tablet_scans.front().map(|active| &active.tablet_scan) {
+            Some(&TabletScan::Continue { ref position, .. }) => position.tserver,
+            _ => panic!("expected the first tablet scan to be open"),
+        };
+        let tablet_servers = runtime.block_on(client.tablet_servers()).unwrap();
+        let rpc_addrs = tablet_servers
+            .iter()
+            .find(|server| server.id() == tserver)
+            .expect("scanned tablet server")
+            .rpc_addrs()
+            .to_owned();
+        let index = cluster
+            .tserver_addrs()
+            .iter()
+            .position(|addr| rpc_addrs.contains(addr))
+            .expect("scanned tablet server index");
+        cluster.stop_tserver(index as u32);
+
+        // The first tablet is reopened at another replica after the last row returned, so every
+        // row is returned exactly once.
+        let batches: Vec<RowBatch> = runtime.block_on(scan.collect()).unwrap();
+        keys.extend(
+            batches
+                .iter()
+                .flat_map(|batch| batch.into_iter())
+                .map(|row| row.get::<_, i32>("key").unwrap()),
+        );
+        keys.sort();
+        assert_eq!((0..num_rows).collect::<Vec<_>>(), keys);
+    }
+
+    #[test]
+    fn table_dropped_mid_scan() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        // Three tablets: (, 10), [10, 20), [20, ).
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "table_dropped_mid_scan",
+            30,
+            Partitioning::Range(vec![10, 20]),
+        );
+
+        // Scan one tablet at a time, so that the first batch holds the entire first tablet.
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().set_max_concurrent_tablets(1).build())
+            })).unwrap();
+        let (batch, scan) = runtime
+            .block_on(::futures::future::lazy(|| scan.into_future()))
+            .map_err(|(error, _)| error)
+            .unwrap();
+        assert_eq!(10, batch.expect("first batch").num_rows());
+
+        runtime
+            .block_on(client.delete_table_by_id(table.id()))
+            .unwrap();
+        // Force the remaining tablets to be looked up from the master.
+        table.table_locations().clear();
+
+        match runtime.block_on(scan.collect()) {
+            Err(Error::NotFound(ref message)) => {
+                assert_eq!("table was dropped during scan", message)
+            }
+            result => panic!("unexpected result: {:?}", result.map(|batches| batches.len())),
+        }
+    }
+
+    #[test]
+    fn continuation_token() {
+        let _ = env_logger::try_init();
+        let mut cluster =
+            MiniCluster::new(MiniClusterConfig::default().scanner_batch_size_bytes(1024));
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        // A single tablet, scanned in multiple batches.
+        let num_rows = 3000i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "continuation_token",
+            num_rows,
+            Partitioning::Range(vec![]),
+        );
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().build())
+            })).unwrap();
+        let (batch, scan) = runtime
+            .block_on(::futures::future::lazy(|| scan.into_future()))
+            .map_err(|(error, _)| error)
+            .unwrap();
+        let batch = batch.expect("first batch");
+        assert!(batch.num_rows() < num_rows as usize);
+        let mut keys = batch
+            .into_iter()
+            .map(|row| row.get::<_, i32>("key").unwrap())
+            .collect::<Vec<_>>();
+
+        // Pause the scan, and resume it from the token.
+        let token = scan.continuation_token().expect("continuation token");
+        drop(scan);
+        let scan = runtime
+            .block_on(future::lazy(|| client.resume_scan(&token)))
+            .unwrap();
+        let batches: Vec<RowBatch> = runtime.block_on(scan.collect()).unwrap();
+        keys.extend(
+            batches
+                .iter()
+                .flat_map(|batch| batch.into_iter())
+                .map(|row| row.get::<_, i32>("key").unwrap()),
+        );
+        keys.sort();
+        assert_eq!((0..num_rows).collect::<Vec<_>>(), keys);
+
+        // A prefetching scan has already requested the next batch, so it can not be paused.
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().set_prefetch(true).build())
+            })).unwrap();
+        let (batch, scan) = runtime
+            .block_on(::futures::future::lazy(|| scan.into_future()))
+            .map_err(|(error, _)| error)
+            .unwrap();
+        assert!(batch.expect("first batch").num_rows() < num_rows as usize);
+        assert!(scan.continuation_token().is_none());
+
+        assert!(
+            runtime
+                .block_on(future::lazy(|| client.resume_scan(b"bogus")))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn target_replica() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(MiniClusterConfig::default().num_tservers(3));
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        // A single tablet with a single replica, so only one tablet server can serve the scan.
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "target_replica",
+            10,
+            Partitioning::Range(vec![]),
+        );
+
+        let tablets = runtime.block_on(table.list_tablets()).unwrap();
+        assert_eq!(1, tablets.len());
+        assert_eq!(1, tablets[0].replicas().len());
+        let replica_server = tablets[0].replicas()[0].id();
+
+        let tablet_servers = runtime.block_on(client.tablet_servers()).unwrap();
+        assert_eq!(3, tablet_servers.len());
+        for tablet_server in tablet_servers {
+            let builder = table
+                .scan_builder()
+                .set_target_replica(&tablet_server.id().to_string())
+                .unwrap();
+            let mut scan: Scan = runtime
+                .block_on(::futures::future::lazy::<_, Result<Scan>>(|| Ok(builder.build())))
+                .unwrap();
+            let result: Result<Vec<RowBatch>> =
+                runtime.block_on(::futures::future::lazy(|| (&mut scan).collect()));
+            if tablet_server.id() == replica_server {
+                let batches = result.unwrap();
+                assert_eq!(10, batches.iter().map(RowBatch::num_rows).sum::<usize>());
+            } else {
+                match result {
+                    Err(Error::NotFound(_)) => (),
+                    Err(error) => panic!("unexpected error: {}", error),
+                    Ok(_) => panic!("expected an error"),
+                }
+                assert_eq!(0, scan.stats().rpcs());
+            }
+        }
+
+        assert!(table.scan_builder().set_target_replica("not-a-uuid").is_err());
+    }
+
+    #[test]
+    fn filter_in_list() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("country", DataType::String).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("filter_in_list", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 4);
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+        let mut writer = table.new_writer(WriterConfig::default());
+
+        let countries = ["US", "CA", "MX", "DE", "FR", "JP"];
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                for i in 0..30i32 {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", i).unwrap();
+                    insert
+                        .set("country", countries[i as usize % countries.len()])
+                        .unwrap();
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        let scan_rows = |runtime: &mut Runtime, builder: ScanBuilder| {
+            let batches: Vec<RowBatch> = runtime
+                .block_on(future::lazy(|| builder.build().collect()))
+                .unwrap();
+            let mut rows = batches
+                .iter()
+                .flat_map(|batch| batch.into_iter())
+                .map(|row| {
+                    (
+                        row.get::<_, i32>("key").unwrap(),
+                        row.get::<_, String>("country").unwrap(),
+                    )
+                }).collect::<Vec<_>>();
+            rows.sort();
+            rows
+        };
+
+        let expected = (0..30i32)
+            .map(|i| (i, countries[i as usize % countries.len()].to_owned()))
+            .filter(|&(_, ref country)| ["US", "CA", "MX"].contains(&&country[..]))
+            .collect::<Vec<_>>();
+
+        let builder = table
+            .scan_builder()
+            .filter_in_list(
+                "country",
+                vec![
+                    Datum::String("US".to_owned()),
+                    Datum::String("CA".to_owned()),
+                    Datum::String("MX".to_owned()),
+                    Datum::Null,
+                ],
+            ).unwrap();
+        assert_eq!(expected, scan_rows(&mut runtime, builder));
+
+        let builder = table
+            .scan_builder()
+            .filter("country", Filter::in_list(vec!["MX", "US", "CA", "US"]))
+            .unwrap();
+        assert_eq!(expected, scan_rows(&mut runtime, builder));
+
+        // Combined with a key IN list.
+        let builder = table
+            .scan_builder()
+            .filter_in_list("country", vec![Datum::String("US".to_owned())])
+            .unwrap()
+            .filter("key", Filter::in_list(vec![0i32, 1, 6, 7]))
+            .unwrap();
+        assert_eq!(
+            vec![(0, "US".to_owned()), (6, "US".to_owned())],
+            scan_rows(&mut runtime, builder)
+        );
+
+        // Values must match the column type.
+        assert!(
+            table
+                .scan_builder()
+                .filter_in_list("country", vec![Datum::Int32(1)])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn null_values() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("null_values", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 4);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        let num_rows = 10i32;
+
+        // Insert a null value for every odd key.
+        insert_rows(&mut runtime, &table, num_rows, |i, row| {
+            row.set("key", i).unwrap();
+            if i % 2 == 0 {
+                row.set("val", i).unwrap();
+            } else {
+                row.set_null("val").unwrap();
+            }
+        });
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().build())
+            })).unwrap();
+
+        let batches = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+
+        let mut rows = Vec::new();
+        for batch in batches {
+            for row in batch.into_iter() {
+                assert_eq!(
+                    row.get::<_, i32>("key").unwrap() % 2 == 1,
+                    row.is_null("val").unwrap()
+                );
+                rows.push((
+                    row.get::<_, i32>("key").unwrap(),
+                    row.get::<_, Option<i32>>("val").unwrap(),
+                ));
+            }
+        }
+
+        rows.sort();
+
+        let expected = (0..num_rows)
+            .map(|i| (i, if i % 2 == 0 { Some(i) } else { None }))
+            .collect::<Vec<_>>();
+
+        assert_eq!(rows, expected);
+    }
 
     #[test]
-    fn count() {
+    fn diff_scan() {
         let _ = env_logger::try_init();
         let mut cluster = MiniCluster::default();
         let mut runtime = Runtime::new().unwrap();
@@ -553,60 +4648,103 @@ mod test {
 
         let schema = SchemaBuilder::new()
             .add_column(Column::new("key", DataType::Int32).set_not_null())
-            .add_column(Column::new("val", DataType::Int32))
+            .add_column(Column::new("val", DataType::Int32).set_not_null())
             .set_primary_key(vec!["key"])
             .build()
             .unwrap();
 
-        let mut table_builder = TableBuilder::new("count", schema.clone());
-        table_builder.add_hash_partitions(vec!["key"], 4);
+        let mut table_builder = TableBuilder::new("diff_scan", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
         table_builder.set_num_replicas(1);
 
-        // TODO: don't wait for table creation in order to test retry logic.
         let table_id = runtime
             .block_on(client.create_table(table_builder))
             .unwrap();
-
         let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
-
         let mut writer = table.new_writer(WriterConfig::default());
 
-        let num_rows = 100i32;
-
-        // TODO: remove lazy once apply no longer polls.
         runtime
             .block_on(future::lazy::<_, Result<()>>(|| {
-                // Insert a bunch of values
-                for i in 0..num_rows {
+                for i in 0..10i32 {
                     let mut insert = table.schema().new_row();
-                    insert.set("key", i).unwrap();
-                    insert.set("val", i).unwrap();
+                    insert.set("key", i).unwrap().set("val", i).unwrap();
                     writer.insert(insert);
                 }
                 Ok(())
             })).unwrap();
-
         runtime
             .block_on(future::poll_fn(|| writer.poll_flush()))
             .unwrap();
 
-        let scan: Scan = runtime
-            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
-                Ok(table.scan_builder().count().build())
+        let start = HybridTime::from_system_time(SystemTime::now()).unwrap();
+
+        runtime
+            .block_on(future::lazy::<_, Result<()>>(|| {
+                for i in 1..3i32 {
+                    let mut update = table.schema().new_row();
+                    update.set("key", i).unwrap().set("val", i * 100).unwrap();
+                    writer.update(update);
+                }
+                let mut delete = table.schema().new_row();
+                delete.set("key", 3i32).unwrap();
+                writer.delete(delete);
+                let mut insert = table.schema().new_row();
+                insert.set("key", 10i32).unwrap().set("val", 10i32).unwrap();
+                writer.insert(insert);
+                Ok(())
             })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        let end = HybridTime::from_system_time(SystemTime::now()).unwrap();
+
+        // Scan tokens don't carry the diff scan timestamps, so they can't be built.
+        let builder = table.scan_builder().set_diff_scan(start, end);
+        match runtime.block_on(future::lazy(|| builder.clone().build_scan_tokens())) {
+            Err(Error::InvalidArgument(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
 
         let batches: Vec<RowBatch> = runtime
-            .block_on(::futures::future::lazy(|| scan.collect()))
+            .block_on(future::lazy(|| builder.build().collect()))
             .unwrap();
+        let mut rows = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| {
+                let key = row.get::<_, i32>("key").unwrap();
+                let is_deleted = row.is_deleted().unwrap();
+                let val = if is_deleted {
+                    None
+                } else {
+                    Some(row.get::<_, i32>("val").unwrap())
+                };
+                (key, val, is_deleted)
+            }).collect::<Vec<_>>();
+        rows.sort();
 
         assert_eq!(
-            num_rows as usize,
-            batches.into_iter().map(|batch| batch.len).sum()
+            vec![
+                (1, Some(100), false),
+                (2, Some(200), false),
+                (3, None, true),
+                (10, Some(10), false),
+            ],
+            rows
         );
+
+        // Regular scans don't project the IS_DELETED column.
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        for row in batches.iter().flat_map(|batch| batch.into_iter()) {
+            assert_eq!(None, row.is_deleted());
+        }
     }
 
     #[test]
-    fn select() {
+    fn project_is_deleted() {
         let _ = env_logger::try_init();
         let mut cluster = MiniCluster::default();
         let mut runtime = Runtime::new().unwrap();
@@ -615,18 +4753,19 @@ mod test {
             .block_on(Client::new(cluster.master_addrs(), Options::default()))
             .expect("client");
 
+        // The table has a column named is_deleted, which the virtual column must not collide
+        // with.
         let schema = SchemaBuilder::new()
             .add_column(Column::new("key", DataType::Int32).set_not_null())
-            .add_column(Column::new("val", DataType::Int32))
+            .add_column(Column::new("is_deleted", DataType::Int32))
             .set_primary_key(vec!["key"])
             .build()
             .unwrap();
 
-        let mut table_builder = TableBuilder::new("count", schema.clone());
-        table_builder.add_hash_partitions(vec!["key"], 4);
+        let mut table_builder = TableBuilder::new("project_is_deleted", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
         table_builder.set_num_replicas(1);
 
-        // TODO: don't wait for table creation in order to test retry logic.
         let table_id = runtime
             .block_on(client.create_table(table_builder))
             .unwrap();
@@ -634,14 +4773,11 @@ mod test {
         let mut writer = table.new_writer(WriterConfig::default());
         let num_rows = 10i32;
 
-        // TODO: remove lazy once apply no longer polls.
         runtime
             .block_on(future::lazy::<_, Result<()>>(|| {
-                // Insert a bunch of values
                 for i in 0..num_rows {
                     let mut insert = table.schema().new_row();
-                    insert.set("key", i).unwrap();
-                    insert.set("val", i).unwrap();
+                    insert.set("key", i).unwrap().set("is_deleted", i).unwrap();
                     writer.insert(insert);
                 }
                 Ok(())
@@ -650,34 +4786,31 @@ mod test {
             .block_on(future::poll_fn(|| writer.poll_flush()))
             .unwrap();
 
-        let scan: Scan = runtime
-            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
-                Ok(table.scan_builder().build())
-            })).unwrap();
+        let scan = table.scan_builder().project_is_deleted().build();
+        assert_eq!(3, scan.projected_schema().columns().len());
+        assert_eq!(
+            "_is_deleted",
+            scan.projected_schema().columns()[2].name()
+        );
 
-        let batches = runtime
-            .block_on(::futures::future::lazy(|| scan.collect()))
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| scan.collect()))
             .unwrap();
-
-        let mut rows = Vec::new();
-        for batch in batches {
-            for row in batch.into_iter() {
-                rows.push((
-                    row.get::<_, i32>("key").unwrap(),
-                    row.get::<_, i32>("val").unwrap(),
-                ));
-            }
+        let mut keys = Vec::new();
+        for row in batches.iter().flat_map(|batch| batch.into_iter()) {
+            assert_eq!(Some(false), row.is_deleted());
+            assert_eq!(
+                row.get::<_, i32>("key").unwrap(),
+                row.get::<_, i32>("is_deleted").unwrap()
+            );
+            keys.push(row.get::<_, i32>("key").unwrap());
         }
-
-        rows.sort();
-
-        let expected = (0..num_rows).map(|i| (i, i)).collect::<Vec<_>>();
-
-        assert_eq!(rows, expected);
+        keys.sort();
+        assert_eq!((0..num_rows).collect::<Vec<_>>(), keys);
     }
 
     #[test]
-    fn filter() {
+    fn filter_is_null() {
         let _ = env_logger::try_init();
         let mut cluster = MiniCluster::default();
         let mut runtime = Runtime::new().unwrap();
@@ -688,31 +4821,33 @@ mod test {
 
         let schema = SchemaBuilder::new()
             .add_column(Column::new("key", DataType::Int32).set_not_null())
-            .add_column(Column::new("val", DataType::Int32))
+            .add_column(Column::new("val", DataType::String))
             .set_primary_key(vec!["key"])
             .build()
             .unwrap();
 
-        let mut table_builder = TableBuilder::new("count", schema.clone());
+        let mut table_builder = TableBuilder::new("filter_is_null", schema.clone());
         table_builder.add_hash_partitions(vec!["key"], 4);
         table_builder.set_num_replicas(1);
 
-        // TODO: don't wait for table creation in order to test retry logic.
         let table_id = runtime
             .block_on(client.create_table(table_builder))
             .unwrap();
         let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
         let mut writer = table.new_writer(WriterConfig::default());
-        let num_rows = 10i32;
+        let num_rows = 20i32;
 
-        // TODO: remove lazy once apply no longer polls.
         runtime
             .block_on(future::lazy::<_, Result<()>>(|| {
-                // Insert a bunch of values
+                // Insert a null value for every key divisible by three.
                 for i in 0..num_rows {
                     let mut insert = table.schema().new_row();
                     insert.set("key", i).unwrap();
-                    insert.set("val", i).unwrap();
+                    if i % 3 == 0 {
+                        insert.set_null("val").unwrap();
+                    } else {
+                        insert.set("val", i.to_string()).unwrap();
+                    }
                     writer.insert(insert);
                 }
                 Ok(())
@@ -721,35 +4856,400 @@ mod test {
             .block_on(future::poll_fn(|| writer.poll_flush()))
             .unwrap();
 
+        let scan_keys = |runtime: &mut Runtime, filter: Filter| {
+            let builder = table.scan_builder().filter("val", filter).unwrap();
+            let batches: Vec<RowBatch> = runtime
+                .block_on(future::lazy(|| builder.build().collect()))
+                .unwrap();
+            let mut keys = batches
+                .iter()
+                .flat_map(|batch| batch.into_iter())
+                .map(|row| row.get::<_, i32>("key").unwrap())
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(
+            (0..num_rows).filter(|i| i % 3 == 0).collect::<Vec<_>>(),
+            scan_keys(&mut runtime, Filter::is_null())
+        );
+        assert_eq!(
+            (0..num_rows).filter(|i| i % 3 != 0).collect::<Vec<_>>(),
+            scan_keys(&mut runtime, Filter::is_not_null())
+        );
+
+        // IS NULL can never match a non-nullable column.
+        match table.scan_builder().filter("key", Filter::is_null()) {
+            Err(Error::InvalidArgument(_)) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("expected IS NULL filter on a non-nullable column to fail"),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn to_json() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("timestamp", DataType::Timestamp))
+            .add_column(Column::new("binary", DataType::Binary))
+            .add_column(Column::new("string", DataType::String))
+            .add_column(Column::new("double", DataType::Double))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("to_json", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        insert_rows(&mut runtime, &table, 1, |_, row| {
+            row.set("key", 1).unwrap();
+            row.set("timestamp", UNIX_EPOCH + Duration::from_micros(1_514_768_523_456_789))
+                .unwrap();
+            row.set("binary", &b"\x00\x01\x02"[..]).unwrap();
+            row.set("string", "foo").unwrap();
+            row.set_null("double").unwrap();
+        });
+
         let scan: Scan = runtime
             .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
-                Ok(table
-                    .scan_builder()
-                    .filter("key", Filter::range(5i32..))?
-                    .build())
+                Ok(table.scan_builder().build())
             })).unwrap();
-
         let batches = runtime
             .block_on(::futures::future::lazy(|| scan.collect()))
             .unwrap();
 
-        let mut rows = Vec::new();
-        for batch in batches {
-            for row in batch.into_iter() {
-                rows.push((
-                    row.get::<_, i32>("key").unwrap(),
-                    row.get::<_, i32>("val").unwrap(),
-                ));
+        let rows = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter().map(|row| row.to_json()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            vec![json!({
+                "key": 1,
+                "timestamp": "2018-01-01T01:02:03.456789Z",
+                "binary": "AAEC",
+                "string": "foo",
+                "double": null,
+            })],
+            rows
+        );
+    }
+
+    #[test]
+    fn fmt_table() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String))
+            .add_column(Column::new("bin", DataType::Binary))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        // A single tablet, so that rows are returned in primary key order.
+        let mut table_builder = TableBuilder::new("fmt_table", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        insert_rows(&mut runtime, &table, 2, |i, row| {
+            row.set("key", i + 1).unwrap();
+            if i == 0 {
+                row.set("val", "one").unwrap();
+                row.set("bin", &b"\x00\xff"[..]).unwrap();
+            } else {
+                row.set_null("val").unwrap();
+                row.set("bin", vec![0xab; 20]).unwrap();
+            }
+        });
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(::futures::future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        let batch = batches.iter().find(|batch| batch.num_rows() > 0).unwrap();
+        assert_eq!(2, batch.num_rows());
+
+        let mut rendered = String::new();
+        batch.fmt_table(&mut rendered).unwrap();
+
+        let expected = "+-----+------+---------------------------------------+\n\
+                        | key | val  | bin                                   |\n\
+                        +-----+------+---------------------------------------+\n\
+                        | 1   | one  | 0x00ff                                |\n\
+                        | 2   | NULL | 0xabababababababababababababababab... |\n\
+                        +-----+------+---------------------------------------+\n";
+        assert_eq!(expected, rendered);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn write_csv() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("timestamp", DataType::Timestamp))
+            .add_column(Column::new("binary", DataType::Binary))
+            .add_column(Column::new("string, quoted", DataType::String))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        // A single tablet, so that rows are returned in primary key order.
+        let mut table_builder = TableBuilder::new("write_csv", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+        insert_rows(&mut runtime, &table, 3, |i, row| {
+            row.set("key", i + 1).unwrap();
+            match i {
+                0 => {
+                    row.set("timestamp", UNIX_EPOCH + Duration::from_micros(1_514_768_523_456_789))
+                        .unwrap();
+                    row.set("binary", &b"\x00\xff"[..]).unwrap();
+                    row.set("string, quoted", "say \"hi\", bye").unwrap();
+                }
+                1 => {
+                    row.set_null("timestamp").unwrap();
+                    row.set_null("binary").unwrap();
+                    row.set("string, quoted", "").unwrap();
+                }
+                _ => {
+                    row.set_null("timestamp").unwrap();
+                    row.set("binary", &b""[..]).unwrap();
+                    row.set_null("string, quoted").unwrap();
+                }
             }
+        });
+
+        let scan: Scan = runtime
+            .block_on(::futures::future::lazy::<_, Result<Scan>>(|| {
+                Ok(table.scan_builder().build())
+            })).unwrap();
+        let batches = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+
+        let mut csv = Vec::new();
+        for (idx, batch) in batches.iter().enumerate() {
+            batch.write_csv(&mut csv, idx == 0).unwrap();
+        }
+
+        let expected = "key,timestamp,binary,\"string, quoted\"\n\
+                        1,2018-01-01T01:02:03.456789Z,0x00ff,\"say \"\"hi\"\", bye\"\n\
+                        2,,,\"\"\n\
+                        3,,0x,\n";
+        assert_eq!(expected, String::from_utf8(csv).unwrap());
+
+        let mut csv = Vec::new();
+        for batch in &batches {
+            batch
+                .write_csv_with_timestamp_format(&mut csv, false, TimestampFormat::EpochMicros)
+                .unwrap();
         }
+        assert!(
+            String::from_utf8(csv)
+                .unwrap()
+                .starts_with("1,1514768523456789,0x00ff,")
+        );
+    }
+
+    #[test]
+    fn scan_tokens() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let num_rows = 100i32;
+        let table = create_table_with_rows(
+            &mut runtime,
+            &mut client,
+            "scan_tokens",
+            num_rows,
+            Partitioning::Hash(4),
+        );
+
+        let collect_rows = |batches: Vec<RowBatch>, rows: &mut Vec<(i32, i32)>| {
+            for batch in batches {
+                for row in batch.into_iter() {
+                    rows.push((
+                        row.get::<_, i32>("key").unwrap(),
+                        row.get::<_, i32>("val").unwrap(),
+                    ));
+                }
+            }
+        };
+
+        let scan_builder = table
+            .scan_builder()
+            .filter("key", Filter::range(10i32..90))
+            .unwrap();
+
+        let scan = scan_builder.clone().build();
+        let batches = runtime
+            .block_on(::futures::future::lazy(|| scan.collect()))
+            .unwrap();
+        let mut expected = Vec::new();
+        collect_rows(batches, &mut expected);
+        expected.sort();
+        assert_eq!(80, expected.len());
 
+        let tokens = runtime
+            .block_on(::futures::future::lazy(|| {
+                scan_builder.build_scan_tokens()
+            })).unwrap();
+        assert_eq!(4, tokens.len());
+
+        let mut rows = Vec::new();
+        for token in tokens {
+            let serialized = token.serialize();
+            assert_eq!(token, ScanToken::deserialize(&serialized).unwrap());
+
+            let scan = runtime
+                .block_on(client.scan_from_token(&serialized))
+                .unwrap();
+            let batches = runtime
+                .block_on(::futures::future::lazy(|| scan.collect()))
+                .unwrap();
+            collect_rows(batches, &mut rows);
+        }
         rows.sort();
 
-        let expected = (0..num_rows)
-            .filter(|&i| i >= 5)
-            .map(|i| (i, i))
-            .collect::<Vec<_>>();
+        assert_eq!(expected, rows);
 
-        assert_eq!(rows, expected);
+        assert!(client.scan_from_token(b"bogus").wait().is_err());
+
+        // Tokens identify their table by ID, so a token of a dropped table does not scan a new
+        // table which has taken its name.
+        let token = runtime
+            .block_on(::futures::future::lazy(|| {
+                table.scan_builder().build_scan_tokens()
+            })).unwrap()
+            .remove(0)
+            .serialize();
+        runtime.block_on(client.delete_table_by_id(table.id())).unwrap();
+        let mut table_builder = TableBuilder::new("scan_tokens", table.schema().clone());
+        table_builder.add_hash_partitions(vec!["key"], 4);
+        table_builder.set_num_replicas(1);
+        runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        match runtime.block_on(client.scan_from_token(&token)) {
+            Err(Error::NotFound(_)) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("scan token scanned a different table"),
+        }
+    }
+
+    #[test]
+    fn aggregate() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32).set_nullable())
+            .add_column(Column::new("name", DataType::String).set_nullable())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("aggregate", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 4);
+        table_builder.set_num_replicas(1);
+
+        let table = create_table(&mut runtime, &mut client, table_builder);
+
+        // Every tenth value is null, and is excluded from the sum, min, and max.
+        let mut sum = 0i64;
+        insert_rows(&mut runtime, &table, 100, |i, row| {
+            row.set("key", i).unwrap();
+            if i % 10 == 0 {
+                row.set_null("val").unwrap();
+            } else {
+                row.set("val", i * 1000).unwrap();
+                sum += i64::from(i * 1000);
+            }
+            row.set("name", format!("{:03}", i)).unwrap();
+        });
+
+        let result = runtime
+            .block_on(::futures::future::lazy(|| {
+                let spec = AggSpec::new("val").sum().min().max();
+                table.scan_builder().build().aggregate(spec)
+            })).unwrap();
+        assert_eq!(100, result.count());
+        assert_eq!(Some(&Datum::Int64(sum)), result.sum());
+        assert_eq!(Some(&Datum::Int32(1000)), result.min());
+        assert_eq!(Some(&Datum::Int32(99_000)), result.max());
+
+        // Only the requested aggregates are computed.
+        let result = runtime
+            .block_on(::futures::future::lazy(|| {
+                let spec = AggSpec::new("name").max();
+                table.scan_builder().build().aggregate(spec)
+            })).unwrap();
+        assert_eq!(100, result.count());
+        assert_eq!(None, result.sum());
+        assert_eq!(None, result.min());
+        assert_eq!(Some(&Datum::String("099".to_owned())), result.max());
+
+        // Strings can not be summed.
+        runtime
+            .block_on(::futures::future::lazy(|| {
+                let spec = AggSpec::new("name").sum();
+                table.scan_builder().build().aggregate(spec)
+            })).unwrap_err();
+
+        // The column must be projected.
+        runtime
+            .block_on(::futures::future::lazy(|| {
+                let spec = AggSpec::new("val").max();
+                table
+                    .scan_builder()
+                    .select(vec!["key"])
+                    .unwrap()
+                    .build()
+                    .aggregate(spec)
+            })).unwrap_err();
     }
 }
@@ -1,16 +1,21 @@
 use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
+use std::ops::Bound;
 use std::sync::Arc;
 
 use bitmap;
-use pb::{ColumnSchemaPb, SchemaPb};
+use key;
+use pb::{ColumnSchemaPb, ColumnTypeAttributesPb, SchemaPb};
 use CompressionType;
 use DataType;
 use EncodingType;
 use Error;
+use Filter;
+use Predicate;
 use Result;
 use Row;
+use Value;
 
 /// `Column` instances hold schema and metadata about a column in a Kudu table.
 ///
@@ -21,9 +26,12 @@ pub struct Column {
     name: String,
     data_type: DataType,
     is_nullable: bool,
+    is_immutable: bool,
     compression: CompressionType,
     encoding: EncodingType,
     cfile_block_size: u32,
+    comment: String,
+    length: Option<u16>,
 }
 
 impl Column {
@@ -39,6 +47,11 @@ impl Column {
         self.is_nullable
     }
 
+    /// Returns `true` if the column's values can not be updated once the row is inserted.
+    pub fn is_immutable(&self) -> bool {
+        self.is_immutable
+    }
+
     pub fn encoding(&self) -> EncodingType {
         self.encoding
     }
@@ -55,6 +68,26 @@ impl Column {
         }
     }
 
+    /// Returns the maximum length of the column's values in characters, if the column is a
+    /// `VARCHAR` column.
+    pub fn length(&self) -> Option<u16> {
+        self.length
+    }
+
+    /// Returns the column's type as encoded in `ColumnSchemaPb`. `String` columns with a length
+    /// are `VARCHAR` columns.
+    pub(crate) fn type_pb(&self) -> i32 {
+        match self.length {
+            Some(_) if self.data_type == DataType::String => ::pb::DataType::Varchar as i32,
+            _ => self.data_type.to_pb(),
+        }
+    }
+
+    /// Returns the column's comment, or an empty string if the column has no comment.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
     /// Returns a new column.
     pub fn new<S>(name: S, data_type: DataType) -> Column
     where
@@ -64,9 +97,12 @@ impl Column {
             name: name.into(),
             data_type,
             is_nullable: true,
+            is_immutable: false,
             compression: CompressionType::Default,
             encoding: EncodingType::Auto,
             cfile_block_size: 0,
+            comment: String::new(),
+            length: None,
         }
     }
 
@@ -95,32 +131,172 @@ impl Column {
         self
     }
 
+    /// Sets whether the column is immutable. Updates which set an immutable column are rejected,
+    /// as are upserts which set it on an existing row, so rows are given their immutable values
+    /// with inserts or upserts of new rows. Requires Kudu 1.17 or later.
+    pub fn set_immutable(mut self, is_immutable: bool) -> Column {
+        self.is_immutable = is_immutable;
+        self
+    }
+
     pub fn set_cfile_block_size(mut self, cfile_block_size: u32) -> Column {
         self.cfile_block_size = cfile_block_size;
         self
     }
 
+    /// Sets the maximum length of the column's values in characters, making the column a
+    /// `VARCHAR` column. Only `String` columns may have a length. Requires Kudu 1.12 or later.
+    pub fn set_length(mut self, length: u16) -> Column {
+        self.length = Some(length);
+        self
+    }
+
+    /// Sets the column's comment. An empty comment clears the comment.
+    pub fn set_comment<S>(mut self, comment: S) -> Column
+    where
+        S: Into<String>,
+    {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Returns a predicate matching rows where the column is equal to `value`.
+    ///
+    /// Returns an error if the value's type does not match the column's type, or the value is
+    /// null.
+    pub fn equal<V>(&self, value: V) -> Result<Predicate>
+    where
+        for<'data> V: Value<'data>,
+    {
+        self.check_literal(&value)?;
+        Ok(Predicate::new(self.name.clone(), Filter::equals(value)))
+    }
+
+    /// Returns a predicate matching rows where the column is greater than `value`.
+    ///
+    /// Returns an error if the value's type does not match the column's type, or the value is
+    /// null.
+    pub fn greater_than<'data, V>(&self, value: V) -> Result<Predicate>
+    where
+        V: Value<'data>,
+    {
+        self.range_predicate((Bound::Excluded(value), Bound::Unbounded))
+    }
+
+    /// Returns a predicate matching rows where the column is greater than or equal to `value`.
+    ///
+    /// Returns an error if the value's type does not match the column's type, or the value is
+    /// null.
+    pub fn greater_equal<'data, V>(&self, value: V) -> Result<Predicate>
+    where
+        V: Value<'data>,
+    {
+        self.range_predicate((Bound::Included(value), Bound::Unbounded))
+    }
+
+    /// Returns a predicate matching rows where the column is less than `value`.
+    ///
+    /// Returns an error if the value's type does not match the column's type, or the value is
+    /// null.
+    pub fn less_than<'data, V>(&self, value: V) -> Result<Predicate>
+    where
+        V: Value<'data>,
+    {
+        self.range_predicate((Bound::Unbounded, Bound::Excluded(value)))
+    }
+
+    /// Returns a predicate matching rows where the column is less than or equal to `value`.
+    ///
+    /// Returns an error if the value's type does not match the column's type, or the value is
+    /// null.
+    pub fn less_equal<'data, V>(&self, value: V) -> Result<Predicate>
+    where
+        V: Value<'data>,
+    {
+        self.range_predicate((Bound::Unbounded, Bound::Included(value)))
+    }
+
+    fn range_predicate<'data, V>(&self, bounds: (Bound<V>, Bound<V>)) -> Result<Predicate>
+    where
+        V: Value<'data>,
+    {
+        match bounds {
+            (Bound::Included(ref value), _)
+            | (Bound::Excluded(ref value), _)
+            | (_, Bound::Included(ref value))
+            | (_, Bound::Excluded(ref value)) => self.check_literal(value)?,
+            (Bound::Unbounded, Bound::Unbounded) => (),
+        }
+        Ok(Predicate::new(self.name.clone(), Filter::range(bounds)))
+    }
+
+    /// Checks that a predicate literal is a non-null value of the column's type.
+    ///
+    /// The check is done against the literal rather than the resulting filter, since filters may
+    /// simplify to a form which no longer carries a type, e.g. `>= i32::MIN` to `IS NOT NULL`.
+    fn check_literal<'data, V>(&self, value: &V) -> Result<()>
+    where
+        V: Value<'data>,
+    {
+        if !V::can_read_from(self.data_type) {
+            return Err(Error::InvalidArgument(format!(
+                "literal of type {:?} is invalid for column {} of type {:?}",
+                V::DATA_TYPE,
+                self.name,
+                self.data_type
+            )));
+        }
+        if !value.is_comparable() {
+            return Err(Error::InvalidArgument(format!(
+                "null literal is invalid in a comparison with column {}",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
     pub(crate) fn into_pb(self, is_key: bool) -> ColumnSchemaPb {
         ColumnSchemaPb {
             name: self.name,
-            type_: self.data_type.to_pb(),
+            type_: self.type_pb(),
+            type_attributes: self.length.map(|length| ColumnTypeAttributesPb {
+                length: Some(i32::from(length)),
+                ..Default::default()
+            }),
             is_nullable: Some(self.is_nullable),
+            immutable: Some(self.is_immutable),
             is_key: Some(is_key),
             encoding: Some(self.encoding.to_pb()),
             compression: Some(self.compression.to_pb()),
             // TODO: checked cast.
             cfile_block_size: Some(self.cfile_block_size as i32),
+            comment: if self.comment.is_empty() {
+                None
+            } else {
+                Some(self.comment)
+            },
             ..Default::default()
         }
     }
 
     pub(crate) fn from_pb(pb: ColumnSchemaPb) -> Result<Column> {
+        let length = if pb.type_() == ::pb::DataType::Varchar {
+            pb.type_attributes
+                .as_ref()
+                .and_then(|attributes| attributes.length)
+                .map(|length| length as u16)
+        } else {
+            None
+        };
         Ok(Column {
+            length,
             is_nullable: pb.is_nullable(),
+            is_immutable: pb.immutable(),
             data_type: DataType::from_pb(pb.type_())?,
             compression: CompressionType::from_pb(pb.compression())?,
             encoding: EncodingType::from_pb(pb.encoding())?,
             cfile_block_size: pb.cfile_block_size() as u32,
+            comment: pb.comment.unwrap_or_default(),
             name: pb.name,
         })
     }
@@ -129,9 +305,15 @@ impl Column {
 impl fmt::Debug for Column {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?} {:?}", self.name, self.data_type)?;
+        if let Some(length) = self.length {
+            write!(f, " LENGTH {}", length)?;
+        }
         if !self.is_nullable {
             write!(f, " NOT NULL")?;
         }
+        if self.is_immutable {
+            write!(f, " IMMUTABLE")?;
+        }
         if self.encoding != EncodingType::Auto {
             write!(f, " ENCODING {:?}", self.encoding)?;
         }
@@ -141,6 +323,23 @@ impl fmt::Debug for Column {
         if let Some(cfile_block_size) = self.cfile_block_size() {
             write!(f, " CFILE BLOCK SIZE {}", cfile_block_size)?;
         }
+        if !self.comment.is_empty() {
+            write!(f, " COMMENT {:?}", self.comment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats the column as a SQL-style column definition, e.g. `val INT32 NOT NULL`.
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.length {
+            Some(length) => write!(f, "{} VARCHAR({})", self.name, length)?,
+            None => write!(f, "{} {}", self.name, self.data_type)?,
+        }
+        if !self.is_nullable {
+            f.write_str(" NOT NULL")?;
+        }
         Ok(())
     }
 }
@@ -154,10 +353,16 @@ struct Inner {
     // TODO: replace this with a bitset containing indices of var len columns
     var_len_column_offsets: Vec<usize>,
     num_primary_key_columns: usize,
+    /// Indexes of the primary key columns, which always lead the schema.
+    primary_key_indexes: Vec<usize>,
 
     /// Length of the encoded columns.
     row_len: usize,
     has_nullable_columns: bool,
+
+    /// Index of the `IS_DELETED` virtual column, if the schema is a scan projection which
+    /// includes it.
+    is_deleted_column: Option<usize>,
 }
 
 pub trait ColumnSelector {
@@ -186,6 +391,27 @@ pub struct Schema {
 
 impl Schema {
     pub(crate) fn new(columns: Vec<Column>, num_primary_key_columns: usize) -> Schema {
+        Schema::with_is_deleted_column(columns, num_primary_key_columns, None)
+    }
+
+    /// Creates a scan projection schema from the projected columns, with a trailing `IS_DELETED`
+    /// virtual column. The virtual column is named `is_deleted`, prefixed with underscores as
+    /// necessary to avoid colliding with the projected columns.
+    pub(crate) fn projection_with_is_deleted(mut columns: Vec<Column>) -> Schema {
+        let mut name = "is_deleted".to_string();
+        while columns.iter().any(|column| column.name() == name) {
+            name.insert(0, '_');
+        }
+        let idx = columns.len();
+        columns.push(Column::new(name, DataType::Bool).set_not_null());
+        Schema::with_is_deleted_column(columns, 0, Some(idx))
+    }
+
+    fn with_is_deleted_column(
+        columns: Vec<Column>,
+        num_primary_key_columns: usize,
+        is_deleted_column: Option<usize>,
+    ) -> Schema {
         let mut columns_by_name = HashMap::with_capacity(columns.len());
         let mut column_offsets = Vec::with_capacity(columns.len());
         let mut var_len_column_offsets = Vec::new();
@@ -208,32 +434,52 @@ impl Schema {
                 column_offsets,
                 var_len_column_offsets,
                 num_primary_key_columns,
+                primary_key_indexes: (0..num_primary_key_columns).collect(),
                 row_len,
                 has_nullable_columns,
+                is_deleted_column,
             }),
         }
     }
 
+    /// Returns the columns, in schema order.
     pub fn columns(&self) -> &[Column] {
         &self.inner.columns
     }
 
+    /// Returns the column at the index, or `None` if the index is out of range. Callers which
+    /// have already validated the index may index into `Schema::columns` instead.
     pub fn column(&self, index: usize) -> Option<&Column> {
         self.inner.columns.get(index)
     }
 
+    /// Returns the column with the name, or `None` if the schema has no such column.
     pub fn column_by_name(&self, name: &str) -> Option<&Column> {
         self.column_index(name).map(|idx| &self.inner.columns[idx])
     }
 
+    /// Returns the index of the column with the name, or `None` if the schema has no such column.
     pub fn column_index(&self, name: &str) -> Option<usize> {
         self.inner.columns_by_name.get(name).cloned()
     }
 
+    /// Returns whether the column at the index is nullable.
+    ///
+    /// Returns an error if the index is out of range.
+    pub fn is_nullable(&self, idx: usize) -> Result<bool> {
+        self.check_index(idx)?;
+        Ok(self.inner.columns[idx].is_nullable())
+    }
+
     pub fn primary_key(&self) -> &[Column] {
         &self.inner.columns[0..self.inner.num_primary_key_columns]
     }
 
+    /// Returns the indexes of the primary key columns.
+    pub fn primary_key_indexes(&self) -> &[usize] {
+        &self.inner.primary_key_indexes
+    }
+
     pub(crate) fn num_primary_key_columns(&self) -> usize {
         self.inner.num_primary_key_columns
     }
@@ -248,14 +494,22 @@ impl Schema {
     pub(crate) fn check_index(&self, idx: usize) -> Result<()> {
         if idx >= self.columns().len() {
             Err(Error::InvalidArgument(format!(
-                "index {} is invalid for schema {:?}",
-                idx, self
+                "column index {} is out of range for schema with {} columns",
+                idx,
+                self.columns().len()
             )))
         } else {
             Ok(())
         }
     }
 
+    /// Returns the index of the `IS_DELETED` virtual column, if the schema is a scan projection
+    /// which includes it.
+    #[inline]
+    pub(crate) fn is_deleted_column(&self) -> Option<usize> {
+        self.inner.is_deleted_column
+    }
+
     #[inline]
     pub(crate) fn bitmap_len(&self) -> usize {
         bitmap::len(self.inner.columns.len())
@@ -266,6 +520,24 @@ impl Schema {
         self.inner.row_len
     }
 
+    /// Returns the size in bytes of a row in the results of a scan projecting the columns at the
+    /// indexes, including the null bitmap if any projected column is nullable.
+    ///
+    /// String and binary columns contribute a 16 byte slot holding the offset and length of the
+    /// value; the value itself is held in the batch's indirect data, which is not included.
+    ///
+    /// Panics if an index is out of range.
+    pub fn projected_row_size(&self, projected: &[usize]) -> usize {
+        let mut row_len = 0;
+        let mut has_nullable_columns = false;
+        for &idx in projected {
+            let column = &self.inner.columns[idx];
+            row_len += column.data_type().size();
+            has_nullable_columns |= column.is_nullable();
+        }
+        row_len + has_nullable_columns as usize * bitmap::len(projected.len())
+    }
+
     #[inline]
     pub fn has_nullable_columns(&self) -> bool {
         self.inner.has_nullable_columns
@@ -290,6 +562,18 @@ impl Schema {
         Row::partial(self.clone())
     }
 
+    /// Decodes an encoded range partition key into a row, for example in order to display a
+    /// tablet's split points.
+    ///
+    /// The key must be the range portion of a partition key (i.e. without hash bucket prefixes),
+    /// with the range partition columns being the primary key columns, which is the default. The
+    /// key may be a prefix of a full primary key, in which case only the prefix columns are set in
+    /// the returned row. An empty key, which represents an unbounded range, decodes to an empty
+    /// row.
+    pub fn decode_partition_key(&self, key: &[u8]) -> Result<Row<'static>> {
+        key::decode_primary_key(self, key)
+    }
+
     pub fn ref_eq(&self, other: &Schema) -> bool {
         let this: *const Inner = &*self.inner;
         let that: *const Inner = &*other.inner;
@@ -357,6 +641,25 @@ impl fmt::Debug for Schema {
     }
 }
 
+/// Formats the schema as a SQL-style table definition, e.g.
+/// `(key INT32 NOT NULL, val STRING, PRIMARY KEY (key))`.
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("(")?;
+        for column in self.columns() {
+            write!(f, "{}, ", column)?;
+        }
+        f.write_str("PRIMARY KEY (")?;
+        for (idx, column) in self.primary_key().iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(column.name())?;
+        }
+        f.write_str("))")
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SchemaBuilder {
     columns: Vec<Column>,
@@ -402,12 +705,33 @@ impl SchemaBuilder {
                 columns.push(self.columns.remove(idx));
             } else {
                 return Err(Error::InvalidArgument(format!(
-                    "primary key column '{}' has no corresponding column",
+                    "unknown primary key column: {}",
                     column_name
                 )));
             }
         }
 
+        for column in columns.iter().chain(&self.columns) {
+            if column.length().is_some() && column.data_type() != DataType::String {
+                return Err(Error::InvalidArgument(format!(
+                    "column {} has a length, but only STRING columns may have a length",
+                    column.name()
+                )));
+            }
+        }
+
+        let nullable_keys = columns
+            .iter()
+            .filter(|column| column.is_nullable())
+            .map(Column::name)
+            .collect::<Vec<_>>();
+        if !nullable_keys.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "primary key columns must be non-nullable: {}",
+                nullable_keys.join(", ")
+            )));
+        }
+
         columns.extend(self.columns.drain(..));
 
         Ok(Schema::new(columns, self.primary_key.len()))
@@ -461,4 +785,245 @@ pub mod tests {
     fn test_create_schema() {
         all_types_schema();
     }
+
+    #[test]
+    fn test_predicates() {
+        let schema = all_types_schema();
+        let column = schema.column_by_name("i32").unwrap();
+
+        let predicate = column.equal(18i32).unwrap();
+        assert_eq!("i32", predicate.column());
+        assert_eq!(&Filter::equals(18i32), predicate.filter());
+        assert_eq!(
+            &Filter::range(19i32..),
+            column.greater_than(18i32).unwrap().filter()
+        );
+        assert_eq!(
+            &Filter::range(18i32..),
+            column.greater_equal(18i32).unwrap().filter()
+        );
+        assert_eq!(
+            &Filter::range(..18i32),
+            column.less_than(18i32).unwrap().filter()
+        );
+        assert_eq!(
+            &Filter::range(..=18i32),
+            column.less_equal(18i32).unwrap().filter()
+        );
+
+        // Literals with the same physical type as the column are accepted.
+        let timestamp = schema.column_by_name("timestamp").unwrap();
+        assert!(timestamp.greater_than(0i64).is_ok());
+
+        fn is_invalid_argument(result: Result<Predicate>) -> bool {
+            match result {
+                Err(Error::InvalidArgument(_)) => true,
+                _ => false,
+            }
+        }
+
+        assert!(is_invalid_argument(column.greater_than(18i64)));
+        assert!(is_invalid_argument(column.equal(18i16)));
+        assert!(is_invalid_argument(column.less_than("18")));
+
+        // The type is checked even when the predicate simplifies to an untyped filter.
+        let string = schema.column_by_name("string").unwrap();
+        assert!(is_invalid_argument(string.greater_equal(i32::min_value())));
+
+        let nullable = schema.column_by_name("nullable_i32").unwrap();
+        assert!(is_invalid_argument(nullable.equal(None::<i32>)));
+        assert!(nullable.equal(Some(18i32)).is_ok());
+    }
+
+    #[test]
+    fn test_decode_partition_key() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("host", DataType::String).set_not_null())
+            .add_column(Column::new("time", DataType::Timestamp).set_not_null())
+            .add_column(Column::new("val", DataType::Double))
+            .set_primary_key(vec!["host", "time"])
+            .build()
+            .unwrap();
+
+        let mut lower_bound = schema.new_row();
+        lower_bound.set("host", "a").unwrap();
+        lower_bound.set("time", 100i64).unwrap();
+        let mut upper_bound = schema.new_row();
+        upper_bound.set("host", "m").unwrap();
+
+        let encoded_lower_bound = key::encode_primary_key(&lower_bound).unwrap();
+        let decoded = schema.decode_partition_key(&encoded_lower_bound).unwrap();
+        assert_eq!(lower_bound, decoded);
+
+        // The upper bound only has the first primary key column set, so encode it by truncating
+        // an encoded key after the first column.
+        let mut full_upper_bound = upper_bound.clone();
+        full_upper_bound.set("time", 0i64).unwrap();
+        let encoded_upper_bound = key::encode_primary_key(&full_upper_bound).unwrap();
+        let decoded = schema
+            .decode_partition_key(&encoded_upper_bound[..encoded_upper_bound.len() - 8])
+            .unwrap();
+        assert_eq!(upper_bound, decoded);
+        assert!(!decoded.is_set("time").unwrap());
+
+        assert_eq!(schema.new_row(), schema.decode_partition_key(&[]).unwrap());
+    }
+
+    #[test]
+    fn test_display() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("val", DataType::Timestamp))
+            .add_column(Column::new("key1", DataType::Int32).set_not_null())
+            .add_column(
+                Column::new("key2", DataType::String)
+                    .set_not_null()
+                    .set_encoding(EncodingType::Dictionary),
+            ).set_primary_key(vec!["key1", "key2"])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            "key2 STRING NOT NULL",
+            &format!("{}", schema.columns()[1])
+        );
+        assert_eq!(
+            "(key1 INT32 NOT NULL, key2 STRING NOT NULL, val UNIXTIME_MICROS, \
+             PRIMARY KEY (key1, key2))",
+            &format!("{}", schema)
+        );
+    }
+
+    #[test]
+    fn test_nullable_primary_key() {
+        let result = SchemaBuilder::new()
+            .add_column(Column::new("key1", DataType::Int32).set_not_null())
+            .add_column(Column::new("key2", DataType::String))
+            .add_column(Column::new("key3", DataType::Int64))
+            .add_column(Column::new("val", DataType::Int64))
+            .set_primary_key(vec!["key1", "key2", "key3"])
+            .build();
+
+        match result {
+            Err(Error::InvalidArgument(ref message)) => assert_eq!(
+                "primary key columns must be non-nullable: key2, key3",
+                message
+            ),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_length() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String).set_length(10))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+        let column = &schema.columns()[1];
+        assert_eq!(Some(10), column.length());
+        assert_eq!("val VARCHAR(10)", &format!("{}", column));
+        assert_eq!(column, &Column::from_pb(column.clone().into_pb(false)).unwrap());
+
+        let result = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Binary).set_length(10))
+            .set_primary_key(vec!["key"])
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_unknown_primary_key() {
+        let result = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int64))
+            .set_primary_key(vec!["kye"])
+            .build();
+
+        match result {
+            Err(Error::InvalidArgument(ref message)) => {
+                assert_eq!("unknown primary key column: kye", message)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_column_lookup() {
+        let schema = all_types_schema();
+        let idx = schema.column_index("nullable_string").unwrap();
+        assert_eq!("nullable_string", schema.columns()[idx].name());
+        let column = schema.column_by_name("nullable_string").unwrap();
+        assert_eq!(DataType::String, column.data_type());
+        assert!(column.is_nullable());
+
+        assert_eq!(None, schema.column_index("missing"));
+        assert!(schema.column_by_name("missing").is_none());
+        // Lookups are case sensitive.
+        assert_eq!(None, schema.column_index("KEY"));
+    }
+
+    #[test]
+    fn test_column_iteration() {
+        let schema = all_types_schema();
+        let names = schema
+            .columns()
+            .iter()
+            .map(Column::name)
+            .filter(|name| name.starts_with("nullable_i"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["nullable_i8", "nullable_i16", "nullable_i32", "nullable_i64"],
+            names
+        );
+
+        // Columns are borrowed from the schema, and remain valid for as long as it does.
+        let key = schema.columns().iter().next().unwrap();
+        assert_eq!("key", key.name());
+    }
+
+    #[test]
+    fn test_column_out_of_range() {
+        let schema = simple_schema();
+        let num_columns = schema.columns().len();
+        assert_eq!("key", schema.column(0).unwrap().name());
+        assert!(schema.column(num_columns - 1).is_some());
+        assert!(schema.column(num_columns).is_none());
+        assert!(schema.column(usize::max_value()).is_none());
+    }
+
+    #[test]
+    fn test_is_nullable() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("nullable", DataType::String).set_nullable())
+            .add_column(Column::new("not_null", DataType::Int64).set_not_null())
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        assert!(!schema.is_nullable(0).unwrap());
+        assert!(schema.is_nullable(1).unwrap());
+        assert!(!schema.is_nullable(2).unwrap());
+        match schema.is_nullable(3) {
+            Err(Error::InvalidArgument(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_primary_key_indexes() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("val", DataType::Int64))
+            .add_column(Column::new("key2", DataType::String).set_not_null())
+            .add_column(Column::new("key1", DataType::Int32).set_not_null())
+            .set_primary_key(vec!["key1", "key2"])
+            .build()
+            .unwrap();
+
+        assert_eq!(&[0, 1], schema.primary_key_indexes());
+        assert_eq!("key1", schema.columns()[0].name());
+        assert_eq!("key2", schema.columns()[1].name());
+    }
 }
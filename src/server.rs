@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use url::Url;
 
+use pb::hms::HiveMetastoreConfig as HiveMetastoreConfigPb;
 use pb::master::list_tablet_servers_response_pb::Entry as TabletServerEntryPb;
 use pb::ExpectField;
 use pb::ServerEntryPb as MasterEntryPb;
@@ -169,3 +170,51 @@ impl TabletServerInfo {
         })
     }
 }
+
+/// The cluster's Hive Metastore (HMS) integration configuration.
+///
+/// Tables in a cluster integrated with the HMS are named `database.table`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HmsConfig {
+    uris: Vec<String>,
+    is_sasl_enabled: bool,
+    uuid: Option<String>,
+}
+
+impl HmsConfig {
+    /// The HMS URIs.
+    pub fn uris(&self) -> &[String] {
+        &self.uris
+    }
+
+    /// Whether the HMS requires SASL authentication.
+    pub fn is_sasl_enabled(&self) -> bool {
+        self.is_sasl_enabled
+    }
+
+    /// The HMS UUID, if the master knows it.
+    pub fn uuid(&self) -> Option<&str> {
+        self.uuid.as_ref().map(String::as_str)
+    }
+
+    /// Creates an `HmsConfig` from the Protobuf message format. Returns `None` if HMS integration
+    /// is disabled.
+    pub(crate) fn from_pb(config: HiveMetastoreConfigPb) -> Option<HmsConfig> {
+        let is_sasl_enabled = config.hms_sasl_enabled();
+        let uris = config
+            .hms_uris?
+            .split(',')
+            .map(str::trim)
+            .filter(|uri| !uri.is_empty())
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        if uris.is_empty() {
+            return None;
+        }
+        Some(HmsConfig {
+            uris,
+            is_sasl_enabled,
+            uuid: config.hms_uuid,
+        })
+    }
+}
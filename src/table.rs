@@ -1,24 +1,34 @@
+use std::collections::HashMap;
+
 use pb::master::alter_table_request_pb::{
-    AddColumn, AddRangePartition, DropColumn, DropRangePartition, RenameColumn, Step, StepType,
+    AddColumn, AddRangePartition, AlterColumn as AlterColumnPb, DropColumn, DropRangePartition,
+    RenameColumn, Step, StepType,
 };
 use pb::master::{AlterTableRequestPb, CreateTableRequestPb};
 use pb::partition_schema_pb::{ColumnIdentifierPb, HashBucketSchemaPb, RangeSchemaPb};
-use pb::PartitionSchemaPb;
+use pb::{ColumnSchemaDeltaPb, PartitionSchemaPb};
 
+use key;
 use meta_cache::{Entry, Lookup, TableLocations};
 use partition::PartitionSchema;
 use scanner::ScanBuilder;
 use tablet::TabletInfo;
+use BulkLoadReport;
 use Column;
+use CompressionType;
+use Datum;
+use EncodingType;
 use Error;
 use OperationEncoder;
 use Result;
 use Row;
 use Schema;
 use TableId;
+use TabletId;
 use Writer;
 use WriterConfig;
 
+use futures::future;
 use futures::{Async, Future, Poll, Stream};
 
 #[derive(Clone)]
@@ -28,16 +38,23 @@ pub struct Table {
     schema: Schema,
     partition_schema: PartitionSchema,
     num_replicas: u32,
+    owner: String,
+    comment: String,
+    extra_configs: HashMap<String, String>,
     table_locations: TableLocations,
 }
 
 impl Table {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub(crate) fn new(
         name: String,
         id: TableId,
         schema: Schema,
         partition_schema: PartitionSchema,
         num_replicas: u32,
+        owner: String,
+        comment: String,
+        extra_configs: HashMap<String, String>,
         table_locations: TableLocations,
     ) -> Table {
         Table {
@@ -46,6 +63,9 @@ impl Table {
             schema,
             partition_schema,
             num_replicas,
+            owner,
+            comment,
+            extra_configs,
             table_locations,
         }
     }
@@ -62,22 +82,69 @@ impl Table {
         &self.schema
     }
 
+    /// Returns the table's partition schema, as reported by the master when the table was opened.
     pub fn partition_schema(&self) -> &PartitionSchema {
         &self.partition_schema
     }
 
+    /// Returns the replication factor of the table.
     pub fn num_replicas(&self) -> u32 {
         self.num_replicas
     }
 
+    /// Returns the table's owner, or an empty string if the table has no owner.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Returns the table's comment, or an empty string if the table has no comment.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Returns the value of the table's extra configuration property, if it is set.
+    pub fn extra_config(&self, key: &str) -> Option<&str> {
+        self.extra_configs.get(key).map(String::as_str)
+    }
+
     pub fn new_writer(&self, config: WriterConfig) -> Writer {
         Writer::new(self.clone(), config)
     }
 
+    /// Inserts the rows into the table, resolving once every row has been written or has failed.
+    ///
+    /// Rows are batched and flushed by a writer with the default `WriterConfig`, so they need not
+    /// fit in the writer's buffer at once. Rows which fail to insert, such as rows whose primary
+    /// key already exists, are listed in the report rather than failing the future.
+    pub fn bulk_insert<I>(&self, rows: I) -> impl Future<Item = BulkLoadReport, Error = Error>
+    where
+        I: IntoIterator<Item = Row<'static>>,
+    {
+        let writer = self.new_writer(WriterConfig::default());
+        let errors = writer.error_stream().collect();
+        writer
+            .insert_all(rows)
+            .and_then(Writer::flush)
+            .and_then(|(writer, stats)| {
+                // The error stream ends once the writer is dropped.
+                drop(writer);
+                errors
+                    .map_err(|()| -> Error { unreachable!() })
+                    .map(move |errors| BulkLoadReport::new(stats.successful_operations(), errors))
+            })
+    }
+
     pub fn scan_builder(&self) -> ScanBuilder {
-        ScanBuilder::new(self.schema.clone(), self.table_locations.clone())
+        ScanBuilder::new(
+            self.name.clone(),
+            self.id,
+            self.schema.clone(),
+            self.partition_schema.clone(),
+            self.table_locations.clone(),
+        )
     }
 
+    /// Returns a stream of the table's tablets, in partition key order.
     // TODO: should this be a stream?
     pub fn tablets(&self) -> Tablets {
         Tablets {
@@ -88,6 +155,39 @@ impl Table {
         }
     }
 
+    /// Lists the table's tablets, in partition key order.
+    ///
+    /// Each tablet's info includes the tablet ID, the encoded partition bounds, and the addresses
+    /// of the tablet's replicas.
+    pub fn list_tablets(&self) -> impl Future<Item = Vec<TabletInfo>, Error = Error> {
+        self.tablets().collect()
+    }
+
+    /// Returns the ID of the tablet which the row belongs to, and which writes of the row are sent
+    /// to.
+    ///
+    /// The tablet is found by encoding the row's partition key on the client, and looking it up
+    /// in the tablet locations reported by the master, which makes this useful for verifying how
+    /// rows are partitioned. The row's partition columns must be set. Fails with
+    /// `Error::NoRangePartition` if no range partition covers the row.
+    pub fn locate_tablet(&self, row: &Row) -> impl Future<Item = TabletId, Error = Error> {
+        let lookup = key::encode_partition_key(&self.partition_schema, row)
+            .map(|partition_key| self.table_locations.tablet(&partition_key));
+        future::result(lookup).flatten().and_then(|tablet| {
+            tablet
+                .map(|tablet| tablet.id())
+                .ok_or(Error::NoRangePartition)
+        })
+    }
+
+    /// Clears the client's cached tablet locations for the table, so that subsequent operations
+    /// refetch them from the master.
+    ///
+    /// The locations are shared by all handles to the table opened from the same client.
+    pub fn clear_location_cache(&self) {
+        self.table_locations.clear()
+    }
+
     pub(crate) fn table_locations(&self) -> &TableLocations {
         &self.table_locations
     }
@@ -160,6 +260,9 @@ pub struct TableBuilder {
     range_partitions: Vec<(RangePartitionBound, RangePartitionBound)>,
     range_partition_splits: Vec<Row<'static>>,
     num_replicas: Option<u32>,
+    owner: Option<String>,
+    comment: Option<String>,
+    extra_configs: HashMap<String, String>,
 }
 
 impl TableBuilder {
@@ -176,6 +279,9 @@ impl TableBuilder {
             range_partitions: Vec::new(),
             range_partition_splits: Vec::new(),
             num_replicas: None,
+            owner: None,
+            comment: None,
+            extra_configs: HashMap::new(),
         }
     }
 
@@ -184,6 +290,9 @@ impl TableBuilder {
     }
 
     /// Hash partitions the table by the specfied columns.
+    ///
+    /// The columns must be primary key columns; creating the table fails with
+    /// `Error::InvalidArgument` otherwise.
     pub fn add_hash_partitions<S>(
         &mut self,
         columns: Vec<S>,
@@ -241,6 +350,38 @@ impl TableBuilder {
         self.num_replicas = Some(num_replicas);
     }
 
+    /// Sets the owner of the table.
+    ///
+    /// Defaults to the user which creates the table.
+    pub fn set_owner<S>(&mut self, owner: S) -> &mut TableBuilder
+    where
+        S: Into<String>,
+    {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Sets a free-form comment describing the table.
+    pub fn set_comment<S>(&mut self, comment: S) -> &mut TableBuilder
+    where
+        S: Into<String>,
+    {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Sets an extra configuration property of the table, such as
+    /// `kudu.table.history_max_age_sec`, which overrides the cluster-wide default of the
+    /// corresponding tablet server flag for the table.
+    pub fn set_extra_config<K, V>(&mut self, key: K, value: V) -> &mut TableBuilder
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.extra_configs.insert(key.into(), value.into());
+        self
+    }
+
     pub(crate) fn into_pb(self) -> Result<CreateTableRequestPb> {
         let TableBuilder {
             name,
@@ -250,6 +391,9 @@ impl TableBuilder {
             range_partition_splits,
             hash_partitions,
             num_replicas,
+            owner,
+            comment,
+            extra_configs,
         } = self;
 
         let mut range_encoder = OperationEncoder::new();
@@ -284,6 +428,23 @@ impl TableBuilder {
 
         let split_rows_range_bounds = range_encoder.into_pb();
 
+        let mut non_key_columns: Vec<&str> = Vec::new();
+        for column in hash_partitions.iter().flat_map(|&(ref columns, _, _)| columns) {
+            let is_key = schema
+                .primary_key()
+                .iter()
+                .any(|key_column| key_column.name() == column.as_str());
+            if !is_key && !non_key_columns.contains(&column.as_str()) {
+                non_key_columns.push(column);
+            }
+        }
+        if !non_key_columns.is_empty() {
+            return Err(Error::InvalidArgument(format!(
+                "hash partition columns must be primary key columns: {}",
+                non_key_columns.join(", ")
+            )));
+        }
+
         let partition_schema = PartitionSchemaPb {
             hash_bucket_schemas: hash_partitions
                 .into_iter()
@@ -303,6 +464,7 @@ impl TableBuilder {
                     .map(ColumnIdentifierPb::from)
                     .collect(),
             }),
+            ..Default::default()
         };
 
         Ok(CreateTableRequestPb {
@@ -311,6 +473,10 @@ impl TableBuilder {
             split_rows_range_bounds: Some(split_rows_range_bounds),
             partition_schema: Some(partition_schema),
             num_replicas: num_replicas.map(|n| n as i32),
+            owner,
+            comment,
+            extra_configs,
+            ..Default::default()
         })
     }
 }
@@ -319,6 +485,7 @@ pub struct AlterTableBuilder {
     pub(crate) result: Result<()>,
     pub(crate) schema: Option<Schema>,
     pub(crate) pb: AlterTableRequestPb,
+    pub(crate) alter_columns: Vec<AlterColumn>,
 }
 
 impl AlterTableBuilder {
@@ -327,6 +494,7 @@ impl AlterTableBuilder {
             result: Ok(()),
             schema: None,
             pb: AlterTableRequestPb::default(),
+            alter_columns: Vec::new(),
         }
     }
 
@@ -379,6 +547,31 @@ impl AlterTableBuilder {
         self
     }
 
+    /// Alters the storage attributes, default value, name, or comment of an existing column.
+    ///
+    /// The alteration is validated against the table's current schema before the table is
+    /// altered: the column must exist, and any new encoding and default value must be valid for
+    /// the column's type.
+    pub fn alter_column(&mut self, alter: AlterColumn) -> &mut AlterTableBuilder {
+        // The step is filled in once the alteration is validated.
+        self.pb.alter_schema_steps.push(Step {
+            type_: Some(StepType::AlterColumn as i32),
+            ..Default::default()
+        });
+        self.alter_columns.push(alter);
+        self
+    }
+
+    /// Sets an extra configuration property of the table, replacing its current value.
+    pub fn set_extra_config<K, V>(&mut self, key: K, value: V) -> &mut AlterTableBuilder
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.pb.new_extra_configs.insert(key.into(), value.into());
+        self
+    }
+
     fn check_and_set_schema(&mut self, new_schema: &Schema) {
         if self.result.is_err() {
             return;
@@ -412,6 +605,7 @@ impl AlterTableBuilder {
                 type_: Some(StepType::AddRangePartition as i32),
                 add_range_partition: Some(AddRangePartition {
                     range_bounds: Some(encoder.into_pb()),
+                    ..Default::default()
                 }),
                 ..Default::default()
             });
@@ -447,6 +641,152 @@ impl Default for AlterTableBuilder {
     }
 }
 
+/// Fills in the alter column steps added by `AlterTableBuilder::alter_column`, after validating
+/// each alteration against the table's current schema.
+pub(crate) fn fill_alter_column_steps(
+    pb: &mut AlterTableRequestPb,
+    alter_columns: Vec<AlterColumn>,
+    schema: &Schema,
+) -> Result<()> {
+    let steps = pb
+        .alter_schema_steps
+        .iter_mut()
+        .filter(|step| step.type_() == StepType::AlterColumn && step.alter_column.is_none());
+    for (step, alter) in steps.zip(alter_columns) {
+        step.alter_column = Some(AlterColumnPb {
+            delta: Some(alter.into_pb(schema)?),
+        });
+    }
+    Ok(())
+}
+
+/// An alteration of an existing column, applied with `AlterTableBuilder::alter_column`.
+///
+/// Attributes which are not set are left unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlterColumn {
+    name: String,
+    new_name: Option<String>,
+    encoding: Option<EncodingType>,
+    compression: Option<CompressionType>,
+    cfile_block_size: Option<u32>,
+    default: Option<Datum>,
+    comment: Option<String>,
+}
+
+impl AlterColumn {
+    /// Returns a new alteration of the named column.
+    pub fn new<S>(name: S) -> AlterColumn
+    where
+        S: Into<String>,
+    {
+        AlterColumn {
+            name: name.into(),
+            new_name: None,
+            encoding: None,
+            compression: None,
+            cfile_block_size: None,
+            default: None,
+            comment: None,
+        }
+    }
+
+    /// Renames the column.
+    pub fn rename<S>(mut self, new_name: S) -> AlterColumn
+    where
+        S: Into<String>,
+    {
+        self.new_name = Some(new_name.into());
+        self
+    }
+
+    /// Sets the column's encoding. The new encoding applies to data written after the alteration.
+    pub fn set_encoding(mut self, encoding: EncodingType) -> AlterColumn {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Sets the column's compression. The new compression applies to data written after the
+    /// alteration.
+    pub fn set_compression(mut self, compression: CompressionType) -> AlterColumn {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn set_cfile_block_size(mut self, cfile_block_size: u32) -> AlterColumn {
+        self.cfile_block_size = Some(cfile_block_size);
+        self
+    }
+
+    /// Sets the value written to the column when an inserted row does not set it.
+    /// `Datum::Null` removes the column's default value.
+    pub fn set_default(mut self, default: Datum) -> AlterColumn {
+        self.default = Some(default);
+        self
+    }
+
+    /// Sets the column's comment. An empty comment clears the comment.
+    pub fn set_comment<S>(mut self, comment: S) -> AlterColumn
+    where
+        S: Into<String>,
+    {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    fn into_pb(self, schema: &Schema) -> Result<ColumnSchemaDeltaPb> {
+        let column = schema
+            .column_by_name(&self.name)
+            .ok_or_else(|| Error::InvalidArgument(format!("unknown column: {}", self.name)))?;
+
+        if let Some(encoding) = self.encoding {
+            if !encoding.supports(column.data_type()) {
+                return Err(Error::InvalidArgument(format!(
+                    "encoding {:?} is not supported by {} column {}",
+                    encoding,
+                    column.data_type(),
+                    self.name
+                )));
+            }
+        }
+
+        let (default_value, remove_default) = match self.default {
+            None => (None, None),
+            Some(Datum::Null) => (None, Some(true)),
+            Some(default) => {
+                if default.data_type() != Some(column.data_type()) {
+                    return Err(Error::InvalidArgument(format!(
+                        "default value {:?} does not match the type of {} column {}",
+                        default,
+                        column.data_type(),
+                        self.name
+                    )));
+                }
+                let default = default.encode().ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "invalid default value for column {}",
+                        self.name
+                    ))
+                })?;
+                (Some(default), None)
+            }
+        };
+
+        Ok(ColumnSchemaDeltaPb {
+            name: self.name,
+            new_name: self.new_name,
+            default_value,
+            remove_default,
+            encoding: self.encoding.map(EncodingType::to_pb),
+            compression: self.compression.map(CompressionType::to_pb),
+            // TODO: checked cast.
+            block_size: self.cfile_block_size.map(|size| size as i32),
+            new_comment: self.comment,
+            ..Default::default()
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -475,6 +815,71 @@ mod tests {
         table_builder.add_range_partition_split(split_row);
     }
 
+    #[test]
+    fn hash_partition_non_key_columns() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .add_column(Column::new("other", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("t", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 4);
+        assert!(table_builder.into_pb().is_ok());
+
+        let mut table_builder = TableBuilder::new("t", schema);
+        table_builder.add_hash_partitions(vec!["key", "val"], 4);
+        table_builder.add_hash_partitions(vec!["other"], 2);
+        match table_builder.into_pb() {
+            Err(Error::InvalidArgument(message)) => assert_eq!(
+                "hash partition columns must be primary key columns: val, other",
+                message
+            ),
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn alter_column_validation() {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let delta = AlterColumn::new("val")
+            .set_encoding(EncodingType::Dictionary)
+            .set_compression(CompressionType::Lz4)
+            .set_default(Datum::String("foo".to_string()))
+            .into_pb(&schema)
+            .unwrap();
+        assert_eq!("val", delta.name);
+        assert_eq!(Some(b"foo".to_vec()), delta.default_value);
+
+        let delta = AlterColumn::new("val")
+            .set_default(Datum::Null)
+            .into_pb(&schema)
+            .unwrap();
+        assert_eq!(Some(true), delta.remove_default);
+
+        assert!(AlterColumn::new("missing").into_pb(&schema).is_err());
+        assert!(
+            AlterColumn::new("key")
+                .set_encoding(EncodingType::Dictionary)
+                .into_pb(&schema)
+                .is_err()
+        );
+        assert!(
+            AlterColumn::new("key")
+                .set_default(Datum::Int64(1))
+                .into_pb(&schema)
+                .is_err()
+        );
+    }
+
     #[test]
     fn tablets_unpartitioned() {
         let _ = env_logger::try_init();
@@ -549,5 +954,160 @@ mod tests {
         let tablets = runtime.block_on(table.tablets().collect()).unwrap();
 
         assert_eq!(8, tablets.len());
+
+        let listed_tablets = runtime.block_on(table.list_tablets()).unwrap();
+        assert_eq!(tablets, listed_tablets);
+
+        for tablet in &listed_tablets {
+            assert_eq!(3, tablet.replicas().len());
+            for replica in tablet.replicas() {
+                assert!(!replica.rpc_addrs().is_empty());
+            }
+        }
+
+        // Tablets are returned in partition key order, with no overlap.
+        for window in listed_tablets.windows(2) {
+            let upper_bound = window[0].partition().upper_bound();
+            let lower_bound = window[1].partition().lower_bound();
+            assert!(upper_bound <= lower_bound);
+        }
+    }
+
+    #[test]
+    fn locate_tablet() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = simple_schema();
+        let mut table_builder = TableBuilder::new("locate_tablet", schema.clone());
+        table_builder.set_range_partition_columns(vec!["key"]);
+        table_builder.set_num_replicas(1);
+        for &(lower, upper) in &[("a", "m"), ("p", "s")] {
+            let mut lower_bound = schema.new_row();
+            lower_bound.set(0, lower).unwrap();
+            let mut upper_bound = schema.new_row();
+            upper_bound.set(0, upper).unwrap();
+            table_builder.add_range_partition(
+                RangePartitionBound::Inclusive(lower_bound),
+                RangePartitionBound::Exclusive(upper_bound),
+            );
+        }
+        let mut split = schema.new_row();
+        split.set(0, "c").unwrap();
+        table_builder.add_range_partition_split(split);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        // The tablets cover [a, c), [c, m), and [p, s), in partition key order.
+        let tablets = runtime.block_on(table.list_tablets()).unwrap();
+        assert_eq!(3, tablets.len());
+
+        let mut locate = |key: &str| {
+            let mut row = table.schema().new_row();
+            row.set("key", key).unwrap();
+            runtime.block_on(table.locate_tablet(&row))
+        };
+        assert_eq!(tablets[0].id(), locate("a").unwrap());
+        assert_eq!(tablets[0].id(), locate("bzz").unwrap());
+        assert_eq!(tablets[1].id(), locate("c").unwrap());
+        assert_eq!(tablets[1].id(), locate("l").unwrap());
+        assert_eq!(tablets[2].id(), locate("q").unwrap());
+        match locate("n") {
+            Err(Error::NoRangePartition) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn open_table_properties() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = simple_schema();
+
+        let mut table_builder = TableBuilder::new("open_table_properties", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_range_partition_columns(vec!["key"]);
+        table_builder.set_num_replicas(1);
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+
+        assert_eq!(1, table.num_replicas());
+
+        let partition_schema = table.partition_schema();
+        assert_eq!(&[0], partition_schema.range_partition_schema().columns());
+        assert_eq!(1, partition_schema.hash_partition_schemas().len());
+        assert_eq!(&[0], partition_schema.hash_partition_schemas()[0].columns());
+        assert_eq!(2, partition_schema.hash_partition_schemas()[0].num_buckets());
+        assert_eq!("", table.comment());
+    }
+
+    #[test]
+    fn owner_and_comment() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("owner_and_comment", simple_schema());
+        table_builder.set_num_replicas(1);
+        table_builder.set_owner("alice").set_comment("a table with an owner");
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+        assert_eq!("alice", table.owner());
+        assert_eq!("a table with an owner", table.comment());
+    }
+
+    #[test]
+    fn extra_config() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let mut table_builder = TableBuilder::new("extra_config", simple_schema());
+        table_builder.set_num_replicas(1);
+        table_builder.set_extra_config("kudu.table.history_max_age_sec", "3600");
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .expect("create_table");
+
+        let table = runtime
+            .block_on(client.open_table_by_id(table_id))
+            .expect("open_table");
+        assert_eq!(
+            Some("3600"),
+            table.extra_config("kudu.table.history_max_age_sec")
+        );
+        assert_eq!(None, table.extra_config("kudu.table.maintenance_priority"));
     }
 }
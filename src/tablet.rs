@@ -1,6 +1,7 @@
 use std::fmt;
 use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::Arc;
+use std::time::Instant;
 
 use krpc;
 
@@ -30,6 +31,12 @@ pub(crate) struct Tablet {
 
     /// The tablet replicas.
     pub replicas: Vec<TabletReplica>,
+
+    /// The time after which the cached tablet location must be refreshed from the master.
+    pub deadline: Instant,
+
+    /// Whether the cached tablet location has been found to be out of date.
+    pub is_invalidated: AtomicBool,
 }
 
 impl Tablet {
@@ -46,6 +53,11 @@ impl Tablet {
         &self.upper_bound
     }
 
+    /// Returns `true` if the cached tablet location has expired or been invalidated.
+    pub fn is_stale(&self, now: Instant) -> bool {
+        self.is_invalidated.load(Relaxed) || self.deadline <= now
+    }
+
     pub(crate) fn info(
         &self,
         primary_key_schema: &Schema,
@@ -79,6 +91,10 @@ impl ReplicaSet for Arc<Tablet> {
     fn replicas(&self) -> &[TabletReplica] {
         &self.replicas
     }
+
+    fn invalidate(&self) {
+        self.is_invalidated.store(true, Relaxed);
+    }
 }
 
 /// Tablet replica belonging to a tablet server.
@@ -25,8 +25,8 @@ pub struct DateTime {
     nanos: u32,
 }
 
-impl fmt::Display for DateTime {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl DateTime {
+    fn fmt_date(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.year > 9999 {
             write!(f, "+{}", self.year)?;
         } else if self.year < 0 {
@@ -34,12 +34,16 @@ impl fmt::Display for DateTime {
         } else {
             write!(f, "{:04}", self.year)?;
         }
+        write!(f, "-{:02}-{:02}", self.month, self.day)
+    }
+}
 
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_date(f)?;
         write!(
             f,
-            "-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
-            self.month,
-            self.day,
+            "T{:02}:{:02}:{:02}.{:06}Z",
             self.hour,
             self.minute,
             self.second,
@@ -48,6 +52,39 @@ impl fmt::Display for DateTime {
     }
 }
 
+/// The format of timestamp values written by exporters such as `Row::to_json` and
+/// `RowBatch::write_csv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimestampFormat {
+    /// An RFC 3339 string in UTC with microsecond precision, e.g. `2018-01-01T01:02:03.456789Z`.
+    Rfc3339,
+    /// The number of microseconds since the Unix epoch, as stored by Kudu.
+    EpochMicros,
+}
+
+impl Default for TimestampFormat {
+    fn default() -> TimestampFormat {
+        TimestampFormat::Rfc3339
+    }
+}
+
+/// A calendar date, which exists to format Kudu `DATE` values as ISO 8601 dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date(DateTime);
+
+impl Date {
+    /// Creates a date from the number of days since the Unix epoch.
+    pub fn from_days(days: i32) -> Date {
+        Date(DateTime::from_secs(i64::from(days) * 86_400, 0))
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_date(f)
+    }
+}
+
 impl From<SystemTime> for DateTime {
     fn from(timestamp: SystemTime) -> DateTime {
         let (t, nanos) = match timestamp.duration_since(UNIX_EPOCH) {
@@ -66,6 +103,14 @@ impl From<SystemTime> for DateTime {
                 }
             }
         };
+        DateTime::from_secs(t, nanos)
+    }
+}
+
+impl DateTime {
+    /// Creates a date/time from seconds since the Unix epoch, and a sub-second nanosecond
+    /// component.
+    fn from_secs(t: i64, nanos: u32) -> DateTime {
 
         // 2000-03-01 (mod 400 year, immediately after feb29
         const LEAPOCH: i64 = 946_684_800 + 86400 * (31 + 29);
@@ -205,4 +250,20 @@ mod tests {
         case("2345-06-07T08:09:01.000000Z", 11847456541, 0);
         case("-2345-06-07T08:09:01.000000Z", -136154620259, 0);
     }
+
+    #[test]
+    fn test_date() {
+        let case = |expected: &str, days: i32| {
+            assert_eq!(expected, Date::from_days(days).to_string(), "days: {}", days)
+        };
+
+        case("1970-01-01", 0);
+        case("1970-01-02", 1);
+        case("1969-12-31", -1);
+        case("2000-02-29", 11_016);
+        case("0001-01-01", -719_162);
+        case("9999-12-31", 2_932_896);
+        case("+5881580-07-11", i32::MAX);
+        case("-5877641-06-24", i32::MIN);
+    }
 }
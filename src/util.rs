@@ -1,33 +1,58 @@
 use std::collections::HashSet;
 use std::fmt;
+use std::i64;
 use std::net::IpAddr;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures::{Async, Future, Poll};
 use ifaces;
+use tokio_timer::Delay;
 use url::Url;
 
 use pb::HostPortPb;
-use timestamp::DateTime;
+use timestamp::{Date, DateTime};
 use DataType;
 use Error;
 use Row;
 
+/// Converts a time to the number of microseconds since the Unix epoch, which is how Kudu stores
+/// `UNIXTIME_MICROS` values.
+///
+/// Times before the epoch are negative. Sub-microsecond precision is rounded towards negative
+/// infinity, so that the conversion preserves ordering. An `i64` of microseconds spans roughly
+/// 292,000 years either side of the epoch; times outside of that range saturate to `i64::MIN` or
+/// `i64::MAX`.
 pub fn time_to_us(time: SystemTime) -> i64 {
-    // TODO: do overflow checking
     match time.duration_since(UNIX_EPOCH) {
         Ok(duration) => {
-            (duration.as_secs() * 1_000_000 + u64::from(duration.subsec_nanos()) / 1000) as i64
+            let us = duration
+                .as_secs()
+                .checked_mul(1_000_000)
+                .and_then(|us| us.checked_add(u64::from(duration.subsec_nanos() / 1_000)));
+            match us {
+                Some(us) if us <= i64::MAX as u64 => us as i64,
+                _ => i64::MAX,
+            }
         }
         Err(error) => {
             let duration = error.duration();
-            (-((duration.as_secs() * 1_000_000 + u64::from(duration.subsec_nanos()) / 1000) as i64))
+            // Round the magnitude up, so that the result is rounded towards negative infinity.
+            let us = duration
+                .as_secs()
+                .checked_mul(1_000_000)
+                .and_then(|us| us.checked_add(u64::from((duration.subsec_nanos() + 999) / 1_000)));
+            match us {
+                // i64::MIN has no positive counterpart, so negate with wrapping.
+                Some(us) if us <= i64::MAX as u64 + 1 => (us as i64).wrapping_neg(),
+                _ => i64::MIN,
+            }
         }
     }
 }
 
+/// Converts a number of microseconds since the Unix epoch to a time.
 pub fn us_to_time(us: i64) -> SystemTime {
-    let abs = us.abs() as u64;
+    let abs = us.wrapping_abs() as u64;
 
     let s = abs / 1_000_000;
     let ns = (abs % 1_000_000) as u32 * 1000;
@@ -75,6 +100,7 @@ pub fn fmt_cell(f: &mut fmt::Formatter, row: &Row, idx: usize) -> fmt::Result {
         DataType::Double => write!(f, "{}", row.get::<_, f64>(idx).unwrap()),
         DataType::Binary => fmt_hex(f, row.get::<_, &[u8]>(idx).unwrap()),
         DataType::String => write!(f, "{:?}", row.get::<_, &str>(idx).unwrap()),
+        DataType::Date => write!(f, "{}", Date::from_days(row.get(idx).unwrap())),
     }
 }
 
@@ -150,6 +176,40 @@ where
     }
 }
 
+/// A future which fails with `Error::TimedOut` if the wrapped future does not complete before a
+/// deadline.
+pub struct TimeoutFuture<F> {
+    future: F,
+    delay: Delay,
+}
+
+impl<F> TimeoutFuture<F> {
+    pub fn new(future: F, deadline: Instant) -> TimeoutFuture<F> {
+        TimeoutFuture {
+            future,
+            delay: Delay::new(deadline),
+        }
+    }
+}
+
+impl<F> Future for TimeoutFuture<F>
+where
+    F: Future<Error = Error>,
+{
+    type Item = F::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<F::Item, Error> {
+        if let Async::Ready(item) = self.future.poll()? {
+            return Ok(Async::Ready(item));
+        }
+        match self.delay.poll().expect("timer failed") {
+            Async::Ready(()) => Err(Error::TimedOut),
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -179,6 +239,29 @@ mod tests {
         assert!(is_local_addr(&addr));
     }
 
+    #[test]
+    fn test_time_to_us() {
+        assert_eq!(0, time_to_us(UNIX_EPOCH));
+        assert_eq!(1, time_to_us(UNIX_EPOCH + Duration::from_micros(1)));
+        assert_eq!(0, time_to_us(UNIX_EPOCH + Duration::from_nanos(999)));
+        assert_eq!(-1, time_to_us(UNIX_EPOCH - Duration::from_nanos(1)));
+        assert_eq!(-1, time_to_us(UNIX_EPOCH - Duration::from_micros(1)));
+        assert_eq!(-2, time_to_us(UNIX_EPOCH - Duration::from_nanos(1_500)));
+        assert_eq!(
+            -86_400_000_000,
+            time_to_us(UNIX_EPOCH - Duration::from_secs(86_400))
+        );
+
+        let far_future = UNIX_EPOCH + Duration::from_secs(i64::MAX as u64 / 2);
+        assert_eq!(i64::MAX, time_to_us(far_future));
+        let far_past = UNIX_EPOCH - Duration::from_secs(i64::MAX as u64 / 2);
+        assert_eq!(i64::MIN, time_to_us(far_past));
+
+        assert_eq!(UNIX_EPOCH, us_to_time(0));
+        assert_eq!(UNIX_EPOCH - Duration::from_micros(1), us_to_time(-1));
+        assert_eq!(i64::MIN, time_to_us(us_to_time(i64::MIN)));
+    }
+
     proptest! {
 
         #[test]
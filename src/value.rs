@@ -5,7 +5,11 @@ use std::time::SystemTime;
 use std::{f32, f64};
 
 use ieee754::Ieee754;
+#[cfg(feature = "serde")]
+use {base64, serde_json};
 
+#[cfg(feature = "serde")]
+use timestamp::{Date, DateTime, TimestampFormat};
 use util::{time_to_us, us_to_time};
 use DataType;
 use PhysicalType;
@@ -339,6 +343,102 @@ where
     }
 }
 
+/// A dynamically-typed column value.
+///
+/// `Datum` is useful for generic code which does not know the types of columns at compile time,
+/// for instance when exporting the results of a scan. See `Row::get_datum` and `Row::set_datum`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Datum {
+    Bool(bool),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Timestamp(SystemTime),
+    Float(f32),
+    Double(f64),
+    Binary(Vec<u8>),
+    String(String),
+    /// A date, as the number of days since the Unix epoch.
+    Date(i32),
+    Null,
+}
+
+impl Datum {
+    /// Returns the data type of the datum, or `None` if the datum is null.
+    pub fn data_type(&self) -> Option<DataType> {
+        match *self {
+            Datum::Bool(_) => Some(DataType::Bool),
+            Datum::Int8(_) => Some(DataType::Int8),
+            Datum::Int16(_) => Some(DataType::Int16),
+            Datum::Int32(_) => Some(DataType::Int32),
+            Datum::Int64(_) => Some(DataType::Int64),
+            Datum::Timestamp(_) => Some(DataType::Timestamp),
+            Datum::Float(_) => Some(DataType::Float),
+            Datum::Double(_) => Some(DataType::Double),
+            Datum::Binary(_) => Some(DataType::Binary),
+            Datum::String(_) => Some(DataType::String),
+            Datum::Date(_) => Some(DataType::Date),
+            Datum::Null => None,
+        }
+    }
+
+    /// Returns `true` if the datum is null.
+    pub fn is_null(&self) -> bool {
+        *self == Datum::Null
+    }
+
+    /// Encodes the datum as a predicate value, or returns `None` if the datum is null or can
+    /// not be compared (i.e. NaN).
+    pub(crate) fn encode(self) -> Option<Vec<u8>> {
+        fn encode<'data, V: Value<'data>>(value: V) -> Option<Vec<u8>> {
+            if value.is_comparable() {
+                Some(value.encode())
+            } else {
+                None
+            }
+        }
+        match self {
+            Datum::Bool(value) => encode(value),
+            Datum::Int8(value) => encode(value),
+            Datum::Int16(value) => encode(value),
+            Datum::Int32(value) => encode(value),
+            Datum::Int64(value) => encode(value),
+            Datum::Timestamp(value) => encode(value),
+            Datum::Float(value) => encode(value),
+            Datum::Double(value) => encode(value),
+            Datum::Binary(value) => encode(value),
+            Datum::String(value) => encode(value),
+            Datum::Date(value) => encode(value),
+            Datum::Null => None,
+        }
+    }
+
+    /// Converts the datum to JSON. Binary values are base64 encoded, timestamps are formatted
+    /// according to `timestamp_format`, and dates are formatted as ISO 8601 dates.
+    #[cfg(feature = "serde")]
+    pub(crate) fn into_json(self, timestamp_format: TimestampFormat) -> serde_json::Value {
+        use serde_json::Value as Json;
+        match self {
+            Datum::Bool(value) => Json::from(value),
+            Datum::Int8(value) => Json::from(value),
+            Datum::Int16(value) => Json::from(value),
+            Datum::Int32(value) => Json::from(value),
+            Datum::Int64(value) => Json::from(value),
+            Datum::Timestamp(value) => match timestamp_format {
+                TimestampFormat::Rfc3339 => Json::from(DateTime::from(value).to_string()),
+                TimestampFormat::EpochMicros => Json::from(time_to_us(value)),
+            },
+            Datum::Float(value) => Json::from(value),
+            Datum::Double(value) => Json::from(value),
+            Datum::Binary(value) => Json::from(base64::encode(&value)),
+            Datum::String(value) => Json::from(value),
+            Datum::Date(value) => Json::from(Date::from_days(value).to_string()),
+            Datum::Null => Json::Null,
+        }
+    }
+}
+
 pub(crate) unsafe fn write_var_len_value(data: *mut u8, ptr: *const u8, len: usize, cap: usize) {
     debug_assert!(
         len < u32::max_value() as usize,
@@ -12,25 +12,59 @@ use futures::stream::{FuturesOrdered, FuturesUnordered};
 use futures::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 use futures::{future, Async, Future, Poll, Sink, Stream};
 use krpc::Call;
+use parking_lot::Mutex;
 
-use backoff::Backoff;
 use key;
 use operation::{Operation, OperationDecoder, OperationEncoder, OperationError, OperationKind};
 use partition::PartitionKey;
 use pb::tserver::{TabletServerService, WriteRequestPb, WriteResponsePb};
 use replica::{Replica, ReplicaRpc, ReplicaSet, Selection, Speculation};
+use request_id::TrackedRequest;
 use tablet::Tablet;
 use tokio_timer::Delay;
+use util::TimeoutFuture;
 use Client;
 use Error;
 use PartitionSchema;
 use Row;
+use RowPool;
 use Schema;
 use Table;
 use TabletId;
 
+/// Determines when a writer sends buffered operations to tablet servers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlushMode {
+    /// Operations are batched per tablet, and each batch is sent as soon as it is full. Partial
+    /// batches are sent when the writer is flushed.
+    AutoFlushBackground,
+
+    /// Each operation is sent in its own batch as soon as it is applied, and the writer is not
+    /// ready to accept another operation until the previous one completes. Trades throughput for
+    /// the latency of individual writes.
+    ///
+    /// `Writer::poll_ready` returns the result of the previous operation once it completes: it
+    /// fails with the operation's error, such as an `Error::RowError` for an insert of a row
+    /// which already exists, and is otherwise ready. Failed operations are also delivered to the
+    /// writer's error streams.
+    AutoFlushSync,
+
+    /// Operations are only sent when the writer is flushed with `Writer::poll_flush` or
+    /// `Writer::flush`. Once the writer has buffered its maximum amount of data,
+    /// `Writer::poll_ready` fails with `Error::BufferFull` until the writer is flushed.
+    ManualFlush,
+}
+
+/// The maximum number of row data arrays retained by a writer for reuse.
+const MAX_POOLED_ROWS: usize = 1024;
+
 #[derive(Debug, Clone)]
 pub struct WriterConfig {
+    /// When operations are sent to tablet servers.
+    ///
+    /// Defaults to `FlushMode::AutoFlushBackground`.
+    flush_mode: FlushMode,
+
     /// Maximum amount of time to wait for a batch of write operations to be sent to a tablet
     /// server. If the timeout expires before the batch completes, the operations will fail with
     /// `Error::TimedOut`.
@@ -63,20 +97,58 @@ pub struct WriterConfig {
     ///
     /// Defaults to 80. Must be between 0 (exclusive) and 100 (inclusive).
     early_flush_watermark: u8,
+
+    /// Maximum number of attempts made to send a batch, including the initial attempt. Batches
+    /// which keep failing with retriable errors fail with `Error::RetriesExhausted` once the
+    /// attempts are spent.
+    ///
+    /// Defaults to `None`, in which case attempts are only limited by the flush timeout.
+    max_attempts: Option<usize>,
 }
 
 impl Default for WriterConfig {
     fn default() -> WriterConfig {
         WriterConfig {
+            flush_mode: FlushMode::AutoFlushBackground,
             flush_timeout: Duration::from_secs(120),
             max_buffered_data: 256 * 1024 * 1024,
             max_data_per_batch: 7 * 1024 * 1024,
             max_batches_per_tablet: 2,
             early_flush_watermark: 80,
+            max_attempts: None,
         }
     }
 }
 
+impl WriterConfig {
+    /// Sets when operations are sent to tablet servers.
+    pub fn set_flush_mode(mut self, flush_mode: FlushMode) -> WriterConfig {
+        self.flush_mode = flush_mode;
+        self
+    }
+
+    /// Sets the maximum amount of time to wait for a batch of write operations to be written
+    /// after it is sent. Batches which take longer, for instance because a tablet server has
+    /// stopped responding, fail with `Error::TimedOut`.
+    ///
+    /// Defaults to 120 seconds.
+    pub fn set_flush_timeout(mut self, flush_timeout: Duration) -> WriterConfig {
+        self.flush_timeout = flush_timeout;
+        self
+    }
+
+    /// Sets the maximum number of attempts made to send a batch, including the initial attempt.
+    ///
+    /// Batches which fail with a transient error are retried after a backoff period. Transient
+    /// errors include a tablet which is still bootstrapping (`TABLET_NOT_RUNNING`), a replica
+    /// which is not the leader, and an overloaded tablet server. Batches which keep failing with
+    /// transient errors fail with `Error::RetriesExhausted` once the attempts are spent.
+    pub fn set_max_attempts(mut self, max_attempts: usize) -> WriterConfig {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+}
+
 pub struct Writer {
     operations_in_lookup: FuturesOrdered<
         Box<
@@ -93,9 +165,18 @@ pub struct Writer {
     /// Current amount of unflushed data (in-lookup + queued batches + batches in flight).
     buffered_data: usize,
 
+    /// Current number of unflushed operations (in-lookup + queued batches + batches in flight).
+    buffered_operations: usize,
+
     /// Stats for current flush.
     flush_stats: FlushStats,
 
+    /// Data arrays of encoded rows, reused by `Writer::new_row`.
+    row_pool: RowPool,
+
+    /// The error of the previous operation, in `FlushMode::AutoFlushSync` mode.
+    sync_error: Option<Error>,
+
     common: Common,
 }
 
@@ -103,31 +184,43 @@ struct Common {
     config: WriterConfig,
     table: Table,
 
-    batches_in_flight: FuturesUnordered<Box<Future<Item = BatchStats, Error = BatchError> + Send>>,
+    /// Batches which have been sent, each of which completes with its stats and the error of its
+    /// first failed operation, if any.
+    batches_in_flight: FuturesUnordered<
+        Box<Future<Item = (BatchStats, Option<Error>), Error = BatchError> + Send>,
+    >,
 
-    error_sender: UnboundedSender<OperationError>,
-    #[allow(dead_code)] // TODO: expose this in a nice API so applications can handle row errors.
-    error_receiver: UnboundedReceiver<OperationError>,
+    error_subscribers: ErrorSubscribers,
 }
 
 impl Writer {
     pub(crate) fn new(table: Table, config: WriterConfig) -> Writer {
-        let (error_sender, error_receiver) = mpsc::unbounded();
         Writer {
             operations_in_lookup: FuturesOrdered::new(),
             batchers: HashMap::new(),
             buffered_data: 0,
+            buffered_operations: 0,
             flush_stats: FlushStats::new(),
+            row_pool: RowPool::new(table.schema().clone(), MAX_POOLED_ROWS),
+            sync_error: None,
             common: Common {
                 config,
                 table,
                 batches_in_flight: FuturesUnordered::new(),
-                error_sender,
-                error_receiver,
+                error_subscribers: ErrorSubscribers::default(),
             },
         }
     }
 
+    /// Returns an empty row of the table's schema.
+    ///
+    /// Once a row created by this method has been applied to the writer and encoded into a
+    /// batch, its storage is reused for subsequently created rows, which avoids an allocation per
+    /// row when writing at high throughput.
+    pub fn new_row(&mut self) -> Row<'static> {
+        self.row_pool.new_row()
+    }
+
     pub fn poll_ready(&mut self) -> Poll<(), Error> {
         self.poll_operations_in_lookup()?;
 
@@ -135,7 +228,24 @@ impl Writer {
 
         // TODO: figure out if the amount of data in batches is over the early flush watermark.
 
+        if self.common.config.flush_mode == FlushMode::AutoFlushSync {
+            if !(self.operations_in_lookup.is_empty() && self.common.batches_in_flight.is_empty())
+            {
+                // Wait for the previous operation to complete.
+                return Ok(Async::NotReady);
+            }
+            if let Some(error) = self.sync_error.take() {
+                return Err(error);
+            }
+        }
+
         if self.buffered_data >= self.common.config.max_buffered_data {
+            if self.common.config.flush_mode == FlushMode::ManualFlush
+                && self.common.batches_in_flight.is_empty()
+            {
+                // Nothing is sent until the writer is flushed, so the buffer will never drain.
+                return Err(Error::BufferFull);
+            }
             Ok(Async::NotReady)
         } else {
             Ok(Async::Ready(()))
@@ -163,11 +273,36 @@ impl Writer {
         }
     }
 
+    /// Returns the number of operations which have been applied to the writer, but not yet
+    /// acknowledged by a tablet server. Includes operations waiting for a tablet location lookup,
+    /// operations in unsent batches, and operations in batches which are in flight.
+    pub fn buffered_operations(&self) -> usize {
+        self.buffered_operations
+    }
+
+    /// Returns the encoded size of the operations counted by `buffered_operations`. The writer
+    /// stops accepting operations once this reaches the configured maximum amount of buffered
+    /// data.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_data
+    }
+
+    /// Returns a stream of the errors of individual row operations applied to the writer, such
+    /// as inserts of rows which already exist.
+    ///
+    /// Errors are delivered as soon as the failed batch completes, without waiting for the
+    /// writer to be flushed. Each stream receives the errors which occur after it is created.
+    /// The stream ends once the writer, and all of its in-flight batches, have been dropped.
+    pub fn error_stream(&self) -> impl Stream<Item = OperationError, Error = ()> {
+        self.common.error_subscribers.subscribe()
+    }
+
     pub fn flush(self) -> Flush {
         Flush { writer: Some(self) }
     }
 
-    pub fn apply(&mut self, op: Operation) {
+    pub fn apply(&mut self, mut op: Operation) {
+        self.sync_error = None;
         if op.row.schema() != self.common.table.schema() {
             self.fail_operation(
                 op,
@@ -178,6 +313,26 @@ impl Writer {
             return;
         }
 
+        if let Err(error) = check_lengths(&op.row) {
+            self.fail_operation(op, error);
+            return;
+        }
+
+        // Upserts may set immutable columns, since upserts of new rows are inserts. The tablet
+        // server rejects upserts which set an immutable column of an existing row.
+        if op.kind == OperationKind::Update || op.kind == OperationKind::UpdateIgnore {
+            if let Some(column) = immutable_column(&op.row) {
+                self.fail_operation(op, Error::Immutable { column });
+                return;
+            }
+        }
+
+        // Deletes are identified entirely by the primary key, so values for other columns are
+        // dropped rather than sent to the tablet server, which would reject them.
+        if op.kind == OperationKind::Delete || op.kind == OperationKind::DeleteIgnore {
+            unset_non_key_columns(&mut op.row);
+        }
+
         let encoded_len = OperationEncoder::encoded_len(&op.row);
 
         // Sanity check: if the operation is bigger than the max batch data size,
@@ -193,6 +348,7 @@ impl Writer {
         }
 
         self.buffered_data += encoded_len;
+        self.buffered_operations += 1;
 
         // TODO: encode the partition key into a cached buffer in order to avoid allocating for
         // every insert.
@@ -200,7 +356,7 @@ impl Writer {
             match key::encode_partition_key(self.common.table.partition_schema(), &op.row) {
                 Ok(partition_key) => partition_key,
                 Err(error) => {
-                    self.fail_operation(op, error);
+                    self.fail_buffered_operation(op, encoded_len, error);
                     return;
                 }
             };
@@ -213,8 +369,13 @@ impl Writer {
         };
 
         match poll {
-            Ok(Async::Ready(Some(tablet))) => self.buffer_operation(tablet, &op, encoded_len),
-            Ok(Async::Ready(None)) => self.fail_operation(op, Error::NoRangePartition),
+            Ok(Async::Ready(Some(tablet))) => {
+                self.buffer_operation(tablet, &op, encoded_len);
+                self.row_pool.recycle(op.row);
+            }
+            Ok(Async::Ready(None)) => {
+                self.fail_buffered_operation(op, encoded_len, Error::NoRangePartition)
+            }
             Ok(Async::NotReady) => {
                 let op = op.into_owned();
                 let operation_in_lookup = Box::new(tablet.then(move |result| match result {
@@ -223,7 +384,7 @@ impl Writer {
                 }));
                 self.operations_in_lookup.push(operation_in_lookup);
             }
-            Err(error) => self.fail_operation(op, error),
+            Err(error) => self.fail_buffered_operation(op, encoded_len, error),
         }
     }
 
@@ -256,6 +417,15 @@ impl Writer {
         }
     }
 
+    /// Inserts the row, unless a row with the same primary key already exists, in which case the
+    /// operation is dropped without producing a row error. Requires Kudu 1.14 or later.
+    pub fn insert_ignore(&mut self, row: Row) {
+        self.apply(Operation {
+            row,
+            kind: OperationKind::InsertIgnore,
+        })
+    }
+
     pub fn update(&mut self, row: Row) {
         self.apply(Operation {
             row,
@@ -263,6 +433,15 @@ impl Writer {
         })
     }
 
+    /// Updates the row, unless no row with the same primary key exists, in which case the
+    /// operation is dropped without producing a row error. Requires Kudu 1.14 or later.
+    pub fn update_ignore(&mut self, row: Row) {
+        self.apply(Operation {
+            row,
+            kind: OperationKind::UpdateIgnore,
+        })
+    }
+
     pub fn update_all<'data, I>(self, rows: I) -> WriteAll<I::IntoIter>
     where
         I: IntoIterator<Item = Row<'data>>,
@@ -274,6 +453,10 @@ impl Writer {
         }
     }
 
+    /// Deletes the row with the same primary key.
+    ///
+    /// Only the primary key columns of the row are used; any other columns which are set are
+    /// ignored, so a full row (for instance, one read from a scan) may be passed.
     pub fn delete(&mut self, row: Row) {
         self.apply(Operation {
             row,
@@ -281,6 +464,17 @@ impl Writer {
         })
     }
 
+    /// Deletes the row, unless no row with the same primary key exists, in which case the
+    /// operation is dropped without producing a row error. Requires Kudu 1.14 or later.
+    ///
+    /// As with `delete`, only the primary key columns of the row are used.
+    pub fn delete_ignore(&mut self, row: Row) {
+        self.apply(Operation {
+            row,
+            kind: OperationKind::DeleteIgnore,
+        })
+    }
+
     pub fn delete_all<'data, I>(self, rows: I) -> WriteAll<I::IntoIter>
     where
         I: IntoIterator<Item = Row<'data>>,
@@ -296,14 +490,18 @@ impl Writer {
         loop {
             match self.operations_in_lookup.poll() {
                 Ok(Async::Ready(Some((Some(tablet), op, encoded_len)))) => {
-                    self.buffer_operation(tablet, &op, encoded_len)
+                    self.buffer_operation(tablet, &op, encoded_len);
+                    self.row_pool.recycle(op.row);
                 }
-                Ok(Async::Ready(Some((None, op, _)))) => {
-                    self.fail_operation(op, Error::NoRangePartition)
+                Ok(Async::Ready(Some((None, op, encoded_len)))) => {
+                    self.fail_buffered_operation(op, encoded_len, Error::NoRangePartition)
                 }
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
-                Err((op, error)) => self.fail_operation(op, error),
+                Err((op, error)) => {
+                    let encoded_len = OperationEncoder::encoded_len(&op.row);
+                    self.fail_buffered_operation(op, encoded_len, error)
+                }
             }
         }
     }
@@ -316,16 +514,25 @@ impl Writer {
         );
         loop {
             match self.common.batches_in_flight.poll() {
-                Ok(Async::Ready(Some(stats))) => {
+                Ok(Async::Ready(Some((stats, row_error)))) => {
+                    if self.common.config.flush_mode == FlushMode::AutoFlushSync {
+                        self.sync_error = row_error;
+                    }
                     self.buffered_data -= stats.data;
+                    self.buffered_operations -= stats.operations;
                     self.flush_stats.add_succesful_batch(&stats);
+                    self.common
+                        .table
+                        .table_locations()
+                        .counters()
+                        .add_rows_written(stats.operations - stats.row_errors);
 
                     match self.batchers.entry(stats.tablet) {
                         Entry::Occupied(ref mut entry) => {
                             entry.get_mut().batches_in_flight -= 1;
                             if flush_batches {
                                 entry.get_mut().flush(&mut self.common, false);
-                            } else {
+                            } else if self.common.config.flush_mode != FlushMode::ManualFlush {
                                 entry.get_mut().send_batches(&mut self.common);
                             }
                         }
@@ -337,6 +544,7 @@ impl Writer {
                 Err(BatchError { call, stats, error }) => {
                     // TODO: handle recoverable errors here.
                     self.buffered_data -= stats.data;
+                    self.buffered_operations -= stats.operations;
                     self.flush_stats.failed_batches += 1;
                     return Err(error);
                 }
@@ -361,15 +569,34 @@ impl Writer {
         // Overwrite the tablet in case it's been updated.
         batcher.tablet = tablet;
 
+        let flush_mode = self.common.config.flush_mode;
         if batcher.batch.encoder.len() + encoded_len > self.common.config.max_data_per_batch {
-            batcher.flush(&mut self.common, true);
+            if flush_mode == FlushMode::ManualFlush {
+                batcher.roll();
+            } else {
+                batcher.flush(&mut self.common, true);
+            }
         }
-        batcher.batch.encoder.encode_row(op.kind.as_pb(), &op.row);
+        batcher.batch.encoder.encode_operation(op.kind, &op.row);
         batcher.batch.operations += 1;
+
+        if flush_mode == FlushMode::AutoFlushSync {
+            batcher.flush(&mut self.common, true);
+        }
+    }
+
+    /// Fails an operation which has already been counted as buffered.
+    fn fail_buffered_operation(&mut self, operation: Operation, encoded_len: usize, error: Error) {
+        self.buffered_data -= encoded_len;
+        self.buffered_operations -= 1;
+        self.fail_operation(operation, error);
     }
 
-    fn fail_operation(&self, operation: Operation, error: Error) {
-        let _ = self.common.error_sender.unbounded_send(OperationError {
+    fn fail_operation(&mut self, operation: Operation, error: Error) {
+        if self.common.config.flush_mode == FlushMode::AutoFlushSync {
+            self.sync_error = Some(error.clone());
+        }
+        self.common.error_subscribers.send(OperationError {
             row: operation.row.into_owned(),
             kind: operation.kind,
             error,
@@ -377,6 +604,49 @@ impl Writer {
     }
 }
 
+/// Returns the name of the first immutable column set in the row, if any. Key columns are
+/// skipped, since they identify the row being updated rather than changing it.
+fn immutable_column(row: &Row) -> Option<String> {
+    let schema = row.schema();
+    schema
+        .columns()
+        .iter()
+        .enumerate()
+        .skip(schema.num_primary_key_columns())
+        .find(|&(idx, column)| column.is_immutable() && row.is_set(idx).unwrap_or(false))
+        .map(|(_, column)| column.name().to_owned())
+}
+
+/// Unsets all non-primary-key columns in the row.
+fn unset_non_key_columns(row: &mut Row) {
+    let num_columns = row.schema().columns().len();
+    for idx in row.schema().num_primary_key_columns()..num_columns {
+        if row.is_set(idx).unwrap_or(false) {
+            row.unset(idx).unwrap();
+        }
+    }
+}
+
+/// Checks that the row's values for `VARCHAR` columns do not exceed the column lengths.
+fn check_lengths(row: &Row) -> Result<(), Error> {
+    for (idx, column) in row.schema().columns().iter().enumerate() {
+        let length = match column.length() {
+            Some(length) if row.is_set(idx)? => length,
+            _ => continue,
+        };
+        if let Some(value) = row.get::<_, Option<&str>>(idx)? {
+            if value.chars().count() > usize::from(length) {
+                return Err(Error::InvalidArgument(format!(
+                    "value for column {} is longer than the column length {}",
+                    column.name(),
+                    length
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
 impl fmt::Debug for Writer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Writer")
@@ -456,6 +726,14 @@ impl TabletBatcher {
         }
     }
 
+    /// Moves the currently active batch to the queue of batches waiting to be sent.
+    fn roll(&mut self) {
+        if !self.batch.is_empty() {
+            let batch = mem::replace(&mut self.batch, Batch::new());
+            self.batch_queue.push_back(batch);
+        }
+    }
+
     /// Rolls the currently active batch, and sends it to the remote tablet server if the max
     /// batches in-flight limit has not been reached for the tablet.
     fn flush(&mut self, common: &mut Common, force: bool) {
@@ -525,26 +803,37 @@ impl Batch {
         request.schema = Some(common.table.schema().as_pb());
         //request.propagated_timestamp = Some(self.client().latest_observed_timestamp());
         request.row_operations = Some(self.encoder.into_pb());
-        let call1 = TabletServerService::write(
-            Arc::new(request),
-            Instant::now() + common.config.flush_timeout,
-        );
+        let deadline = Instant::now() + common.config.flush_timeout;
+        // The request ID is retained until the batch completes, so that tablet servers apply the
+        // batch exactly once, however many times it is retried.
+        let tracked_request = TrackedRequest::new(common.table.table_locations().request_ids());
+        let mut call1 = TabletServerService::write(Arc::new(request), deadline);
+        call1.set_request_id(tracked_request.request_id().clone());
         let call2 = call1.clone();
         let call3 = call1.clone();
 
         let schema = common.table.schema().clone();
-        let error_sender = common.error_sender.clone();
+        let error_subscribers = common.error_subscribers.clone();
 
+        // The RPC deadline is not enforced against tablet servers which stop responding after the
+        // request is sent, so the batch is additionally bounded by a timer.
+        let rpc = ReplicaRpc::new(
+            tablet,
+            call1,
+            Speculation::Staggered(Duration::from_millis(100)),
+            Selection::Leader,
+            common
+                .table
+                .table_locations()
+                .backoff()
+                .with_max_attempts(common.config.max_attempts),
+        );
         common.batches_in_flight.push(Box::new(
-            ReplicaRpc::new(
-                tablet,
-                call1,
-                Speculation::Staggered(Duration::from_millis(100)),
-                Selection::Leader,
-                Backoff::default(),
-            ).and_then(move |(_, response, _)| {
+            TimeoutFuture::new(rpc, deadline).and_then(move |(_, response, _)| {
+                drop(tracked_request);
                 assert!(response.error.is_none());
                 let row_errors = response.per_row_errors.len();
+                let mut row_error = None;
                 if row_errors != 0 {
                     debug!("row_errors: {:?}", response.per_row_errors);
                     let row_operations = call2.request.row_operations.as_ref().unwrap();
@@ -575,16 +864,20 @@ impl Batch {
                             kind: operation.kind,
                             error: Error::RowError(error.error.into()),
                         };
+                        if row_error.is_none() {
+                            row_error = Some(error.error.clone());
+                        }
 
-                        if error_sender.unbounded_send(error).is_err() {
-                            // The receiver has been dropped, so no point in sending additional errors.
+                        if !error_subscribers.send(error) {
+                            // There are no error streams, so no point in decoding additional
+                            // errors.
                             break;
                         }
                     }
                 }
 
                 stats.row_errors = row_errors;
-                Ok(stats)
+                Ok((stats, row_error))
             }).map_err(move |error| BatchError {
                 call: call3,
                 stats,
@@ -594,6 +887,28 @@ impl Batch {
     }
 }
 
+/// The senders of the streams returned by `Writer::error_stream`.
+#[derive(Clone, Default)]
+struct ErrorSubscribers {
+    senders: Arc<Mutex<Vec<UnboundedSender<OperationError>>>>,
+}
+
+impl ErrorSubscribers {
+    fn subscribe(&self) -> UnboundedReceiver<OperationError> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.senders.lock().push(sender);
+        receiver
+    }
+
+    /// Sends the error to every subscriber, and forgets subscribers whose streams have been
+    /// dropped. Returns `false` if there are no remaining subscribers.
+    fn send(&self, error: OperationError) -> bool {
+        let mut senders = self.senders.lock();
+        senders.retain(|sender| sender.unbounded_send(error.clone()).is_ok());
+        !senders.is_empty()
+    }
+}
+
 #[derive(Clone, Copy)]
 struct BatchStats {
     tablet: TabletId,
@@ -665,22 +980,66 @@ impl FlushStats {
     }
 }
 
+/// The outcome of a `Table::bulk_insert`.
+#[derive(Clone, Debug)]
+pub struct BulkLoadReport {
+    rows_inserted: usize,
+    errors: Vec<OperationError>,
+}
+
+impl BulkLoadReport {
+    pub(crate) fn new(rows_inserted: usize, errors: Vec<OperationError>) -> BulkLoadReport {
+        BulkLoadReport {
+            rows_inserted,
+            errors,
+        }
+    }
+
+    /// Returns the number of rows which were inserted.
+    pub fn rows_inserted(&self) -> usize {
+        self.rows_inserted
+    }
+
+    /// Returns the number of rows which failed to be inserted.
+    pub fn rows_failed(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns the rows which failed to be inserted, along with their errors.
+    pub fn errors(&self) -> &[OperationError] {
+        &self.errors
+    }
+}
+
 #[cfg(test)]
 mod test {
 
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicBool;
     use std::time::{Duration, Instant};
 
     use super::*;
+    use krpc;
     use mini_cluster::{MiniCluster, MiniClusterConfig};
+    use pb::tserver::TabletServerErrorPb;
+    use pb::AppStatusPb;
+    use rpc_proxy::{Action, RpcProxy};
+    use tablet::TabletReplica;
     use Client;
     use Column;
     use DataType;
+    use HostPort;
     use Options;
+    use RowBatch;
+    use StatusCode;
     use SchemaBuilder;
+    use RpcErrorCode;
     use TableBuilder;
+    use TabletServerErrorCode;
 
     use env_logger;
-    use futures::future;
+    use futures::{future, Stream};
+    use prost::Message;
     use tokio::runtime::current_thread::Runtime;
 
     #[test]
@@ -751,4 +1110,1083 @@ mod test {
         assert_eq!(stats.row_errors, 1);
         assert_eq!(stats.data, 128);
     }
+
+    #[test]
+    fn buffered_counts() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "buffered_counts");
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        assert_eq!(0, writer.buffered_operations());
+        assert_eq!(0, writer.buffered_bytes());
+
+        let mut bytes = 0;
+        for key in 0..3 {
+            runtime
+                .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                    insert(&mut writer, &table, key);
+                    Ok(())
+                })).unwrap();
+            assert_eq!(key as usize + 1, writer.buffered_operations());
+            assert!(writer.buffered_bytes() > bytes);
+            bytes = writer.buffered_bytes();
+        }
+
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(0, writer.buffered_operations());
+        assert_eq!(0, writer.buffered_bytes());
+    }
+
+    #[test]
+    fn row_reuse() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "row_reuse");
+
+        let mut writer = table.new_writer(WriterConfig::default());
+
+        // Warm up the meta cache, so that rows are encoded as soon as they are applied.
+        runtime
+            .block_on(table.table_locations().tablet(&[]))
+            .unwrap();
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                let mut data = None;
+                for key in 0..100i32 {
+                    let mut row = writer.new_row();
+                    if let Some(data) = data {
+                        assert_eq!(data, row.data());
+                    }
+                    data = Some(row.data());
+                    row.set("key", key).unwrap();
+                    row.set("val", key).unwrap();
+                    writer.insert(row);
+                    assert_eq!(1, writer.row_pool.len());
+                }
+                Ok(())
+            })).unwrap();
+
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(100, count_rows(&mut runtime, &table));
+    }
+
+    #[test]
+    fn error_stream() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "error_stream");
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        let errors = writer.error_stream();
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                insert(&mut writer, &table, 1);
+                insert(&mut writer, &table, 2);
+                insert(&mut writer, &table, 1);
+                Ok(())
+            })).unwrap();
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(1, stats.row_errors);
+
+        // The stream ends once the writer is dropped.
+        drop(writer);
+        let errors = runtime.block_on(errors.collect()).unwrap();
+        assert_eq!(1, errors.len());
+        assert_eq!(OperationKind::Insert, errors[0].kind);
+        assert_eq!(1, errors[0].row.get::<_, i32>("key").unwrap());
+        match errors[0].error {
+            Error::RowError(ref status) => assert_eq!(StatusCode::AlreadyPresent, status.code()),
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn update_immutable_column() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32).set_immutable(true))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+        let mut table_builder = TableBuilder::new("update_immutable_column", schema);
+        table_builder.set_num_replicas(1);
+        let table_id = runtime.block_on(client.create_table(table_builder)).unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+        assert!(table.schema().column_by_name("val").unwrap().is_immutable());
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        let errors = writer.error_stream();
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                insert(&mut writer, &table, 1);
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for &kind in &[OperationKind::Update, OperationKind::UpdateIgnore] {
+                    let mut row = table.schema().new_row();
+                    row.set("key", 1i32).unwrap();
+                    row.set("val", 2i32).unwrap();
+                    writer.apply(Operation { row, kind });
+                }
+                Ok(())
+            })).unwrap();
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(0, stats.operations);
+
+        // An upsert of a new row inserts it, immutable column included, while the tablet server
+        // rejects an upsert which sets the immutable column of an existing row.
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for &key in &[2i32, 1] {
+                    let mut row = table.schema().new_row();
+                    row.set("key", key).unwrap();
+                    row.set("val", 2i32).unwrap();
+                    writer.apply(Operation {
+                        row,
+                        kind: OperationKind::Upsert,
+                    });
+                }
+                Ok(())
+            })).unwrap();
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(2, stats.operations);
+        assert_eq!(1, stats.row_errors);
+        assert_eq!(2, count_rows(&mut runtime, &table));
+
+        drop(writer);
+        let errors = runtime.block_on(errors.collect()).unwrap();
+        assert_eq!(
+            vec![
+                OperationKind::Update,
+                OperationKind::UpdateIgnore,
+                OperationKind::Upsert,
+            ],
+            errors.iter().map(|error| error.kind).collect::<Vec<_>>()
+        );
+        for error in &errors[..2] {
+            match error.error {
+                Error::Immutable { ref column } => assert_eq!("val", column),
+                ref other => panic!("unexpected error: {:?}", other),
+            }
+        }
+        assert_eq!(1, errors[2].row.get::<_, i32>("key").unwrap());
+        match errors[2].error {
+            Error::RowError(_) => (),
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn varchar_length() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String).set_length(4))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+        let mut table_builder = TableBuilder::new("varchar_length", schema);
+        table_builder.set_num_replicas(1);
+        let table_id = runtime.block_on(client.create_table(table_builder)).unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+        let column = table.schema().column_by_name("val").unwrap();
+        assert_eq!(DataType::String, column.data_type());
+        assert_eq!(Some(4), column.length());
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        let errors = writer.error_stream();
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                // Lengths are measured in characters, not bytes.
+                for (key, val) in vec![(1i32, "abcd"), (2, "ab\u{e9}d"), (3, "abcde")] {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", key).unwrap();
+                    insert.set("val", val).unwrap();
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(2, stats.operations);
+        assert_eq!(0, stats.row_errors);
+
+        drop(writer);
+        let errors = runtime.block_on(errors.collect()).unwrap();
+        assert_eq!(1, errors.len());
+        assert_eq!(3, errors[0].row.get::<_, i32>("key").unwrap());
+        match errors[0].error {
+            Error::InvalidArgument(_) => (),
+            ref other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn insert_ignore() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("insert_ignore", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let mut writer = table.new_writer(WriterConfig::default());
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for val in 0..2i32 {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", 1i32).unwrap();
+                    insert.set("val", val).unwrap();
+                    writer.insert_ignore(insert);
+                }
+                Ok(())
+            })).unwrap();
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(stats.failed_batches, 0);
+        assert_eq!(stats.operations, 2);
+        assert_eq!(stats.row_errors, 0);
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        let rows = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| row.get::<_, i32>("val").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0], rows);
+    }
+
+    #[test]
+    fn update_and_delete_ignore() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("update_and_delete_ignore", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let mut writer = table.new_writer(WriterConfig::default());
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                let mut insert = table.schema().new_row();
+                insert.set("key", 1i32).unwrap();
+                insert.set("val", 1i32).unwrap();
+                writer.insert(insert);
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                // Update an existing key, and a missing key.
+                for &key in &[1i32, 2] {
+                    let mut update = table.schema().new_row();
+                    update.set("key", key).unwrap();
+                    update.set("val", 10i32).unwrap();
+                    writer.update_ignore(update);
+                }
+
+                // Delete a missing key.
+                let mut delete = table.schema().new_row();
+                delete.set("key", 3i32).unwrap();
+                writer.delete_ignore(delete);
+                Ok(())
+            })).unwrap();
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(stats.failed_batches, 0);
+        assert_eq!(stats.operations, 3);
+        assert_eq!(stats.row_errors, 0);
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        let rows = batches
+            .iter()
+            .flat_map(|batch| batch.into_iter())
+            .map(|row| {
+                (
+                    row.get::<_, i32>("key").unwrap(),
+                    row.get::<_, i32>("val").unwrap(),
+                )
+            }).collect::<Vec<_>>();
+        assert_eq!(vec![(1, 10)], rows);
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                let mut delete = table.schema().new_row();
+                delete.set("key", 1i32).unwrap();
+                writer.delete_ignore(delete);
+                Ok(())
+            })).unwrap();
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(stats.row_errors, 0);
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        assert!(batches.iter().all(|batch| batch.into_iter().next().is_none()));
+    }
+
+    #[test]
+    fn delete_full_row() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::String))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("delete_full_row", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 2);
+        table_builder.set_num_replicas(1);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        let mut writer = table.new_writer(WriterConfig::default());
+
+        let full_row = |key: i32| {
+            let mut row = table.schema().new_row();
+            row.set("key", key).unwrap();
+            row.set("val", format!("val-{}", key)).unwrap();
+            row
+        };
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for key in 0..10 {
+                    writer.insert(full_row(key));
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+
+        // Delete using the same fully-populated rows that were inserted.
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for key in 0..5 {
+                    writer.delete(full_row(key));
+                }
+                for key in 5..10 {
+                    writer.delete_ignore(full_row(key));
+                }
+                Ok(())
+            })).unwrap();
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(stats.failed_batches, 0);
+        assert_eq!(stats.operations, 10);
+        assert_eq!(stats.row_errors, 0);
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        assert!(batches.iter().all(|batch| batch.into_iter().next().is_none()));
+    }
+
+    #[test]
+    fn bulk_insert() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "bulk_insert");
+
+        let rows = (0..1000).map(|key| {
+            let mut row = table.schema().new_row();
+            row.set("key", key).unwrap();
+            row.set("val", key).unwrap();
+            row
+        });
+        let report = runtime
+            .block_on(future::lazy(|| table.bulk_insert(rows)))
+            .unwrap();
+        assert_eq!(1000, report.rows_inserted());
+        assert_eq!(0, report.rows_failed());
+        assert_eq!(1000, count_rows(&mut runtime, &table));
+
+        // Rows which already exist are reported as failures.
+        let rows = (990..1010).map(|key| {
+            let mut row = table.schema().new_row();
+            row.set("key", key).unwrap();
+            row
+        });
+        let report = runtime
+            .block_on(future::lazy(|| table.bulk_insert(rows)))
+            .unwrap();
+        assert_eq!(10, report.rows_inserted());
+        assert_eq!(10, report.rows_failed());
+        for error in report.errors() {
+            assert_eq!(OperationKind::Insert, error.kind);
+            assert!(error.row.get::<_, i32>("key").unwrap() < 1000);
+        }
+        assert_eq!(1010, count_rows(&mut runtime, &table));
+    }
+
+    fn create_flush_mode_table(runtime: &mut Runtime, client: &mut Client, name: &str) -> Table {
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new(name, schema);
+        table_builder.set_num_replicas(1);
+        let table_id = runtime.block_on(client.create_table(table_builder)).unwrap();
+        runtime.block_on(client.open_table_by_id(table_id)).unwrap()
+    }
+
+    fn count_rows(runtime: &mut Runtime, table: &Table) -> usize {
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        batches.iter().map(|batch| batch.into_iter().count()).sum()
+    }
+
+    fn insert(writer: &mut Writer, table: &Table, key: i32) {
+        let mut insert = table.schema().new_row();
+        insert.set("key", key).unwrap();
+        insert.set("val", key).unwrap();
+        writer.insert(insert);
+    }
+
+    #[test]
+    fn auto_flush_background() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "auto_flush_background");
+
+        let mut config = WriterConfig::default();
+        assert_eq!(FlushMode::AutoFlushBackground, config.flush_mode);
+        // Fit roughly ten operations in a batch.
+        config.max_data_per_batch = 100;
+        let mut writer = table.new_writer(config);
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for key in 0..50 {
+                    insert(&mut writer, &table, key);
+                }
+                Ok(())
+            })).unwrap();
+
+        // Full batches are sent without waiting for a flush.
+        runtime
+            .block_on(future::poll_fn(|| -> Poll<(), Error> {
+                try_ready!(writer.poll_ready());
+                if writer.flush_stats.successful_batches == 0 {
+                    ::futures::task::current().notify();
+                    return Ok(Async::NotReady);
+                }
+                Ok(Async::Ready(()))
+            })).unwrap();
+        assert!(count_rows(&mut runtime, &table) > 0);
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(0, stats.failed_batches());
+        assert_eq!(50, count_rows(&mut runtime, &table));
+    }
+
+    #[test]
+    fn auto_flush_sync() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "auto_flush_sync");
+
+        let config = WriterConfig::default().set_flush_mode(FlushMode::AutoFlushSync);
+        let mut writer = table.new_writer(config);
+
+        for key in 0..3 {
+            runtime
+                .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                    insert(&mut writer, &table, key);
+                    Ok(())
+                })).unwrap();
+
+            // The writer becomes ready once the operation has been written, without a flush.
+            runtime
+                .block_on(future::poll_fn(|| writer.poll_ready()))
+                .unwrap();
+            assert_eq!(key as usize + 1, count_rows(&mut runtime, &table));
+        }
+
+        // The writer returns the error of a failed operation once it completes.
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                insert(&mut writer, &table, 0);
+                Ok(())
+            })).unwrap();
+        match runtime.block_on(future::poll_fn(|| writer.poll_ready())) {
+            Err(Error::RowError(_)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        // The error doesn't carry over to the next operation.
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                insert(&mut writer, &table, 3);
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_ready()))
+            .unwrap();
+        assert_eq!(4, count_rows(&mut runtime, &table));
+
+        // Each operation was sent in its own batch.
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(5, stats.successful_batches());
+        assert_eq!(5, stats.operations());
+        assert_eq!(1, stats.failed_operations());
+    }
+
+    #[test]
+    fn shared_request_id_generator() {
+        use RequestId;
+        use RequestIdGenerator;
+
+        /// Identifies every operation by the same client ID, as a tracing generator might.
+        struct ConstantRequestIds;
+
+        impl RequestIdGenerator for ConstantRequestIds {
+            fn next_request_id(&self) -> RequestId {
+                RequestId {
+                    client_id: "shared".to_owned(),
+                    seq_no: 0,
+                    first_incomplete_seq_no: 0,
+                    attempt_no: 0,
+                }
+            }
+        }
+
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let generator = Arc::new(ConstantRequestIds);
+
+        // Each client writes a row with the first write sequence number of its own. Tablet
+        // servers would drop the second write as a retry of the first if the clients shared a
+        // write client ID.
+        let mut tables = Vec::new();
+        for _ in 0..2 {
+            let mut options = Options::default();
+            options.set_request_id_generator(generator.clone());
+            let mut client = runtime
+                .block_on(Client::new(cluster.master_addrs(), options))
+                .expect("client");
+            if tables.is_empty() {
+                create_flush_mode_table(&mut runtime, &mut client, "shared_request_id_generator");
+            }
+            let table = runtime
+                .block_on(client.open_table("shared_request_id_generator"))
+                .unwrap();
+            tables.push(table);
+        }
+
+        for (key, table) in tables.iter().enumerate() {
+            let mut writer = table.new_writer(WriterConfig::default());
+            runtime
+                .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                    insert(&mut writer, table, key as i32);
+                    Ok(())
+                })).unwrap();
+            let stats = runtime
+                .block_on(future::poll_fn(|| writer.poll_flush()))
+                .unwrap();
+            assert_eq!(1, stats.successful_operations());
+        }
+        assert_eq!(2, count_rows(&mut runtime, &tables[0]));
+    }
+
+    #[test]
+    fn manual_flush() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "manual_flush");
+
+        let mut config = WriterConfig::default().set_flush_mode(FlushMode::ManualFlush);
+        config.max_data_per_batch = 100;
+        let mut writer = table.new_writer(config);
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for key in 0..50 {
+                    insert(&mut writer, &table, key);
+                }
+                Ok(())
+            })).unwrap();
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_ready()))
+            .unwrap();
+
+        // Nothing is sent until the writer is flushed, even though batches are full.
+        assert_eq!(0, count_rows(&mut runtime, &table));
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert!(stats.successful_batches() > 1);
+        assert_eq!(50, stats.operations());
+        assert_eq!(50, count_rows(&mut runtime, &table));
+    }
+
+    #[test]
+    fn manual_flush_buffer_full() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "manual_flush_buffer_full");
+
+        let mut config = WriterConfig::default().set_flush_mode(FlushMode::ManualFlush);
+        config.max_buffered_data = 100;
+        let mut writer = table.new_writer(config);
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for key in 0..50 {
+                    insert(&mut writer, &table, key);
+                }
+                Ok(())
+            })).unwrap();
+
+        // The buffer can only drain by flushing, so the writer fails rather than waiting forever.
+        match runtime.block_on(future::poll_fn(|| writer.poll_ready())) {
+            Err(Error::BufferFull) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(50, stats.operations());
+        assert_eq!(50, count_rows(&mut runtime, &table));
+        runtime
+            .block_on(future::poll_fn(|| writer.poll_ready()))
+            .unwrap();
+    }
+
+    #[test]
+    fn flush_timeout() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "flush_timeout");
+
+        let tablet = runtime
+            .block_on(table.table_locations().tablet(&[]))
+            .unwrap()
+            .unwrap();
+
+        // A tablet server which accepts connections, but never responds.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = HostPort::from(listener.local_addr().unwrap());
+
+        let config = WriterConfig::default().set_flush_timeout(Duration::from_millis(500));
+        let mut writer = table.new_writer(config);
+
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                let unresponsive = Arc::new(Tablet {
+                    id: tablet.id(),
+                    lower_bound: tablet.lower_bound().clone(),
+                    upper_bound: tablet.upper_bound().clone(),
+                    replicas: vec![TabletReplica {
+                        id: tablet.replicas[0].id,
+                        rpc_addrs: vec![addr.clone()].into_boxed_slice(),
+                        proxy: krpc::Proxy::spawn(
+                            vec![addr].into_boxed_slice(),
+                            krpc::Options::default(),
+                        ),
+                        is_leader: AtomicBool::new(true),
+                        is_stale: AtomicBool::new(false),
+                    }],
+                    deadline: tablet.deadline,
+                    is_invalidated: AtomicBool::new(false),
+                });
+
+                let mut row = table.schema().new_row();
+                row.set("key", 1i32).unwrap();
+                let op = Operation {
+                    row,
+                    kind: OperationKind::Insert,
+                };
+                let encoded_len = OperationEncoder::encoded_len(&op.row);
+                writer.buffered_data += encoded_len;
+                writer.buffered_operations += 1;
+                writer.buffer_operation(unresponsive, &op, encoded_len);
+                Ok(())
+            })).unwrap();
+
+        let start = Instant::now();
+        match runtime.block_on(future::poll_fn(|| writer.poll_flush())) {
+            Err(Error::TimedOut) => (),
+            other => panic!("expected flush to time out: {:?}", other),
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(500), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "{:?}", elapsed);
+        drop(listener);
+    }
+
+    /// Buffers an insert of a row with the key to the tablet, sending it to the tablet's leader
+    /// through the address rather than directly.
+    fn buffer_insert_through(
+        runtime: &mut Runtime,
+        writer: &mut Writer,
+        tablet: &Tablet,
+        addr: HostPort,
+        key: i32,
+    ) {
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                let proxied = Arc::new(Tablet {
+                    id: tablet.id(),
+                    lower_bound: tablet.lower_bound().clone(),
+                    upper_bound: tablet.upper_bound().clone(),
+                    replicas: vec![TabletReplica {
+                        id: tablet.replicas[0].id,
+                        rpc_addrs: vec![addr.clone()].into_boxed_slice(),
+                        proxy: krpc::Proxy::spawn(
+                            vec![addr].into_boxed_slice(),
+                            krpc::Options::default(),
+                        ),
+                        is_leader: AtomicBool::new(true),
+                        is_stale: AtomicBool::new(false),
+                    }],
+                    deadline: tablet.deadline,
+                    is_invalidated: AtomicBool::new(false),
+                });
+
+                let mut row = writer.table.schema().new_row();
+                row.set("key", key).unwrap();
+                row.set("val", key).unwrap();
+                let op = Operation {
+                    row,
+                    kind: OperationKind::Insert,
+                };
+                let encoded_len = OperationEncoder::encoded_len(&op.row);
+                writer.buffered_data += encoded_len;
+                writer.buffered_operations += 1;
+                writer.buffer_operation(proxied, &op, encoded_len);
+                Ok(())
+            })).unwrap();
+    }
+
+    #[test]
+    fn retried_write_applied_once() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "retried_write");
+
+        let tablet = runtime
+            .block_on(table.table_locations().tablet(&[]))
+            .unwrap()
+            .unwrap();
+
+        // Lose the response to the first attempt after the server has already applied it.
+        let mut lost = false;
+        let proxy = RpcProxy::spawn(tablet.replicas[0].rpc_addrs[0].clone(), move |_| {
+            if lost {
+                Action::Forward
+            } else {
+                lost = true;
+                Action::ForwardAndFail(RpcErrorCode::ErrorServerTooBusy)
+            }
+        });
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        buffer_insert_through(&mut runtime, &mut writer, &tablet, proxy.addr(), 1);
+
+        // The retry is answered with the retained response of the first attempt, rather than
+        // failing with a duplicate key error.
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(1, stats.successful_operations());
+        assert_eq!(0, stats.failed_operations());
+        assert_eq!(1, count_rows(&mut runtime, &table));
+        assert_eq!(2, proxy.requests().len());
+    }
+
+    #[test]
+    fn retries_exhausted_server_too_busy() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "server_too_busy");
+
+        let tablet = runtime
+            .block_on(table.table_locations().tablet(&[]))
+            .unwrap()
+            .unwrap();
+
+        // A tablet server which is always too busy to handle writes.
+        let proxy = RpcProxy::spawn(tablet.replicas[0].rpc_addrs[0].clone(), |_| {
+            Action::Fail(RpcErrorCode::ErrorServerTooBusy)
+        });
+
+        let mut writer = table.new_writer(WriterConfig::default().set_max_attempts(3));
+        buffer_insert_through(&mut runtime, &mut writer, &tablet, proxy.addr(), 1);
+
+        match runtime.block_on(future::poll_fn(|| writer.poll_flush())) {
+            Err(Error::RetriesExhausted {
+                attempts,
+                last_error,
+            }) => {
+                assert_eq!(3, attempts);
+                match *last_error {
+                    Error::Rpc(ref error) if error.code == RpcErrorCode::ErrorServerTooBusy => (),
+                    error => panic!("unexpected error: {}", error),
+                }
+            }
+            other => panic!("expected retries to be exhausted: {:?}", other),
+        }
+        assert_eq!(3, proxy.requests().len());
+        assert_eq!(0, count_rows(&mut runtime, &table));
+    }
+
+    #[test]
+    fn throttled_write_retried() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::default();
+        let mut runtime = Runtime::new().unwrap();
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+        let table = create_flush_mode_table(&mut runtime, &mut client, "throttled_write");
+
+        let tablet = runtime
+            .block_on(table.table_locations().tablet(&[]))
+            .unwrap()
+            .unwrap();
+
+        // Reject the first attempt as throttled, without applying it.
+        let mut throttled = false;
+        let proxy = RpcProxy::spawn(tablet.replicas[0].rpc_addrs[0].clone(), move |_| {
+            if throttled {
+                return Action::Forward;
+            }
+            throttled = true;
+            let response = WriteResponsePb {
+                error: Some(TabletServerErrorPb {
+                    code: TabletServerErrorCode::Throttled as i32,
+                    status: AppStatusPb {
+                        code: StatusCode::ServiceUnavailable as i32,
+                        message: Some("write throttled".to_owned()),
+                        ..Default::default()
+                    },
+                }),
+                ..Default::default()
+            };
+            let mut body = Vec::new();
+            response.encode(&mut body).unwrap();
+            Action::Respond(body)
+        });
+
+        let mut writer = table.new_writer(WriterConfig::default());
+        buffer_insert_through(&mut runtime, &mut writer, &tablet, proxy.addr(), 1);
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(1, stats.successful_operations());
+        assert_eq!(0, stats.failed_operations());
+        assert_eq!(1, count_rows(&mut runtime, &table));
+        assert_eq!(2, proxy.requests().len());
+    }
+
+    #[test]
+    fn insert_into_new_table() {
+        let _ = env_logger::try_init();
+        let mut cluster = MiniCluster::new(MiniClusterConfig::default().num_tservers(3));
+        let mut runtime = Runtime::new().unwrap();
+
+        let mut client = runtime
+            .block_on(Client::new(cluster.master_addrs(), Options::default()))
+            .expect("client");
+
+        let schema = SchemaBuilder::new()
+            .add_column(Column::new("key", DataType::Int32).set_not_null())
+            .add_column(Column::new("val", DataType::Int32))
+            .set_primary_key(vec!["key"])
+            .build()
+            .unwrap();
+
+        let mut table_builder = TableBuilder::new("insert_into_new_table", schema.clone());
+        table_builder.add_hash_partitions(vec!["key"], 8);
+        table_builder.set_num_replicas(3);
+
+        let table_id = runtime
+            .block_on(client.create_table(table_builder))
+            .unwrap();
+        let table = runtime.block_on(client.open_table_by_id(table_id)).unwrap();
+
+        // Write immediately, while the new tablets may still be bootstrapping or electing a
+        // leader. Such transient tablet errors are retried rather than failing the batches.
+        let mut writer = table.new_writer(WriterConfig::default());
+        let num_rows = 1000i32;
+        // TODO: remove lazy once apply no longer polls.
+        runtime
+            .block_on(future::lazy::<_, Result<(), ()>>(|| {
+                for i in 0..num_rows {
+                    let mut insert = table.schema().new_row();
+                    insert.set("key", i).unwrap();
+                    insert.set("val", i).unwrap();
+                    writer.insert(insert);
+                }
+                Ok(())
+            })).unwrap();
+
+        let stats = runtime
+            .block_on(future::poll_fn(|| writer.poll_flush()))
+            .unwrap();
+        assert_eq!(0, stats.failed_batches);
+        assert_eq!(num_rows as usize, stats.successful_operations());
+
+        let batches: Vec<RowBatch> = runtime
+            .block_on(future::lazy(|| table.scan_builder().build().collect()))
+            .unwrap();
+        assert_eq!(
+            num_rows as usize,
+            batches.iter().map(RowBatch::num_rows).sum::<usize>()
+        );
+    }
 }